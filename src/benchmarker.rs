@@ -1,35 +1,50 @@
 use crate::benchmarker::modes::CICD;
-use crate::config::{Framework, Named, Project, Test};
+use crate::config::{Named, Project, Test};
+use crate::docker::command_cache::{load_benchmark_commands, store_benchmark_commands};
 use crate::docker::container::{
-    block_until_database_is_ready, create_benchmarker_container, create_container,
-    create_database_verifier_container, create_verifier_container, get_port_bindings_for_container,
-    start_benchmark_command_retrieval_container, start_benchmarker_container, start_container,
-    start_verification_container, stop_docker_container_future,
+    benchmarker_command, block_until_database_is_ready, create_benchmark_probe,
+    create_benchmarker_container, create_container, create_database_verifier_container,
+    create_verifier_container, delete_benchmarker_container, get_application_port_bindings,
+    get_port_bindings_for_container, start_benchmark_command_retrieval_container,
+    start_benchmarker_container, start_container, start_verification_container,
+    stop_docker_container_future, verify_existing_container, wait_for_benchmark_probe,
+    wait_for_benchmarker_container, PortSelection,
 };
-use crate::docker::docker_config::DockerConfig;
-use crate::docker::image::{build_image, pull_image};
-use crate::docker::listener::benchmarker::BenchmarkResults;
+use crate::docker::docker_config::{BenchmarkerReplica, DockerConfig};
+use crate::docker::events::EventRecorder;
+use crate::docker::host_config::{ContainerHostSettings, RestartPolicy};
+use crate::docker::image::{build_image, build_verifier_image, pull_image, VERIFIER_IMAGE};
+use crate::docker::listener::benchmarker::{BenchmarkResults, LevelResult};
 use crate::docker::listener::simple::Simple;
 use crate::docker::listener::verifier::Error;
 use crate::docker::network::connect_container_to_network;
+use crate::docker::readiness::{is_ready, preflight};
+use crate::docker::slots::ContainerSlots;
+use crate::docker::wrk_command::benchmark_level;
 use crate::docker::{
-    BenchmarkCommands, DockerContainerIdFuture, DockerOrchestration, Verification,
+    BenchmarkCommands, ContainerSnapshot, DockerContainerIdFuture, DockerOrchestration,
+    Verification,
 };
 use crate::error::ToolsetError::{
-    AppServerContainerShutDownError, DebugFailedException, NoResponseFromDockerContainerError,
-    VerificationFailedException,
+    AppServerContainerShutDownError, ApplicationNeverReady, DebugFailedException, DockerError,
+    GpuRequestRejectedError, RunTimedOut,
 };
 use crate::error::{ToolsetError, ToolsetResult};
+use crate::hooks::RunHooks;
 use crate::io::{report_verifications, Logger};
-use crate::results::{BenchmarkData, Results};
+use crate::manifest::Manifest;
+use crate::progress::Progress;
+use crate::report::{RunReport, TestKey};
+use crate::results::Results;
+use crate::status::ContainerRole;
 use colored::Colorize;
-use curl::easy::Easy2;
 use dockurl::container::inspect_container;
-use std::collections::HashMap;
+use dockurl::error::DockerError::FailedToStartDockerContainerError;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::{thread, time};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 pub mod modes {
     pub const BENCHMARK: &str = "benchmark";
@@ -64,8 +79,12 @@ pub struct Benchmarker<'a> {
     application_container_id: Arc<Mutex<DockerContainerIdFuture>>,
     database_container_id: Arc<Mutex<DockerContainerIdFuture>>,
     verifier_container_id: Arc<Mutex<DockerContainerIdFuture>>,
-    benchmarker_container_id: Arc<Mutex<DockerContainerIdFuture>>,
+    benchmarker_container_ids: Vec<Arc<Mutex<DockerContainerIdFuture>>>,
     ctrlc_received: Arc<AtomicBool>,
+    run_timed_out: Arc<AtomicBool>,
+    previous_manifest: Manifest,
+    hooks: Vec<Box<dyn RunHooks>>,
+    container_slots: ContainerSlots,
 }
 
 impl<'a> Benchmarker<'a> {
@@ -79,27 +98,42 @@ impl<'a> Benchmarker<'a> {
         let verifier_container_id = Arc::new(Mutex::new(DockerContainerIdFuture::new(
             &docker_config.client_docker_host,
         )));
-        let benchmarker_container_id = Arc::new(Mutex::new(DockerContainerIdFuture::new(
-            &docker_config.client_docker_host,
-        )));
+        let benchmarker_container_ids = docker_config
+            .benchmarker_replicas
+            .iter()
+            .map(|replica| {
+                Arc::new(Mutex::new(DockerContainerIdFuture::new(
+                    &replica.docker_host,
+                )))
+            })
+            .collect();
 
+        let container_slots = ContainerSlots::new(docker_config.max_concurrent);
         let benchmarker = Self {
             docker_config,
             projects,
             application_container_id,
             database_container_id,
             verifier_container_id,
-            benchmarker_container_id,
+            benchmarker_container_ids,
             ctrlc_received: Arc::new(AtomicBool::new(false)),
+            run_timed_out: Arc::new(AtomicBool::new(false)),
+            previous_manifest: Manifest::default(),
+            hooks: Vec::default(),
+            container_slots,
         };
 
         if mode != CICD {
             let use_unix_socket = benchmarker.docker_config.use_unix_socket;
+            let docker_tls = benchmarker.docker_config.docker_tls.clone();
             let docker_cleanup = benchmarker.docker_config.clean_up;
+            let prune_images = benchmarker.docker_config.prune_images;
+            let stop_timeout = benchmarker.docker_config.stop_timeout;
+            let stop_grace_period = benchmarker.docker_config.stop_grace_period;
             let application_container_id = Arc::clone(&benchmarker.application_container_id);
             let database_container_id = Arc::clone(&benchmarker.database_container_id);
             let verifier_container_id = Arc::clone(&benchmarker.verifier_container_id);
-            let benchmarker_container_id = Arc::clone(&benchmarker.benchmarker_container_id);
+            let benchmarker_container_ids = benchmarker.benchmarker_container_ids.clone();
             let ctrlc_received = Arc::clone(&benchmarker.ctrlc_received);
             ctrlc::set_handler(move || {
                 let logger = Logger::default();
@@ -113,28 +147,47 @@ impl<'a> Benchmarker<'a> {
                     let application_container_id = Arc::clone(&application_container_id);
                     let database_container_id = Arc::clone(&database_container_id);
                     let verifier_container_id = Arc::clone(&verifier_container_id);
-                    let benchmarker_container_id = Arc::clone(&benchmarker_container_id);
+                    let benchmarker_container_ids = benchmarker_container_ids.clone();
                     let ctrlc_received = Arc::clone(&ctrlc_received);
+                    let docker_tls = docker_tls.clone();
                     thread::spawn(move || {
                         ctrlc_received.store(true, Ordering::Release);
                         stop_docker_container_future(
                             use_unix_socket,
+                            docker_tls.as_ref(),
                             docker_cleanup,
+                            prune_images,
+                            stop_timeout,
+                            stop_grace_period,
                             &verifier_container_id,
                         );
+                        for benchmarker_container_id in &benchmarker_container_ids {
+                            stop_docker_container_future(
+                                use_unix_socket,
+                                docker_tls.as_ref(),
+                                docker_cleanup,
+                                prune_images,
+                                stop_timeout,
+                                stop_grace_period,
+                                benchmarker_container_id,
+                            );
+                        }
                         stop_docker_container_future(
                             use_unix_socket,
+                            docker_tls.as_ref(),
                             docker_cleanup,
-                            &benchmarker_container_id,
-                        );
-                        stop_docker_container_future(
-                            use_unix_socket,
-                            docker_cleanup,
+                            prune_images,
+                            stop_timeout,
+                            stop_grace_period,
                             &application_container_id,
                         );
                         stop_docker_container_future(
                             use_unix_socket,
+                            docker_tls.as_ref(),
                             docker_cleanup,
+                            prune_images,
+                            stop_timeout,
+                            stop_grace_period,
                             &database_container_id,
                         );
                         std::process::exit(0);
@@ -147,51 +200,93 @@ impl<'a> Benchmarker<'a> {
         benchmarker
     }
 
+    /// Sets the `Manifest` of a previous run which this run is re-running the
+    /// failures of; the manifest written at the end of verification will be
+    /// the previous manifest updated with the results of this run.
+    pub fn set_previous_manifest(&mut self, manifest: Manifest) {
+        self.previous_manifest = manifest;
+    }
+
+    /// Adds `hooks` to be called at each point of the run's lifecycle, after
+    /// any hooks previously added.
+    pub fn add_hooks(&mut self, hooks: Box<dyn RunHooks>) {
+        self.hooks.push(hooks);
+    }
+
     /// Iterates over the specified test implementation(s), starts configured
     /// required services (like a database), starts the test implementation,
     /// verifies the configured end-points for each test type, and, if
     /// successful, will benchmark the running test implementation. When
     /// benchmarking completes, the results are parsed and stored in the
     /// results directory for this benchmark.
-    pub fn benchmark(&mut self) -> ToolsetResult<()> {
+    pub fn benchmark(&mut self) -> ToolsetResult<RunReport> {
+        let started = Instant::now();
+        let mut report = RunReport::default();
         let mut benchmark_results = Results::new(&self.docker_config)?;
         let logger = self.docker_config.logger.clone();
-        logger.log("Pulling verifier; this may take some time.")?;
-        // todo - how should we version this?
-        pull_image(
-            &self.docker_config,
-            &self.docker_config.client_docker_host,
-            "techempower/tfb.verifier",
-        )?;
+        let event_recorder = self.start_event_recorder();
+        let _watchdog = self.start_run_watchdog();
+        self.prepare_verifier_image(&logger)?;
         let projects = &self.projects.clone();
-        for project in projects {
-            for test in &project.tests {
+        let mut progress = Progress::new(self.count_test_types(projects));
+        'projects: for project in projects {
+            for (test, database) in &self.database_variants(&project.tests) {
                 let mut logger = logger.clone();
                 logger.set_test(test);
                 self.trip();
                 match self.start_test_orchestration(project, test, &logger) {
                     Ok(orchestration) => {
                         for test_type in &test.urls {
+                            // The watchdog has torn down the application.
+                            if self.run_timed_out.load(Ordering::Acquire) {
+                                break;
+                            }
                             logger.log(format!("Benchmarking: {}", test_type.0))?;
-                            match self.run_benchmarks(&orchestration, &test_type, &logger) {
-                                Ok(results) => self.report_benchmark_success(
-                                    &mut benchmark_results,
-                                    results,
-                                    &project.framework,
-                                    test_type.0,
-                                    &logger,
-                                ),
-                                Err(e) => self.report_benchmark_error(
-                                    &mut benchmark_results,
-                                    &test,
-                                    test_type.0,
-                                    &e,
-                                    &logger,
-                                ),
+                            match self.run_benchmarks(
+                                project,
+                                test,
+                                &orchestration,
+                                &test_type,
+                                &logger,
+                            ) {
+                                Ok(results) => {
+                                    let key = test_key(project, test, test_type.0, database);
+                                    self.run_hooks(|hooks| {
+                                        hooks.on_benchmark_complete(&key, &results)
+                                    });
+                                    report.add_benchmarks(
+                                        test_key(project, test, test_type.0, database),
+                                        results.clone(),
+                                    );
+                                    self.report_benchmark_success(
+                                        &mut benchmark_results,
+                                        results,
+                                        &test,
+                                        test_type.0,
+                                        database,
+                                        &logger,
+                                    )
+                                }
+                                Err(e) => {
+                                    report.add_error(
+                                        test_key(project, test, test_type.0, database),
+                                        &e,
+                                    );
+                                    self.report_benchmark_error(
+                                        &mut benchmark_results,
+                                        &test,
+                                        test_type.0,
+                                        database,
+                                        &e,
+                                        &logger,
+                                    )
+                                }
                             }
 
                             logger.write_results(&benchmark_results)?;
                             logger.log(format!("Completed benchmarking: {}", test_type.0))?;
+                            progress.complete(1);
+                            self.docker_config.logger.log(&progress)?;
                         }
                     }
                     Err(e) => {
@@ -200,23 +295,41 @@ impl<'a> Benchmarker<'a> {
                         // container(s); all of its test implementations must
                         // fail.
                         for test_type in &test.urls {
+                            report.add_error(test_key(project, test, test_type.0, database), &e);
                             self.report_benchmark_error(
                                 &mut benchmark_results,
                                 &test,
                                 test_type.0,
+                                database,
                                 &e,
                                 &logger,
                             );
                         }
+                        progress.complete(test.urls.len());
+                        self.docker_config.logger.log(&progress)?;
                     }
                 }
 
                 self.trip();
                 self.stop_containers();
+                if self.run_timed_out.load(Ordering::Acquire) {
+                    break 'projects;
+                }
             }
         }
 
-        Ok(())
+        self.stop_event_recorder(event_recorder, &logger)?;
+        self.check_run_timeout()?;
+        report.duration = started.elapsed();
+        if let Some(path) = self.docker_config.export_tfb_results {
+            benchmark_results.completion_time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis();
+            benchmark_results.export(path)?;
+        }
+
+        Ok(report)
     }
 
     /// Starts the given test implementation as a running server and waits
@@ -226,6 +339,7 @@ impl<'a> Benchmarker<'a> {
         // Because it makes no sense to loop over all the specified tests when
         // the first test found will cause the main thread to sleep forever, we
         // just check *that* there is a test to run and start it.
+        let _watchdog = self.start_run_watchdog();
         let projects = self.projects.clone();
         if let Some(project) = projects.get(0) {
             if let Some(test) = project.tests.get(0) {
@@ -241,6 +355,7 @@ impl<'a> Benchmarker<'a> {
                         )?;
                         loop {
                             thread::sleep(Duration::from_secs(1));
+                            self.check_run_timeout()?;
                         }
                     }
                     Err(e) => {
@@ -256,93 +371,148 @@ impl<'a> Benchmarker<'a> {
     }
 
     /// Attempts to run the suite of verifications against the specified
-    /// test implementation(s).
-    pub fn verify(&mut self) -> ToolsetResult<()> {
-        let mut succeeded = true;
+    /// test implementation(s). Failed verifications are not an error; they
+    /// are reported in the returned `RunReport`.
+    pub fn verify(&mut self) -> ToolsetResult<RunReport> {
+        let started = Instant::now();
         let mut verifications = Vec::new();
         let projects = &self.projects.clone();
-        if projects.is_empty() {
-            succeeded = false;
-        } else {
+        if !projects.is_empty() {
             let logger = self.docker_config.logger.clone();
-            logger.log("Pulling verifier; this may take some time.")?;
-            // todo - how should we version this?
-            pull_image(
-                &self.docker_config,
-                &self.docker_config.client_docker_host,
-                "techempower/tfb.verifier",
-            )?;
-            for project in projects {
-                for test in &project.tests {
+            self.prepare_verifier_image(&logger)?;
+            let event_recorder = self.start_event_recorder();
+            let _watchdog = self.start_run_watchdog();
+            let mut progress = Progress::new(self.count_test_types(projects));
+            'projects: for project in projects {
+                for (test, database) in &self.database_variants(&project.tests) {
                     let mut logger = logger.clone();
                     logger.set_test(test);
                     self.trip();
                     match self.start_test_orchestration(project, test, &logger) {
                         Ok(orchestration) => {
                             for test_type in &test.urls {
+                                // The watchdog has torn down the application.
+                                if self.run_timed_out.load(Ordering::Acquire) {
+                                    break;
+                                }
                                 self.trip();
-                                match self.run_verification(
+                                let verification = match self.run_verification(
                                     &project,
                                     &test,
                                     &orchestration,
                                     &test_type,
                                     &logger,
                                 ) {
-                                    Ok(verification) => {
-                                        succeeded &= verification.errors.is_empty();
-                                        verifications.push(verification);
+                                    Ok(mut verification) => {
+                                        verification.database = database.clone();
+                                        verification
                                     }
                                     Err(e) => {
-                                        verifications.push(Verification {
+                                        self.trip();
+                                        self.stop_containers();
+                                        Verification {
                                             framework_name: project.framework.get_name(),
                                             test_name: test.get_name(),
-                                            type_name: String::default(),
+                                            type_name: test_type.0.clone(),
+                                            database: database.clone(),
                                             warnings: Vec::default(),
                                             errors: vec![Error {
                                                 message: format!("{:?}", e),
                                                 short_message: "Failed to Verify".to_string(),
                                             }],
-                                        });
-                                        succeeded = false;
-                                        self.trip();
-                                        self.stop_containers();
+                                            logs: None,
+                                        }
                                     }
-                                }
+                                };
+                                self.run_hooks(|hooks| hooks.on_verified(&verification));
+                                verifications.push(verification);
+                                progress.complete(1);
+                                self.docker_config.logger.log(&progress)?;
                             }
                         }
                         Err(e) => {
                             logger.error(&e)?;
-                            verifications.push(Verification {
+                            let verification = Verification {
                                 framework_name: project.framework.get_name(),
                                 test_name: test.get_name(),
                                 type_name: String::default(),
+                                database: database.clone(),
                                 warnings: Vec::default(),
                                 errors: vec![Error {
                                     message: format!("{:?}", e),
                                     short_message: "Failed to Start".to_string(),
                                 }],
-                            });
-                            succeeded = false;
+                                logs: None,
+                            };
+                            self.run_hooks(|hooks| hooks.on_verified(&verification));
+                            verifications.push(verification);
                             self.trip();
                             self.stop_containers();
+                            progress.complete(test.urls.len());
+                            self.docker_config.logger.log(&progress)?;
                         }
                     };
 
                     self.trip();
                     self.stop_containers();
+                    if self.run_timed_out.load(Ordering::Acquire) {
+                        break 'projects;
+                    }
                 }
             }
 
             self.trip();
             self.stop_containers();
-            report_verifications(verifications, logger)?;
+            self.stop_event_recorder(event_recorder, &logger)?;
+            let mut manifest = self.previous_manifest.clone();
+            manifest.merge(Manifest::from_verifications(&verifications));
+            logger.write_manifest(&manifest)?;
+            report_verifications(verifications.clone(), logger)?;
         }
 
-        if succeeded {
-            Ok(())
-        } else {
-            Err(VerificationFailedException)
+        self.check_run_timeout()?;
+        let mut report = RunReport::default();
+        for verification in verifications {
+            report.add_verification(verification);
         }
+        report.duration = started.elapsed();
+
+        Ok(report)
+    }
+
+    /// Runs the suite of verifications for the first specified test
+    /// implementation against the already running application container
+    /// given by `container_id`, which is left running.
+    pub fn verify_existing(&mut self, container_id: &str) -> ToolsetResult<RunReport> {
+        let started = Instant::now();
+        let mut report = RunReport::default();
+        let projects = self.projects.clone();
+        if let Some(project) = projects.first() {
+            if let Some(test) = project.tests.first() {
+                let mut logger = self.docker_config.logger.clone();
+                logger.set_test(test);
+                self.prepare_verifier_image(&logger)?;
+                let mut verifications = Vec::new();
+                for test_type in &test.urls {
+                    self.trip();
+                    let verification = verify_existing_container(
+                        &self.docker_config,
+                        project,
+                        test,
+                        &test_type,
+                        container_id,
+                        &logger,
+                    )?;
+                    self.run_hooks(|hooks| hooks.on_verified(&verification));
+                    report.add_verification(verification.clone());
+                    verifications.push(verification);
+                }
+                report_verifications(verifications, logger)?;
+            }
+        }
+        report.duration = started.elapsed();
+
+        Ok(report)
     }
 }
 
@@ -350,9 +520,169 @@ impl<'a> Benchmarker<'a> {
 // PRIVATES
 //
 impl<'a> Benchmarker<'a> {
+    /// Gets the given `tests` to run, each paired with the database it is run
+    /// against when sweeping databases. A test which uses a database is run
+    /// once against each of the configured databases; otherwise, each test is
+    /// run once as configured.
+    fn database_variants(&self, tests: &[Test]) -> Vec<(Test, Option<String>)> {
+        let mut variants = Vec::new();
+        for test in tests {
+            if test.database.is_none() || self.docker_config.databases.is_empty() {
+                variants.push((test.clone(), None));
+            } else {
+                for database in &self.docker_config.databases {
+                    let mut variant = test.clone();
+                    variant.database = Some(database.clone());
+                    variants.push((variant, Some(database.clone())));
+                }
+            }
+        }
+
+        variants
+    }
+
+    /// Counts the test types across all the tests (and the databases they are
+    /// run against) of the given `projects`; i.e. the units of work in a run.
+    fn count_test_types(&self, projects: &[Project]) -> usize {
+        projects
+            .iter()
+            .map(|project| {
+                self.database_variants(&project.tests)
+                    .iter()
+                    .map(|(test, _)| test.urls.len())
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// Builds the verifier image from the configured build context, if any;
+    /// otherwise, pulls it.
+    fn prepare_verifier_image(&self, logger: &Logger) -> ToolsetResult<()> {
+        match self.docker_config.verifier_build_context {
+            Some(context) => {
+                logger.log(format!("Building verifier from {}.", context))?;
+                build_verifier_image(
+                    &self.docker_config,
+                    context,
+                    &self.docker_config.client_docker_host,
+                    logger,
+                )?;
+            }
+            None => {
+                logger.log("Pulling verifier; this may take some time.")?;
+                // todo - how should we version this?
+                pull_image(
+                    &self.docker_config,
+                    &self.docker_config.client_docker_host,
+                    VERIFIER_IMAGE,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starts recording Docker events on every configured Docker host, if
+    /// configured to do so.
+    fn start_event_recorder(&self) -> Option<EventRecorder> {
+        if self.docker_config.record_docker_events {
+            Some(EventRecorder::start(
+                self.docker_config.use_unix_socket,
+                &[
+                    &self.docker_config.server_docker_host,
+                    &self.docker_config.database_docker_host,
+                    &self.docker_config.client_docker_host,
+                ],
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Stops the given `event_recorder`, if any, and writes the recorded
+    /// events to the results directory.
+    fn stop_event_recorder(
+        &self,
+        event_recorder: Option<EventRecorder>,
+        logger: &Logger,
+    ) -> ToolsetResult<()> {
+        if let Some(event_recorder) = event_recorder {
+            logger.write_docker_events(&event_recorder.stop())?;
+        }
+
+        Ok(())
+    }
+
+    /// Starts a watchdog which, once the configured run timeout elapses, tears
+    /// down every registered container and marks the run as timed out. The
+    /// watchdog exits without doing anything once the returned `Sender` is
+    /// dropped.
+    fn start_run_watchdog(&self) -> Option<Sender<()>> {
+        let run_timeout = self.docker_config.run_timeout?;
+        let (sender, receiver) = channel::<()>();
+        let use_unix_socket = self.docker_config.use_unix_socket;
+        let docker_tls = self.docker_config.docker_tls.clone();
+        let docker_cleanup = self.docker_config.clean_up;
+        let prune_images = self.docker_config.prune_images;
+        let stop_timeout = self.docker_config.stop_timeout;
+        let stop_grace_period = self.docker_config.stop_grace_period;
+        let container_ids = self.registered_containers();
+        let run_timed_out = Arc::clone(&self.run_timed_out);
+        thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(run_timeout) {
+                run_timed_out.store(true, Ordering::Release);
+                Logger::default()
+                    .log(
+                        format!(
+                            "Run exceeded its timeout of {}s; tearing down all containers",
+                            run_timeout.as_secs()
+                        )
+                        .yellow(),
+                    )
+                    .unwrap_or(());
+                for container_id in &container_ids {
+                    stop_docker_container_future(
+                        use_unix_socket,
+                        docker_tls.as_ref(),
+                        docker_cleanup,
+                        prune_images,
+                        stop_timeout,
+                        stop_grace_period,
+                        container_id,
+                    );
+                }
+            }
+        });
+
+        Some(sender)
+    }
+
+    /// Returns `RunTimedOut` if the run timeout has elapsed.
+    fn check_run_timeout(&self) -> ToolsetResult<()> {
+        match self.docker_config.run_timeout {
+            Some(run_timeout) if self.run_timed_out.load(Ordering::Acquire) => {
+                Err(RunTimedOut(run_timeout))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Gets every container registered with this `Benchmarker` in the order in
+    /// which they should be stopped.
+    fn registered_containers(&self) -> Vec<Arc<Mutex<DockerContainerIdFuture>>> {
+        let mut container_ids = vec![Arc::clone(&self.verifier_container_id)];
+        container_ids.extend(self.benchmarker_container_ids.iter().map(Arc::clone));
+        container_ids.push(Arc::clone(&self.application_container_id));
+        container_ids.push(Arc::clone(&self.database_container_id));
+
+        container_ids
+    }
+
     /// Runs the benchmarks for a given `DockerOrchestration` and `test_type`.
     fn run_benchmarks(
         &mut self,
+        project: &Project,
+        test: &Test,
         orchestration: &DockerOrchestration,
         test_type: &(&String, &String),
         logger: &Logger,
@@ -361,7 +691,11 @@ impl<'a> Benchmarker<'a> {
         let mut logger = logger.clone();
         logger.set_log_file(&format!("{}.txt", test_type.0));
         logger.quiet = true;
-        let benchmark_commands = self.run_command_retrieval(&orchestration, &test_type, &logger)?;
+        let benchmark_commands =
+            self.get_benchmark_commands(project, test, orchestration, test_type, &logger)?;
+        let benchmark_commands = self.docker_config.wrk_overrides.apply(benchmark_commands);
+        let benchmark_commands = orchestration.commands_for(test_type.0, benchmark_commands);
+        let port = orchestration.internal_port_for(test_type.0)?;
 
         logger.log("---------------------------------------------------------")?;
         logger.log(" Running Primer")?;
@@ -370,7 +704,13 @@ impl<'a> Benchmarker<'a> {
             &benchmark_commands.primer_command.join(" ")
         ))?;
         logger.log("---------------------------------------------------------")?;
-        self.run_benchmark(&benchmark_commands.primer_command, &logger)?;
+        self.run_benchmark(
+            test,
+            test_type.0,
+            &port,
+            &benchmark_commands.primer_command,
+            &logger,
+        )?;
 
         logger.log("---------------------------------------------------------")?;
         logger.log(" Running Warmup")?;
@@ -379,103 +719,208 @@ impl<'a> Benchmarker<'a> {
             &benchmark_commands.warmup_command.join(" ")
         ))?;
         logger.log("---------------------------------------------------------")?;
-        self.run_benchmark(&benchmark_commands.warmup_command, &logger)?;
+        self.run_benchmark(
+            test,
+            test_type.0,
+            &port,
+            &benchmark_commands.warmup_command,
+            &logger,
+        )?;
 
         for command in &benchmark_commands.benchmark_commands {
             logger.log("---------------------------------------------------------")?;
             logger.log(format!(" {}", command.join(" ")))?;
             logger.log("---------------------------------------------------------")?;
 
-            results.push(self.run_benchmark(command, &logger)?);
+            let mut result = self.run_benchmark(test, test_type.0, &port, command, &logger)?;
+            if let Some(level) = benchmark_level(test_type.0, command) {
+                result.levels.insert(level, LevelResult::from(&result));
+            }
+            if let Some(max_ratio) = self.docker_config.max_socket_error_ratio {
+                if let Err(error) = result.check_socket_errors(max_ratio) {
+                    return Err(error.with_logs(result.logs));
+                }
+            }
+            results.push(result);
         }
 
         Ok(results)
     }
 
-    /// Runs the benchmarker container against the given `DockerOrchestration`.
+    /// Runs a benchmarker container on each benchmarker replica concurrently
+    /// with the given `command` for `test_type` of `test` and merges their
+    /// results. When configured, first confirms that each replica can reach
+    /// the application on its internal `port`.
     fn run_benchmark(
         &mut self,
+        test: &Test,
+        test_type: &str,
+        port: &str,
         command: &[String],
         logger: &Logger,
     ) -> ToolsetResult<BenchmarkResults> {
-        let container_id = create_benchmarker_container(&self.docker_config, command)?;
+        if self.docker_config.benchmark_preflight {
+            let server_host = self.docker_config.server_host_for(test_type).to_string();
+            let retries = self.docker_config.preflight_retries;
+            let retry_interval = self.docker_config.preflight_retry_interval;
+            for replica in &self.docker_config.benchmarker_replicas.clone() {
+                preflight(&server_host, port, retries, retry_interval, || {
+                    self.probe_benchmark_target(test, replica, test_type, port)
+                })?;
+            }
+        }
 
-        connect_container_to_network(
-            &self.docker_config,
-            &self.docker_config.client_docker_host,
-            &self.docker_config.client_network_id,
-            &container_id,
-        )?;
+        let command = benchmarker_command(&self.docker_config, test_type, command)?;
+        let mut container_ids = Vec::new();
+        for (replica, benchmarker_container_id) in self
+            .docker_config
+            .benchmarker_replicas
+            .clone()
+            .iter()
+            .zip(self.benchmarker_container_ids.clone())
+        {
+            let container_id =
+                create_benchmarker_container(&self.docker_config, replica, test_type, &command)?;
+            self.run_hooks(|hooks| {
+                hooks.on_container_created(
+                    ContainerRole::Benchmarker,
+                    &test.get_name(),
+                    &container_id,
+                )
+            });
+
+            connect_container_to_network(
+                &self.docker_config,
+                &replica.docker_host,
+                &replica.network_id,
+                &container_id,
+            )?;
 
-        if let Ok(mut benchmarker) = self.benchmarker_container_id.lock() {
-            benchmarker.register(&container_id);
+            if let Ok(mut benchmarker) = benchmarker_container_id.lock() {
+                benchmarker.register(&container_id);
+            }
+            container_ids.push(container_id);
         }
 
         self.trip();
-        let benchmark_results =
-            start_benchmarker_container(&self.docker_config, &container_id, logger)?;
-
-        // This signals that the benchmarker exited naturally on
-        // its own, so we don't need to stop its container.
-        if let Ok(mut benchmarker) = self.benchmarker_container_id.lock() {
-            benchmarker.unregister();
+        for (replica, container_id) in self
+            .docker_config
+            .benchmarker_replicas
+            .clone()
+            .iter()
+            .zip(&container_ids)
+        {
+            start_benchmarker_container(&self.docker_config, replica, container_id)?;
+            self.run_hooks(|hooks| {
+                hooks.on_container_started(
+                    ContainerRole::Benchmarker,
+                    &test.get_name(),
+                    container_id,
+                )
+            });
+        }
+        let docker_config = &self.docker_config;
+        let benchmark_results: Vec<ToolsetResult<BenchmarkResults>> = thread::scope(|scope| {
+            let handles: Vec<_> = docker_config
+                .benchmarker_replicas
+                .iter()
+                .zip(&container_ids)
+                .map(|(replica, container_id)| {
+                    scope.spawn(move || {
+                        wait_for_benchmarker_container(docker_config, replica, container_id, logger)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        // This signals that the benchmarkers exited naturally on
+        // their own, so we don't need to stop their containers.
+        for benchmarker_container_id in &self.benchmarker_container_ids {
+            if let Ok(mut benchmarker) = benchmarker_container_id.lock() {
+                benchmarker.unregister();
+            }
+        }
+        if self.docker_config.clean_up {
+            for (replica, container_id) in self
+                .docker_config
+                .benchmarker_replicas
+                .clone()
+                .iter()
+                .zip(&container_ids)
+            {
+                self.run_hooks(|hooks| hooks.on_teardown(container_id));
+                delete_benchmarker_container(&self.docker_config, replica, container_id)?;
+                self.run_hooks(|hooks| hooks.on_torn_down(container_id));
+            }
         }
 
-        Ok(benchmark_results)
+        let benchmark_results = benchmark_results
+            .into_iter()
+            .collect::<ToolsetResult<Vec<BenchmarkResults>>>()?;
+        let mut results = BenchmarkResults::merge(benchmark_results)?;
+        results.executed_command = command;
+
+        Ok(results)
+    }
+
+    /// Runs a probe on the given `replica` which connects to the application
+    /// for `test_type` of `test` on `port`, returning whether the connection
+    /// succeeded. The probe is removed regardless of `clean_up`; it has no
+    /// results.
+    fn probe_benchmark_target(
+        &mut self,
+        test: &Test,
+        replica: &BenchmarkerReplica,
+        test_type: &str,
+        port: &str,
+    ) -> ToolsetResult<bool> {
+        let container_id = create_benchmark_probe(&self.docker_config, replica, test_type, port)?;
+        self.run_hooks(|hooks| {
+            hooks.on_container_created(ContainerRole::Benchmarker, &test.get_name(), &container_id)
+        });
+        let connected = match connect_container_to_network(
+            &self.docker_config,
+            &replica.docker_host,
+            &replica.network_id,
+            &container_id,
+        )
+        .and_then(|_| start_benchmarker_container(&self.docker_config, replica, &container_id))
+        {
+            Ok(()) => {
+                self.run_hooks(|hooks| {
+                    hooks.on_container_started(
+                        ContainerRole::Benchmarker,
+                        &test.get_name(),
+                        &container_id,
+                    )
+                });
+                wait_for_benchmark_probe(&self.docker_config, replica, &container_id)
+            }
+            Err(error) => Err(error),
+        };
+        self.run_hooks(|hooks| hooks.on_teardown(&container_id));
+        delete_benchmarker_container(&self.docker_config, replica, &container_id)?;
+        self.run_hooks(|hooks| hooks.on_torn_down(&container_id));
+
+        connected
     }
 
-    /// Reports the successful benchmark of a given `framework` / `test_type`
+    /// Reports the successful benchmark of a given `test` / `test_type`
     /// via `results.json` output.
     fn report_benchmark_success(
         &self,
         benchmark_results: &mut Results,
         results: Vec<BenchmarkResults>,
-        framework: &Framework,
+        test: &Test,
         test_type: &str,
+        database: &Option<String>,
         _logger: &Logger,
     ) {
-        for result in results {
-            if benchmark_results.raw_data.get(test_type).is_none() {
-                benchmark_results
-                    .raw_data
-                    .insert(test_type.to_string(), HashMap::default());
-            }
-            if let Some(test_type) = benchmark_results.raw_data.get_mut(test_type) {
-                if test_type
-                    .get(&framework.get_name().to_lowercase())
-                    .is_none()
-                {
-                    test_type.insert(framework.get_name().to_lowercase(), Vec::default());
-                }
-
-                if let Some(results) = test_type.get_mut(&framework.get_name().to_lowercase()) {
-                    results.push(BenchmarkData {
-                        latency_avg: result.thread_stats.latency.average,
-                        latency_max: result.thread_stats.latency.max,
-                        latency_stdev: result.thread_stats.latency.standard_deviation,
-                        total_requests: result.total_requests,
-                        start_time: result.start_time,
-                        end_time: result.end_time,
-                    });
-                }
-            }
-        }
-        if benchmark_results.succeeded.get(test_type).is_none() {
-            benchmark_results
-                .succeeded
-                .insert(test_type.to_string(), Vec::default());
-        }
-        if let Some(test_type) = benchmark_results.succeeded.get_mut(test_type) {
-            test_type.push(framework.get_name().to_lowercase());
-        }
-        benchmark_results.completed.insert(
-            framework.get_name().to_lowercase(),
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis()
-                .to_string(),
-        );
+        benchmark_results.add_success(&test.get_name(), test_type, &results, database);
     }
 
     /// Reports the unsuccessful benchmark of a given `test` / `test_type` via
@@ -485,17 +930,11 @@ impl<'a> Benchmarker<'a> {
         benchmark_results: &mut Results,
         test: &Test,
         test_type: &str,
+        database: &Option<String>,
         _error: &ToolsetError,
         _logger: &Logger,
     ) {
-        if benchmark_results.failed.get(test_type).is_none() {
-            benchmark_results
-                .failed
-                .insert(test_type.to_string(), Vec::default());
-        }
-        if let Some(test_type) = benchmark_results.failed.get_mut(test_type) {
-            test_type.push(test.get_name());
-        }
+        benchmark_results.add_failure(&test.get_name(), test_type, database);
     }
 
     /// Runs the verifier against the given test orchestration and returns the
@@ -539,10 +978,64 @@ impl<'a> Benchmarker<'a> {
         if let Ok(mut verifier) = self.verifier_container_id.lock() {
             verifier.unregister();
         }
+        if self.docker_config.keep_failed && !verification.errors.is_empty() {
+            if let Ok(mut application) = self.application_container_id.lock() {
+                application.keep();
+            }
+        }
 
         Ok(verification)
     }
 
+    /// Gets the `BenchmarkCommands` for the given `test_type`, replaying them
+    /// from the command cache when configured to do so, and otherwise
+    /// retrieving them from the verifier and updating the cache.
+    fn get_benchmark_commands(
+        &mut self,
+        project: &Project,
+        test: &Test,
+        orchestration: &DockerOrchestration,
+        test_type: &(&String, &String),
+        logger: &Logger,
+    ) -> ToolsetResult<BenchmarkCommands> {
+        let framework_name = project.framework.get_name();
+        let test_name = test.get_name();
+        if self.docker_config.use_command_cache && !self.docker_config.refresh_command_cache {
+            if let Some(commands) = load_benchmark_commands(
+                &self.docker_config,
+                &framework_name,
+                &test_name,
+                test_type.0,
+            )? {
+                logger.log(format!(
+                    "Replaying cached benchmark commands for {}",
+                    test_type.0
+                ))?;
+                return Ok(commands);
+            }
+        }
+
+        let commands = self.run_command_retrieval(orchestration, test_type, logger)?;
+        if self.docker_config.use_command_cache || self.docker_config.refresh_command_cache {
+            // A cache which cannot be written only costs the next run a
+            // retrieval; it is no reason to abandon this one.
+            if let Err(error) = store_benchmark_commands(
+                &self.docker_config,
+                &framework_name,
+                &test_name,
+                test_type.0,
+                &commands,
+            ) {
+                logger.error(format!(
+                    "Could not cache the benchmark commands for {}: {:?}",
+                    test_type.0, error
+                ))?;
+            }
+        }
+
+        Ok(commands)
+    }
+
     /// Requests the verifier to start for the purposes of retrieving the run
     /// commands for the purposes of benchmarking.
     /// In practice, this will retrieve, for some test type, a `wrk` command to
@@ -600,13 +1093,19 @@ impl<'a> Benchmarker<'a> {
         test: &Test,
         logger: &Logger,
     ) -> ToolsetResult<DockerOrchestration> {
-        let database_container_id = self.start_database_if_necessary(test)?;
+        // The group's slot is released when its application is torn down.
+        let slot = self.container_slots.acquire();
+        if let Ok(mut application_container_id) = self.application_container_id.lock() {
+            application_container_id.hold(slot);
+        }
+        let database_container_id = self.start_database_if_necessary(test, logger)?;
         let mut database_ports = (None, None);
         if let Some(container_id) = &database_container_id {
             let ports = get_port_bindings_for_container(
                 &self.docker_config,
                 &self.docker_config.database_docker_host,
                 container_id,
+                PortSelection::Any,
             )?;
             database_ports = (Some(ports.0), Some(ports.1));
         }
@@ -622,9 +1121,27 @@ impl<'a> Benchmarker<'a> {
             &image_id,
             &self.docker_config.server_network_id,
             &self.docker_config.server_host,
+            self.docker_config
+                .server_domain
+                .unwrap_or(self.docker_config.server_host),
             &self.docker_config.server_docker_host,
+            &ContainerHostSettings {
+                database_host: self.docker_config.database_host_for_test(test)?.to_string(),
+                restart_policy: self.docker_config.restart_policy.clone(),
+                volumes: Vec::default(),
+                gpus: self.docker_config.gpus.clone(),
+                cap_add: self.docker_config.cap_add.clone(),
+                cap_drop: self.docker_config.cap_drop.clone(),
+                security_opt: self.docker_config.security_opt.clone(),
+                memory_limit_bytes: self.docker_config.memory_limit_bytes,
+                memory_reservation_bytes: self.docker_config.memory_reservation_bytes,
+                cpu_shares: self.docker_config.cpu_shares,
+            },
         )?;
 
+        self.run_hooks(|hooks| {
+            hooks.on_container_created(ContainerRole::Application, &test.get_name(), &container_id)
+        });
         let container_ids = (container_id.clone(), database_container_id);
 
         connect_container_to_network(
@@ -639,20 +1156,33 @@ impl<'a> Benchmarker<'a> {
         }
 
         self.trip();
-        start_container(
+        match start_container(
             &self.docker_config,
             &container_id,
             &self.docker_config.server_docker_host,
             logger,
-        )?;
+        ) {
+            // The daemon only rejects a GPU request once the container starts.
+            Err(DockerError(FailedToStartDockerContainerError(message, _)))
+                if self.docker_config.gpus.is_some() =>
+            {
+                return Err(GpuRequestRejectedError(message));
+            }
+            result => result?,
+        }
+        self.run_hooks(|hooks| {
+            hooks.on_container_started(ContainerRole::Application, &test.get_name(), &container_id)
+        });
 
-        let host_ports = get_port_bindings_for_container(
-            &self.docker_config,
-            &self.docker_config.server_docker_host,
-            &container_id,
-        )?;
+        let (host_ports, tls_ports) =
+            get_application_port_bindings(&self.docker_config, test, &container_id)?;
 
-        self.wait_until_accepting_requests(&container_ids, &host_ports.0, test)?;
+        self.wait_until_accepting_requests(
+            &container_ids,
+            &host_ports.0,
+            tls_ports.as_ref().map(|(host_port, _)| host_port.as_str()),
+            test,
+        )?;
 
         Ok(DockerOrchestration {
             host_container_id: container_ids.0,
@@ -662,6 +1192,9 @@ impl<'a> Benchmarker<'a> {
             db_container_id: container_ids.1,
             db_host_port: database_ports.0,
             db_internal_port: database_ports.1,
+            tls_test_types: test.get_tls_test_types(),
+            tls_internal_port: tls_ports.map(|(_, internal_port)| internal_port),
+            tls_port: test.get_tls_port(),
         })
     }
 
@@ -688,31 +1221,44 @@ impl<'a> Benchmarker<'a> {
     /// Convenience method for stopping all running containers and popping them
     /// off the running containers vec.
     fn stop_containers(&mut self) {
-        stop_docker_container_future(
-            self.docker_config.use_unix_socket,
-            self.docker_config.clean_up,
-            &self.verifier_container_id,
-        );
-        stop_docker_container_future(
-            self.docker_config.use_unix_socket,
-            self.docker_config.clean_up,
-            &self.benchmarker_container_id,
-        );
-        stop_docker_container_future(
-            self.docker_config.use_unix_socket,
-            self.docker_config.clean_up,
-            &self.application_container_id,
-        );
-        stop_docker_container_future(
-            self.docker_config.use_unix_socket,
-            self.docker_config.clean_up,
-            &self.database_container_id,
-        );
+        for container_id in &self.registered_containers() {
+            let registered_id = match container_id.lock() {
+                Ok(container_id) => container_id.registered_id(),
+                Err(_) => None,
+            };
+            if let Some(container_id) = &registered_id {
+                self.run_hooks(|hooks| hooks.on_teardown(container_id));
+            }
+            stop_docker_container_future(
+                self.docker_config.use_unix_socket,
+                self.docker_config.docker_tls.as_ref(),
+                self.docker_config.clean_up,
+                self.docker_config.prune_images,
+                self.docker_config.stop_timeout,
+                self.docker_config.stop_grace_period,
+                container_id,
+            );
+            if let Some(container_id) = &registered_id {
+                self.run_hooks(|hooks| hooks.on_torn_down(container_id));
+            }
+        }
+    }
+
+    /// Calls `hook` on each of the `RunHooks` added, in order.
+    fn run_hooks<F: FnMut(&mut dyn RunHooks)>(&mut self, mut hook: F) {
+        for hooks in &mut self.hooks {
+            hook(hooks.as_mut());
+        }
     }
 
     /// Starts the database for the given `Test` if one is specified as being
-    /// required by the underlying configuration file.
-    fn start_database_if_necessary(&mut self, test: &Test) -> ToolsetResult<Option<String>> {
+    /// required by the underlying configuration file. Readiness is reported
+    /// to the given test `logger`.
+    fn start_database_if_necessary(
+        &mut self,
+        test: &Test,
+        test_logger: &Logger,
+    ) -> ToolsetResult<Option<String>> {
         if let Some(database) = &test.database {
             let mut logger = Logger::with_prefix(&database);
             let image_name = format!("techempower/tfb.database.{}", database.to_lowercase());
@@ -728,9 +1274,25 @@ impl<'a> Benchmarker<'a> {
                 &image_name,
                 &self.docker_config.database_network_id,
                 &self.docker_config.database_host,
+                self.docker_config.database_host,
                 &self.docker_config.database_docker_host,
+                &ContainerHostSettings {
+                    database_host: self.docker_config.database_host.to_string(),
+                    restart_policy: RestartPolicy::No,
+                    volumes: self.docker_config.volumes.clone(),
+                    gpus: None,
+                    cap_add: Vec::default(),
+                    cap_drop: Vec::default(),
+                    security_opt: Vec::default(),
+                    memory_limit_bytes: None,
+                    memory_reservation_bytes: None,
+                    cpu_shares: None,
+                },
             )?;
 
+            self.run_hooks(|hooks| {
+                hooks.on_container_created(ContainerRole::Database, &test.get_name(), &container_id)
+            });
             connect_container_to_network(
                 &self.docker_config,
                 &self.docker_config.database_docker_host,
@@ -751,6 +1313,9 @@ impl<'a> Benchmarker<'a> {
                 &self.docker_config.database_docker_host,
                 &logger,
             )?;
+            self.run_hooks(|hooks| {
+                hooks.on_container_started(ContainerRole::Database, &test.get_name(), &container_id)
+            });
 
             // Block until the database is accepting requests.
             self.trip();
@@ -771,7 +1336,12 @@ impl<'a> Benchmarker<'a> {
             }
             self.trip();
 
-            block_until_database_is_ready(&self.docker_config, &verifier_container_id)?;
+            block_until_database_is_ready(
+                &self.docker_config,
+                &verifier_container_id,
+                database,
+                test_logger,
+            )?;
 
             // This signals that the verifier exited naturally on
             // its own, so we don't need to stop its container.
@@ -785,15 +1355,31 @@ impl<'a> Benchmarker<'a> {
         Ok(None)
     }
 
-    /// Blocks the current thread until either the operation times out or `Test`
-    /// responds successfully (200).
+    /// Blocks the current thread until either the configured readiness timeout
+    /// elapses or the `Test` passes the configured readiness probe. The probe
+    /// is made against `host_port`, or over TLS against `tls_host_port` when
+    /// every test type of `test` is a TLS test type.
     fn wait_until_accepting_requests(
         &mut self,
         container_ids: &(String, Option<String>),
         host_port: &str,
+        tls_host_port: Option<&str>,
         test: &Test,
     ) -> ToolsetResult<()> {
-        let mut slept_for = 0;
+        let host = match self.docker_config.server_host {
+            "tfb-server" => "localhost",
+            server_host => server_host,
+        };
+        let (tls, port, test_url) = match test.readiness_url() {
+            Some((test_type, url)) if test.is_tls_test_type(test_type) => match tls_host_port {
+                Some(tls_host_port) => (true, tls_host_port, url.clone()),
+                None => (false, host_port, url.clone()),
+            },
+            Some((_, url)) => (false, host_port, url.clone()),
+            None => (false, host_port, String::new()),
+        };
+
+        let started = Instant::now();
         loop {
             self.trip();
             let inspect = inspect_container(
@@ -803,42 +1389,37 @@ impl<'a> Benchmarker<'a> {
                 Simple::new(),
             )?;
             if !inspect.state.running {
-                return Err(AppServerContainerShutDownError);
+                return Err(AppServerContainerShutDownError(Box::new(
+                    ContainerSnapshot::from_inspection(&inspect),
+                )));
             }
             self.trip();
-            if slept_for > 60 {
+            if started.elapsed() > self.docker_config.readiness_timeout {
                 self.trip();
                 self.stop_containers();
 
-                return Err(NoResponseFromDockerContainerError);
+                return Err(ApplicationNeverReady);
             }
-            let mut easy = Easy2::new(Simple::new());
-
-            let mut endpoint = String::new();
-            if let Some(key) = test.urls.keys().next() {
-                if let Some(_endpoint) = test.urls.get(key) {
-                    endpoint = _endpoint.clone();
-                }
+            if is_ready(
+                &self.docker_config.readiness_probe,
+                tls,
+                host,
+                port,
+                &test_url,
+            ) {
+                return Ok(());
             }
-
-            let url = match self.docker_config.server_host {
-                "tfb-server" => format!("http://localhost:{}{}", host_port, endpoint),
-                _ => format!(
-                    "http://{}:{}{}",
-                    &self.docker_config.server_host, host_port, endpoint
-                ),
-            };
-            easy.url(&url)?;
-            easy.timeout(time::Duration::from_secs(1))?;
-            let _ = easy.perform();
-
-            if let Ok(code) = easy.response_code() {
-                if code > 0 {
-                    return Ok(());
-                }
-            }
-            slept_for += 1;
-            thread::sleep(Duration::from_secs(1));
+            thread::sleep(self.docker_config.readiness_interval);
         }
     }
 }
+
+/// Gets the `TestKey` identifying the given `test_type` of `test`.
+fn test_key(project: &Project, test: &Test, test_type: &str, database: &Option<String>) -> TestKey {
+    TestKey {
+        framework_name: project.framework.get_name(),
+        test_name: test.get_name(),
+        test_type: test_type.to_string(),
+        database: database.clone(),
+    }
+}
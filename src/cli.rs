@@ -1,9 +1,15 @@
 use crate::benchmarker::{modes, Benchmarker};
-use crate::docker::docker_config::DockerConfig;
-use crate::error::ToolsetError::UnknownBenchmarkerModeError;
+use crate::docker::docker_config::{DockerConfig, DockerEnvironment};
+use crate::docker::preflight::check_environment;
+use crate::error::ToolsetError::{
+    PreflightFailed, UnknownBenchmarkerModeError, VerificationFailedException,
+};
 use crate::error::ToolsetResult;
-use crate::io::get_tfb_dir;
-use crate::{io, metadata, options};
+use crate::io::{get_tfb_dir, Logger};
+use crate::manifest::Manifest;
+use crate::{io, metadata, options, status};
+use colored::Colorize;
+use std::path::PathBuf;
 
 /// Runs the CLI matching the arguments/options passed and handling each.
 pub fn run() -> ToolsetResult<()> {
@@ -32,17 +38,82 @@ pub fn run() -> ToolsetResult<()> {
         println!("PARSE_RESULTS");
         Ok(())
     } else if let Some(mode) = matches.value_of(options::args::MODE) {
-        let docker_config = DockerConfig::new(&matches);
-        let projects = metadata::list_projects_to_run(&matches);
+        if matches.is_present(options::args::SELF_CHECK) {
+            return self_check(&DockerEnvironment::new(&matches)?);
+        }
+        let docker_config = DockerConfig::new(&matches)?;
+        let mut projects = metadata::list_projects_to_run(&matches);
+        let previous_manifest = match matches.value_of(options::args::RERUN_FAILED) {
+            Some(path) => {
+                let manifest = Manifest::load(&PathBuf::from(path))?;
+                projects = manifest.filter_projects(projects);
+                Some(manifest)
+            }
+            None => None,
+        };
         let mut benchmarker = Benchmarker::new(docker_config, projects, mode);
-        match mode {
-            modes::BENCHMARK => benchmarker.benchmark(),
-            modes::VERIFY | modes::CICD => benchmarker.verify(),
+        if let Some(manifest) = previous_manifest {
+            benchmarker.set_previous_manifest(manifest);
+        }
+        let status_writer = match matches.value_of(options::args::STATUS_FILE) {
+            Some(path) => {
+                let (sender, handle) = status::write_run_events(path)?;
+                benchmarker.add_hooks(Box::new(sender));
+                Some(handle)
+            }
+            None => None,
+        };
+        let result = match mode {
+            modes::BENCHMARK => benchmarker.benchmark().map(|_| ()),
+            modes::VERIFY | modes::CICD => {
+                let report = match matches.value_of(options::args::VERIFY_CONTAINER) {
+                    Some(container_id) => benchmarker.verify_existing(container_id)?,
+                    None => benchmarker.verify()?,
+                };
+                if report.succeeded() {
+                    Ok(())
+                } else {
+                    Err(VerificationFailedException)
+                }
+            }
             modes::DEBUG => benchmarker.debug(),
             _ => Err(UnknownBenchmarkerModeError(mode.to_string())),
+        };
+        // Closes the status channel so the last events are written.
+        drop(benchmarker);
+        if let Some(handle) = status_writer {
+            handle.join().unwrap_or(());
         }
+
+        result
     } else {
         app.print_help().unwrap();
         Ok(())
     }
 }
+
+//
+// PRIVATES
+//
+
+/// Runs the checks of the Docker environment given by `environment` and logs
+/// the outcome of each.
+fn self_check(environment: &DockerEnvironment) -> ToolsetResult<()> {
+    let report = check_environment(environment)?;
+    let logger = Logger::default();
+    for check in &report.checks {
+        if check.passed {
+            logger.log(format!("{} {}", "✔".green(), check.message))?;
+        } else {
+            logger.log(format!("{} {}", "✘".red(), check.message).red())?;
+        }
+    }
+
+    if report.passed() {
+        Ok(())
+    } else {
+        Err(PreflightFailed {
+            failed: report.failures().len(),
+        })
+    }
+}
@@ -9,6 +9,9 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use toml::Value;
 
+/// The container port assumed to serve TLS when a test does not specify one.
+pub const DEFAULT_TLS_PORT: u16 = 8443;
+
 pub trait Named {
     fn get_name(&self) -> String;
 }
@@ -47,6 +50,10 @@ pub struct Test {
     pub versus: String,
     pub tags: Option<Vec<String>>,
     pub dockerfile: Option<String>,
+    /// The test types which must be verified and benchmarked over TLS.
+    pub tls_test_types: Option<Vec<String>>,
+    /// The container port serving TLS; `DEFAULT_TLS_PORT` when unset.
+    pub tls_port: Option<u16>,
 }
 
 impl Named for Test {
@@ -64,6 +71,37 @@ impl Test {
             self.urls.retain(|key, _| key == test_type);
         }
     }
+
+    /// Whether the given `test_type` must be run over TLS.
+    pub fn is_tls_test_type(&self, test_type: &str) -> bool {
+        self.tls_test_types
+            .iter()
+            .flatten()
+            .any(|tls_test_type| tls_test_type == test_type)
+    }
+
+    /// Gets the test types to be run over TLS.
+    pub fn get_tls_test_types(&self) -> Vec<String> {
+        self.urls
+            .keys()
+            .filter(|test_type| self.is_tls_test_type(test_type))
+            .cloned()
+            .collect()
+    }
+
+    /// Gets the test type and URL against which readiness is probed; a
+    /// plaintext test type when there is one.
+    pub fn readiness_url(&self) -> Option<(&String, &String)> {
+        let mut urls: Vec<(&String, &String)> = self.urls.iter().collect();
+        urls.sort_by_key(|(test_type, _)| self.is_tls_test_type(test_type));
+
+        urls.into_iter().next()
+    }
+
+    /// Gets the container port serving TLS.
+    pub fn get_tls_port(&self) -> u16 {
+        self.tls_port.unwrap_or(DEFAULT_TLS_PORT)
+    }
 }
 
 /// Project is the structure that represents the unit of data on which the
@@ -247,4 +285,32 @@ mod tests {
             Err(e) => panic!("io::get_tfb_dir() failed with error: {:?}", e),
         }
     }
+
+    #[test]
+    fn it_probes_readiness_over_plaintext_when_it_can() {
+        let test = |tls_test_types: &str| -> config::Test {
+            toml::from_str(&format!(
+                r#"
+                    name = "gemini"
+                    approach = "Realistic"
+                    classification = "Fullstack"
+                    platform = "Servlet"
+                    webserver = "Resin"
+                    os = "Linux"
+                    versus = "servlet"
+                    tls_test_types = [{}]
+                    [urls]
+                    json = "/json"
+                    json-tls = "/json"
+                "#,
+                tls_test_types
+            ))
+            .unwrap()
+        };
+
+        let plaintext_and_tls = test(r#""json-tls""#);
+        assert_eq!(plaintext_and_tls.readiness_url().unwrap().0, "json");
+        let test = test(r#""json", "json-tls""#);
+        assert!(test.is_tls_test_type(test.readiness_url().unwrap().0));
+    }
 }
@@ -0,0 +1,197 @@
+//! Persists `BenchmarkCommands` to disk so that subsequent runs can replay
+//! them instead of starting a retrieval container.
+//!
+//! Cached commands still contain the `tfb-server` placeholder; substitution
+//! of the server host happens when the benchmarker container is created, so
+//! a cached command is valid against any server host. The levels and duration
+//! the verifier bakes into the commands are part of the cache key, so changing
+//! any of them retrieves the commands afresh.
+
+use crate::docker::docker_config::{levels_to_env, DockerConfig};
+use crate::docker::BenchmarkCommands;
+use crate::error::ToolsetResult;
+use crate::io::get_tfb_dir;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Loads the cached `BenchmarkCommands` for the given framework, test, and
+/// test type under the levels and duration of `config`, if any exist.
+pub fn load_benchmark_commands(
+    config: &DockerConfig,
+    framework_name: &str,
+    test_name: &str,
+    test_type: &str,
+) -> ToolsetResult<Option<BenchmarkCommands>> {
+    load_from(
+        &get_cache_dir()?,
+        framework_name,
+        test_name,
+        test_type,
+        &get_cache_key(config),
+    )
+}
+
+/// Writes the given `commands` to the cache for the given framework, test,
+/// and test type under the levels and duration of `config`, replacing any
+/// previously cached commands.
+pub fn store_benchmark_commands(
+    config: &DockerConfig,
+    framework_name: &str,
+    test_name: &str,
+    test_type: &str,
+    commands: &BenchmarkCommands,
+) -> ToolsetResult<()> {
+    store_in(
+        &get_cache_dir()?,
+        framework_name,
+        test_name,
+        test_type,
+        &get_cache_key(config),
+        commands,
+    )
+}
+
+//
+// PRIVATES
+//
+
+/// Gets the root directory of the benchmark command cache.
+fn get_cache_dir() -> ToolsetResult<PathBuf> {
+    let mut cache_dir = get_tfb_dir()?;
+    cache_dir.push(".cache");
+    cache_dir.push("benchmark_commands");
+
+    Ok(cache_dir)
+}
+
+/// Gets the part of the cache key which captures the levels and duration of
+/// `config`, each of which the verifier bakes into the commands it retrieves.
+fn get_cache_key(config: &DockerConfig) -> String {
+    cache_key(
+        &config.concurrency_levels,
+        &config.pipeline_concurrency_levels,
+        &config.query_levels,
+        &config.cached_query_levels,
+        config.duration,
+    )
+}
+
+fn cache_key(
+    concurrency_levels: &[u32],
+    pipeline_concurrency_levels: &[u32],
+    query_levels: &str,
+    cached_query_levels: &str,
+    duration: u32,
+) -> String {
+    format!(
+        "c{}_p{}_q{}_cq{}_d{}",
+        levels_to_env(concurrency_levels),
+        levels_to_env(pipeline_concurrency_levels),
+        query_levels,
+        cached_query_levels,
+        duration
+    )
+    .replace(',', "-")
+}
+
+/// Gets the path of the cache file for the given framework, test, test type,
+/// and `key` beneath `cache_dir`.
+fn get_cache_file(
+    cache_dir: &Path,
+    framework_name: &str,
+    test_name: &str,
+    test_type: &str,
+    key: &str,
+) -> PathBuf {
+    let mut cache_file = cache_dir.to_path_buf();
+    cache_file.push(framework_name.to_lowercase());
+    cache_file.push(test_name.to_lowercase());
+    cache_file.push(format!("{}_{}.json", test_type, key));
+
+    cache_file
+}
+
+fn load_from(
+    cache_dir: &Path,
+    framework_name: &str,
+    test_name: &str,
+    test_type: &str,
+    key: &str,
+) -> ToolsetResult<Option<BenchmarkCommands>> {
+    let cache_file = get_cache_file(cache_dir, framework_name, test_name, test_type, key);
+    if !cache_file.exists() {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(cache_file)?;
+    Ok(Some(serde_json::from_str(&contents)?))
+}
+
+fn store_in(
+    cache_dir: &Path,
+    framework_name: &str,
+    test_name: &str,
+    test_type: &str,
+    key: &str,
+    commands: &BenchmarkCommands,
+) -> ToolsetResult<()> {
+    let cache_file = get_cache_file(cache_dir, framework_name, test_name, test_type, key);
+    if let Some(parent) = cache_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(cache_file, serde_json::to_string_pretty(commands)?)?;
+
+    Ok(())
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::command_cache::{cache_key, load_from, store_in};
+    use crate::docker::BenchmarkCommands;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn it_can_round_trip_benchmark_commands() {
+        let mut cache_dir = env::temp_dir();
+        cache_dir.push(format!("tfb_command_cache_{}", std::process::id()));
+
+        let key = cache_key(&[16, 32], &[256], "1,5", "1,10", 15);
+        assert!(load_from(&cache_dir, "Gemini", "gemini", "json", &key)
+            .unwrap()
+            .is_none());
+
+        let commands = BenchmarkCommands {
+            primer_command: vec!["wrk".to_string(), "http://tfb-server:8080/json".to_string()],
+            warmup_command: vec!["wrk".to_string()],
+            benchmark_commands: vec![vec!["wrk".to_string(), "-c".to_string(), "16".to_string()]],
+        };
+        store_in(&cache_dir, "Gemini", "gemini", "json", &key, &commands).unwrap();
+
+        let loaded = load_from(&cache_dir, "Gemini", "gemini", "json", &key)
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.primer_command, commands.primer_command);
+        assert_eq!(loaded.warmup_command, commands.warmup_command);
+        assert_eq!(loaded.benchmark_commands, commands.benchmark_commands);
+
+        // Any change to the levels or duration misses the cache.
+        for stale_key in &[
+            cache_key(&[16, 64], &[256], "1,5", "1,10", 15),
+            cache_key(&[16, 32], &[512], "1,5", "1,10", 15),
+            cache_key(&[16, 32], &[256], "1,20", "1,10", 15),
+            cache_key(&[16, 32], &[256], "1,5", "1,100", 15),
+            cache_key(&[16, 32], &[256], "1,5", "1,10", 30),
+        ] {
+            assert!(load_from(&cache_dir, "Gemini", "gemini", "json", stale_key)
+                .unwrap()
+                .is_none());
+        }
+
+        fs::remove_dir_all(cache_dir).unwrap();
+    }
+}
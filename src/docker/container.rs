@@ -1,36 +1,73 @@
 use crate::benchmarker::Mode;
 use crate::config::{Named, Project, Test};
-use crate::docker::docker_config::DockerConfig;
+use crate::docker::docker_config::{levels_to_env, BenchmarkerReplica, DockerConfig, DockerTls};
+use crate::docker::host_config::{
+    set_cap_add, set_cpuset, set_port_bindings, ContainerHostSettings,
+};
+use crate::docker::image::{
+    get_exposed_ports, get_image_label, get_other_containers_using_image,
+    prune_dangling_toolset_images, VERIFIER_IMAGE, VERIFIER_OPTIONAL_ENV_LABEL,
+};
 use crate::docker::listener::application::Application;
 use crate::docker::listener::benchmark_command_listener::BenchmarkCommandListener;
 use crate::docker::listener::benchmarker::{BenchmarkResults, Benchmarker};
 use crate::docker::listener::build_container::BuildContainer;
+use crate::docker::listener::demultiplexer::{AttachStreams, Demultiplexer};
+use crate::docker::listener::log_buffer::LogBuffer;
 use crate::docker::listener::simple::Simple;
 use crate::docker::listener::verifier::Verifier;
+use crate::docker::network::connect_container_to_network;
+use crate::docker::request::{docker_request, Method};
 use crate::docker::{
-    BenchmarkCommands, DockerContainerIdFuture, DockerOrchestration, Verification,
+    BenchmarkCommands, ContainerSnapshot, DockerContainerIdFuture, DockerOrchestration,
+    Verification,
 };
 use crate::error::ToolsetError::{
-    ContainerPortMappingInspectionError, ExposePortError, FailedBenchmarkCommandRetrievalError,
+    BandwidthLimitRequiresBridgeNetworkError, ContainerPortMappingInspectionError,
+    DatabaseNeverReady, DockerError, DockerRequestFailed, ExposePortError,
+    FailedBenchmarkCommandRetrievalError, NoExposedPorts,
 };
 use crate::error::ToolsetResult;
 use crate::io::Logger;
+use colored::Colorize;
 use dockurl::container::create::host_config::{HostConfig, Ulimit};
 use dockurl::container::create::networking_config::{
     EndpointSettings, EndpointsConfig, NetworkingConfig,
 };
 use dockurl::container::create::options::Options;
+use dockurl::container::inspect::{ExposedPort, Port};
 use dockurl::container::{
     attach_to_container, delete_container, get_container_logs, inspect_container, kill_container,
     wait_for_container_to_exit,
 };
-use dockurl::image::{delete_image, delete_unused_images};
+use dockurl::error::DockerError::StopContainerError;
+use dockurl::image::delete_image;
 use dockurl::network::NetworkMode;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::task::Poll;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// The open file limit of application and database containers.
+pub const APPLICATION_OPEN_FILE_LIMIT: u32 = 200000;
+
+/// The socket backlog (`net.core.somaxconn`) of application, database, and
+/// benchmarker containers.
+pub const SOCKET_BACKLOG: u32 = 65535;
+
+/// How long past a container's grace period to wait for Docker to respond to
+/// a request to stop it before killing it instead.
+const STOP_REQUEST_SLACK: Duration = Duration::from_secs(10);
+
+/// How long a benchmark preflight probe may take to connect to the benchmark
+/// target before it is considered unreachable.
+const PREFLIGHT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The env vars, with placeholder values, which the verifier requires in
+/// database mode but does not use.
+const VERIFIER_PLACEHOLDER_ENV: &[(&str, &str)] =
+    &[("PORT", "0"), ("ENDPOINT", ""), ("TEST_TYPE", "")];
 
 /// Note: this function makes the assumption that the image is already
 /// built and that the Docker daemon is aware of it.
@@ -39,12 +76,14 @@ pub fn create_container(
     image_id: &str,
     network_id: &str,
     host_name: &str,
+    domain_name: &str,
     docker_host: &str,
+    host_settings: &ContainerHostSettings,
 ) -> ToolsetResult<String> {
     let mut options = Options::new();
     options.image(image_id);
     options.hostname(host_name);
-    options.domain_name(host_name);
+    options.domain_name(domain_name);
 
     let mut host_config = HostConfig::new();
     let mut endpoint_settings = EndpointSettings::new();
@@ -55,18 +94,19 @@ pub fn create_container(
             endpoint_settings.alias(host_name);
         }
         dockurl::network::NetworkMode::Host => {
-            host_config.extra_host("tfb-database", &config.database_host);
+            host_config.extra_host("tfb-database", &host_settings.database_host);
             host_config.network_mode(dockurl::network::NetworkMode::Host);
         }
     }
+    let backlog = SOCKET_BACKLOG.to_string();
     let mut sysctls = HashMap::new();
-    sysctls.insert("net.core.somaxconn", "65535");
+    sysctls.insert("net.core.somaxconn", backlog.as_str());
     host_config.sysctls(sysctls);
     host_config.ulimits(vec![
         Ulimit {
             name: "nofile",
-            soft: 200000,
-            hard: 200000,
+            soft: APPLICATION_OPEN_FILE_LIMIT,
+            hard: APPLICATION_OPEN_FILE_LIMIT,
         },
         Ulimit {
             name: "rtprio",
@@ -74,15 +114,27 @@ pub fn create_container(
             hard: 99,
         },
     ]);
-    host_config.publish_all_ports(true);
+    match config.host_port_range {
+        Some(range) => {
+            let exposed_ports = get_exposed_ports(
+                image_id,
+                docker_host,
+                config.use_unix_socket,
+                config.docker_tls.as_ref(),
+            )?;
+            set_port_bindings(&mut host_config, &exposed_ports, &range);
+        }
+        None => host_config.publish_all_ports(true),
+    }
     host_config.privileged(true);
+    host_settings.apply(&mut host_config);
 
     options.networking_config(NetworkingConfig {
         endpoints_config: EndpointsConfig { endpoint_settings },
     });
 
     options.host_config(host_config);
-    options.tty(true);
+    options.tty(config.allocate_tty);
 
     let container_id = dockurl::container::create_container(
         options,
@@ -94,22 +146,35 @@ pub fn create_container(
     Ok(container_id)
 }
 
-/// Creates the benchmarker container and returns the Docker ID
-pub fn create_benchmarker_container(
+/// Gets the command the benchmarker container runs for the given `test_type`
+/// from the `command_strs` retrieved from the verifier.
+pub fn benchmarker_command(
     config: &DockerConfig,
+    test_type: &str,
     command_strs: &[String],
+) -> ToolsetResult<Vec<String>> {
+    resolve_benchmarker_command(
+        command_strs,
+        config.server_host_for(test_type),
+        config.bandwidth_limit,
+        &config.network_mode,
+    )
+}
+
+/// Creates the benchmarker container for the given `replica` to run the given
+/// `command` (see `benchmarker_command`) and returns the Docker ID
+pub fn create_benchmarker_container(
+    config: &DockerConfig,
+    replica: &BenchmarkerReplica,
+    test_type: &str,
+    command: &[String],
 ) -> ToolsetResult<String> {
+    let server_host = config.server_host_for(test_type);
     let mut options = Options::new();
-    options.image("techempower/tfb.verifier");
-    options.tty(true);
+    options.image(VERIFIER_IMAGE);
+    options.tty(config.allocate_tty);
     options.attach_stderr(true);
-    // The command_str we get back is an array of strings that make up the wrk
-    // command; we want to replace `tfb-server` with the IP address
-    let mut command = vec![];
-    for command_str in command_strs {
-        command.push(command_str.replace("tfb-server", &config.server_host));
-    }
-    options.cmds(command.as_slice());
+    options.cmds(command);
 
     let mut host_config = HostConfig::new();
     match &config.network_mode {
@@ -117,12 +182,13 @@ pub fn create_benchmarker_container(
             host_config.network_mode(dockurl::network::NetworkMode::Bridge);
         }
         dockurl::network::NetworkMode::Host => {
-            host_config.extra_host("tfb-server", &config.server_host);
+            host_config.extra_host("tfb-server", server_host);
             host_config.network_mode(dockurl::network::NetworkMode::Host);
         }
     }
+    let backlog = SOCKET_BACKLOG.to_string();
     let mut sysctls = HashMap::new();
-    sysctls.insert("net.core.somaxconn", "65535");
+    sysctls.insert("net.core.somaxconn", backlog.as_str());
     host_config.sysctls(sysctls);
     let ulimit = Ulimit {
         name: "nofile",
@@ -130,11 +196,17 @@ pub fn create_benchmarker_container(
         hard: 65535,
     };
     host_config.ulimits(vec![ulimit]);
+    if config.bandwidth_limit.is_some() {
+        set_cap_add(&mut host_config, &["NET_ADMIN"]);
+    }
+    if let Some(cpus) = config.benchmarker_cpuset {
+        set_cpuset(&mut host_config, cpus);
+    }
 
     options.host_config(host_config);
 
     let mut endpoint_settings = EndpointSettings::new();
-    endpoint_settings.network_id(config.client_network_id.as_str());
+    endpoint_settings.network_id(replica.network_id.as_str());
 
     options.networking_config(NetworkingConfig {
         endpoints_config: EndpointsConfig { endpoint_settings },
@@ -143,7 +215,7 @@ pub fn create_benchmarker_container(
     let container_id = dockurl::container::create_container(
         options,
         config.use_unix_socket,
-        &config.client_docker_host,
+        &replica.docker_host,
         BuildContainer::new(),
     )?;
 
@@ -160,25 +232,35 @@ pub fn create_verifier_container(
     test_type: &(&String, &String),
 ) -> ToolsetResult<String> {
     let mut options = Options::new();
-    options.image("techempower/tfb.verifier");
-    options.tty(true);
-    options.add_env(
-        "MODE",
-        match mode {
-            Mode::Verify => "verify",
-            Mode::Benchmark => "benchmark",
-        },
-    );
-    options.add_env("PORT", &orchestration.host_internal_port);
-    options.add_env("ENDPOINT", test_type.1);
-    options.add_env("TEST_TYPE", test_type.0);
-    options.add_env("CONCURRENCY_LEVELS", &config.concurrency_levels);
-    options.add_env(
-        "PIPELINE_CONCURRENCY_LEVELS",
-        &config.pipeline_concurrency_levels,
-    );
+    options.image(VERIFIER_IMAGE);
+    options.tty(config.allocate_tty);
+    let mut env = vec![
+        (
+            "MODE",
+            match mode {
+                Mode::Verify => "verify",
+                Mode::Benchmark => "benchmark",
+            }
+            .to_string(),
+        ),
+        ("PORT", orchestration.internal_port_for(test_type.0)?),
+        ("SCHEME", orchestration.scheme_for(test_type.0).to_string()),
+        ("ENDPOINT", test_type.1.clone()),
+        ("TEST_TYPE", test_type.0.clone()),
+        (
+            "CONCURRENCY_LEVELS",
+            levels_to_env(&config.concurrency_levels),
+        ),
+        (
+            "PIPELINE_CONCURRENCY_LEVELS",
+            levels_to_env(&config.pipeline_concurrency_levels),
+        ),
+    ];
     if let Some(database_name) = &orchestration.database_name {
-        options.add_env("DATABASE", database_name);
+        env.push(("DATABASE", database_name.clone()));
+    }
+    for (key, value) in merge_verifier_env(&config.verifier_env, &env) {
+        options.add_env(key, value);
     }
 
     let mut host_config = HostConfig::new();
@@ -187,8 +269,8 @@ pub fn create_verifier_container(
             host_config.network_mode(dockurl::network::NetworkMode::Bridge);
         }
         dockurl::network::NetworkMode::Host => {
-            host_config.extra_host("tfb-server", &config.server_host);
-            host_config.extra_host("tfb-database", &config.database_host);
+            host_config.extra_host("tfb-server", config.server_host_for(test_type.0));
+            host_config.extra_host("tfb-database", config.database_host_for(test_type.0));
             host_config.network_mode(dockurl::network::NetworkMode::Host);
         }
     }
@@ -221,20 +303,33 @@ pub fn create_database_verifier_container(
     database_name: &str,
 ) -> ToolsetResult<String> {
     let mut options = Options::new();
-    options.image("techempower/tfb.verifier");
-    options.tty(true);
-    options.add_env("MODE", "database");
-    // These are required but unused.
-    options.add_env("PORT", "0");
-    options.add_env("ENDPOINT", "");
-    options.add_env("TEST_TYPE", "");
-
-    options.add_env("CONCURRENCY_LEVELS", &config.concurrency_levels);
-    options.add_env(
-        "PIPELINE_CONCURRENCY_LEVELS",
-        &config.pipeline_concurrency_levels,
-    );
-    options.add_env("DATABASE", database_name);
+    options.image(VERIFIER_IMAGE);
+    options.tty(config.allocate_tty);
+    let mut env = vec![("MODE", "database".to_string())];
+    if !config.omit_verifier_placeholders {
+        let optional_env = get_image_label(
+            VERIFIER_IMAGE,
+            VERIFIER_OPTIONAL_ENV_LABEL,
+            &config.client_docker_host,
+            config.use_unix_socket,
+            config.docker_tls.as_ref(),
+        )?;
+        env.extend(verifier_placeholder_env(optional_env.as_deref()));
+    }
+    env.extend(vec![
+        (
+            "CONCURRENCY_LEVELS",
+            levels_to_env(&config.concurrency_levels),
+        ),
+        (
+            "PIPELINE_CONCURRENCY_LEVELS",
+            levels_to_env(&config.pipeline_concurrency_levels),
+        ),
+        ("DATABASE", database_name.to_string()),
+    ]);
+    for (key, value) in merge_verifier_env(&config.verifier_env, &env) {
+        options.add_env(key, value);
+    }
 
     let mut host_config = HostConfig::new();
     match &config.network_mode {
@@ -268,50 +363,114 @@ pub fn create_database_verifier_container(
     Ok(container_id)
 }
 
-/// Gets both the internal and host port binding for the container given by
-/// `container_id`.
+/// Which of the ports exposed by a container to get the binding of.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PortSelection {
+    Any,
+    /// Any port other than the given one; e.g. other than its TLS port.
+    AnyExcept(u16),
+    Only(u16),
+}
+impl PortSelection {
+    fn matches(&self, port: &str) -> bool {
+        match self {
+            PortSelection::Any => true,
+            PortSelection::AnyExcept(excluded) => port != excluded.to_string(),
+            PortSelection::Only(selected) => port == selected.to_string(),
+        }
+    }
+}
+
+/// Gets both the internal and host port binding of the port given by
+/// `selection` for the container given by `container_id`.
 pub fn get_port_bindings_for_container(
     docker_config: &DockerConfig,
     docker_host: &str,
     container_id: &str,
+    selection: PortSelection,
 ) -> ToolsetResult<(String, String)> {
-    let inspection = inspect_container(
-        container_id,
-        docker_host,
-        docker_config.use_unix_socket,
-        Simple::new(),
-    )?;
+    let mut retry_interval = docker_config.port_binding_retry_interval;
+    let mut retries = 0;
+    loop {
+        let inspection = inspect_container(
+            container_id,
+            docker_host,
+            docker_config.use_unix_socket,
+            Simple::new(),
+        )?;
 
-    if let Some(exposed_ports) = inspection.config.exposed_ports {
-        for key in exposed_ports.keys() {
-            let inner_port: Vec<&str> = key.split('/').collect();
-
-            match docker_config.network_mode {
-                NetworkMode::Bridge => {
-                    if let Some(key) = inspection.network_settings.ports.get(key) {
-                        if let Some(port_mapping) = key.get(0) {
-                            if let Some(inner_port) = inner_port.get(0) {
-                                return Ok((
-                                    port_mapping.host_port.clone(),
-                                    inner_port.to_string(),
-                                ));
-                            }
-                        }
-                    }
-                }
-                NetworkMode::Host => {
-                    return Ok((
-                        inner_port.get(0).unwrap().to_string(),
-                        inner_port.get(0).unwrap().to_string(),
-                    ));
-                }
-            };
+        match find_port_binding(
+            &docker_config.network_mode,
+            &inspection.config.exposed_ports,
+            &inspection.network_settings.ports,
+            selection,
+        ) {
+            PortBinding::Bound(host_port, inner_port) => return Ok((host_port, inner_port)),
+            PortBinding::NotExposed => {
+                return Err(ExposePortError(Box::new(
+                    ContainerSnapshot::from_inspection(&inspection),
+                )))
+            }
+            PortBinding::NoExposedPorts => {
+                return Err(NoExposedPorts {
+                    container_id: container_id.to_string(),
+                })
+            }
+            // Docker may not have finished assigning the host port yet.
+            PortBinding::Unbound if retries < docker_config.port_binding_retries => {
+                retries += 1;
+                thread::sleep(retry_interval);
+                retry_interval *= 2;
+            }
+            PortBinding::Unbound => {
+                return Err(ContainerPortMappingInspectionError(Box::new(
+                    ContainerSnapshot::from_inspection(&inspection),
+                )))
+            }
         }
-    } else {
-        return Err(ExposePortError);
     }
+}
+
+/// The host port and internal port of a port binding.
+pub type HostPorts = (String, String);
 
-    Err(ContainerPortMappingInspectionError)
+/// Gets both the internal and host port binding of the plaintext port of the
+/// application container given by `container_id` for `test`. When `test` has
+/// TLS test types, also gets both bindings of its TLS port, or `None` if the
+/// container does not expose it.
+pub fn get_application_port_bindings(
+    docker_config: &DockerConfig,
+    test: &Test,
+    container_id: &str,
+) -> ToolsetResult<(HostPorts, Option<HostPorts>)> {
+    if test.get_tls_test_types().is_empty() {
+        let ports = get_port_bindings_for_container(
+            docker_config,
+            &docker_config.server_docker_host,
+            container_id,
+            PortSelection::Any,
+        )?;
+        return Ok((ports, None));
+    }
+
+    let ports = get_port_bindings_for_container(
+        docker_config,
+        &docker_config.server_docker_host,
+        container_id,
+        PortSelection::AnyExcept(test.get_tls_port()),
+    )?;
+    let tls_ports = match get_port_bindings_for_container(
+        docker_config,
+        &docker_config.server_docker_host,
+        container_id,
+        PortSelection::Only(test.get_tls_port()),
+    ) {
+        Ok(tls_ports) => Some(tls_ports),
+        Err(ExposePortError(_)) => None,
+        Err(e) => return Err(e),
+    };
+
+    Ok((ports, tls_ports))
 }
 
 /// Starts the container for the given `Test`.
@@ -327,9 +486,17 @@ pub fn start_container(
     let cid = container_id.to_string();
     let host = docker_host.to_string();
     let use_unix_socket = docker_config.use_unix_socket;
+    let allocate_tty = docker_config.allocate_tty;
+    let attach_streams = docker_config.attach_streams;
     let logger = logger.clone();
     thread::spawn(move || {
-        attach_to_container(&cid, &host, use_unix_socket, Application::new(&logger)).unwrap();
+        attach_to_container(
+            &cid,
+            &host,
+            use_unix_socket,
+            Demultiplexer::new(Application::new(&logger), allocate_tty, attach_streams),
+        )
+        .unwrap();
     });
     dockurl::container::start_container(
         container_id,
@@ -363,8 +530,22 @@ pub fn start_benchmark_command_retrieval_container(
         container_id,
         &docker_config.client_docker_host,
         docker_config.use_unix_socket,
-        BenchmarkCommandListener::new(test_type, logger),
-    )?;
+        Demultiplexer::new(
+            BenchmarkCommandListener::new(test_type, logger),
+            docker_config.allocate_tty,
+            AttachStreams::Both,
+        ),
+    )?
+    .inner;
+    // Captured before any clean up removes the container.
+    let snapshot = match listener.benchmark_commands {
+        Some(_) => None,
+        None => Some(ContainerSnapshot::capture(
+            container_id,
+            &docker_config.client_docker_host,
+            docker_config.use_unix_socket,
+        )),
+    };
 
     if docker_config.clean_up {
         delete_container(
@@ -377,51 +558,136 @@ pub fn start_benchmark_command_retrieval_container(
             false,
         )?;
     }
-    if let Some(commands) = listener.benchmark_commands {
-        Ok(commands)
-    } else {
-        Err(FailedBenchmarkCommandRetrievalError)
+    match (listener.benchmark_commands, snapshot) {
+        (Some(commands), _) => Ok(commands),
+        (None, snapshot) => Err(FailedBenchmarkCommandRetrievalError(Box::new(
+            snapshot.unwrap_or_default(),
+        ))),
     }
 }
 
-/// Starts the benchmarker container and logs its stdout/stderr.
+/// Starts the benchmarker container given by `container_id` on the given
+/// `replica`. Call `create_benchmarker_container()` before running.
 pub fn start_benchmarker_container(
     docker_config: &DockerConfig,
+    replica: &BenchmarkerReplica,
     container_id: &str,
-    logger: &Logger,
-) -> ToolsetResult<BenchmarkResults> {
+) -> ToolsetResult<()> {
     dockurl::container::start_container(
         container_id,
-        &docker_config.client_docker_host,
+        &replica.docker_host,
         docker_config.use_unix_socket,
         Simple::new(),
     )?;
+
+    Ok(())
+}
+
+/// Waits for the started benchmarker container on the given `replica` to
+/// exit, logs its stdout/stderr and parses its results.
+pub fn wait_for_benchmarker_container(
+    docker_config: &DockerConfig,
+    replica: &BenchmarkerReplica,
+    container_id: &str,
+    logger: &Logger,
+) -> ToolsetResult<BenchmarkResults> {
     wait_for_container_to_exit(
         container_id,
-        &docker_config.client_docker_host,
+        &replica.docker_host,
         docker_config.use_unix_socket,
         Simple::new(),
     )?;
     let benchmarker = get_container_logs(
         container_id,
-        &docker_config.client_docker_host,
+        &replica.docker_host,
         docker_config.use_unix_socket,
-        Benchmarker::new(logger),
-    )?;
+        Demultiplexer::new(
+            Benchmarker::new(logger, docker_config.log_buffer_limits),
+            docker_config.allocate_tty,
+            AttachStreams::Both,
+        ),
+    )?
+    .inner;
+    let exit_code = inspect_container(
+        container_id,
+        &replica.docker_host,
+        docker_config.use_unix_socket,
+        Simple::new(),
+    )
+    .ok()
+    .map(|inspection| inspection.state.exit_code);
 
-    if docker_config.clean_up {
-        delete_container(
-            &container_id,
-            &docker_config.client_docker_host,
-            docker_config.use_unix_socket,
-            Simple::new(),
-            true,
-            true,
-            false,
-        )?;
+    // The output is most needed when the benchmark failed.
+    let logs = if docker_config.capture_logs {
+        Some(benchmarker.logs())
+    } else {
+        None
+    };
+    match benchmarker.results(exit_code) {
+        Ok(mut results) => {
+            results.logs = logs;
+            Ok(results)
+        }
+        Err(error) => Err(error.with_logs(logs)),
     }
+}
+
+/// Deletes the exited benchmarker container given by `container_id` from the
+/// given `replica`.
+pub fn delete_benchmarker_container(
+    docker_config: &DockerConfig,
+    replica: &BenchmarkerReplica,
+    container_id: &str,
+) -> ToolsetResult<()> {
+    delete_container(
+        container_id,
+        &replica.docker_host,
+        docker_config.use_unix_socket,
+        Simple::new(),
+        true,
+        true,
+        false,
+    )?;
+
+    Ok(())
+}
 
-    benchmarker.parse_wrk_output()
+/// Creates a short-lived benchmarker container on the given `replica` which
+/// connects to the application for `test_type` on `port`, and returns the
+/// Docker ID. The probe shares the benchmarker's network, so it reaches the
+/// application by the same route as the benchmark itself.
+pub fn create_benchmark_probe(
+    config: &DockerConfig,
+    replica: &BenchmarkerReplica,
+    test_type: &str,
+    port: &str,
+) -> ToolsetResult<String> {
+    let command = preflight_command(config.server_host_for(test_type), port);
+
+    create_benchmarker_container(config, replica, test_type, &command)
+}
+
+/// Waits for the started probe given by `container_id` on the given `replica`
+/// to exit, returning whether its connection succeeded.
+pub fn wait_for_benchmark_probe(
+    config: &DockerConfig,
+    replica: &BenchmarkerReplica,
+    container_id: &str,
+) -> ToolsetResult<bool> {
+    wait_for_container_to_exit(
+        container_id,
+        &replica.docker_host,
+        config.use_unix_socket,
+        Simple::new(),
+    )?;
+    let inspection = inspect_container(
+        container_id,
+        &replica.docker_host,
+        config.use_unix_socket,
+        Simple::new(),
+    )?;
+
+    Ok(inspection.state.exit_code == 0)
 }
 
 /// Starts the verification container, captures its stdout/stderr, parses any
@@ -438,8 +704,10 @@ pub fn start_verification_container(
         framework_name: project.framework.get_name(),
         test_name: test.get_name(),
         type_name: test_type.0.clone(),
+        database: None,
         warnings: vec![],
         errors: vec![],
+        logs: None,
     };
     let verification = Arc::new(Mutex::new(to_ret.clone()));
 
@@ -447,8 +715,17 @@ pub fn start_verification_container(
     let config = docker_config.clone();
     let client_docker_host = config.client_docker_host;
     let use_unix_socket = docker_config.use_unix_socket;
+    let allocate_tty = docker_config.allocate_tty;
     let verifier_logger = logger.clone();
     let inner_verification = Arc::clone(&verification);
+    let output = if docker_config.capture_logs {
+        Some(Arc::new(Mutex::new(LogBuffer::new(
+            docker_config.log_buffer_limits,
+        ))))
+    } else {
+        None
+    };
+    let inner_output = output.clone();
     // This function is extremely complicated and seemingly in the wrong order, but it is very
     // convoluted and intended. We attach to the container *before* it is started in a new thread,
     // and, using an Arc, communicate stderr/stdout and messages from the container (when it runs)
@@ -464,7 +741,15 @@ pub fn start_verification_container(
             &verifier_container_id,
             &client_docker_host,
             use_unix_socket,
-            Verifier::new(Arc::clone(&inner_verification), &verifier_logger),
+            Demultiplexer::new(
+                Verifier::new(
+                    Arc::clone(&inner_verification),
+                    &verifier_logger,
+                    inner_output,
+                ),
+                allocate_tty,
+                AttachStreams::Both,
+            ),
         )
         .unwrap();
     });
@@ -483,7 +768,24 @@ pub fn start_verification_container(
         Simple::new(),
     )?;
 
-    if docker_config.clean_up {
+    if let Ok(verification) = verification.lock() {
+        to_ret = verification.clone();
+    }
+    if let Some(output) = output {
+        if let Ok(output) = output.lock() {
+            to_ret.logs = Some(String::from_utf8_lossy(&output.contents()).to_string());
+        }
+    }
+
+    if docker_config.keep_failed && !to_ret.errors.is_empty() {
+        logger.log(
+            format!(
+                "Keeping verifier container {} which failed verification",
+                container_id
+            )
+            .yellow(),
+        )?;
+    } else if docker_config.clean_up {
         delete_container(
             &container_id,
             &docker_config.client_docker_host,
@@ -495,32 +797,108 @@ pub fn start_verification_container(
         )?;
     }
 
-    if let Ok(verification) = verification.lock() {
-        to_ret = verification.clone();
-    }
-
     Ok(to_ret)
 }
 
-/// Starts the verification container and blocks until the database is accepting connections.
-pub fn block_until_database_is_ready(
+/// Verifies the given `test_type` against the application container given by
+/// `container_id`, which is already running (e.g. started by other tooling).
+/// The application container is neither created nor stopped; only the
+/// verifier container is, and it is removed afterwards only when cleaning up.
+pub fn verify_existing_container(
     docker_config: &DockerConfig,
+    project: &Project,
+    test: &Test,
+    test_type: &(&String, &String),
     container_id: &str,
-) -> ToolsetResult<()> {
-    dockurl::container::start_container(
-        container_id,
-        &docker_config.client_docker_host,
-        docker_config.use_unix_socket,
-        Simple::new(),
-    )?;
+    logger: &Logger,
+) -> ToolsetResult<Verification> {
+    let ((host_port, host_internal_port), tls_ports) =
+        get_application_port_bindings(docker_config, test, container_id)?;
+    let orchestration = DockerOrchestration {
+        host_container_id: container_id.to_string(),
+        host_port,
+        host_internal_port,
+        database_name: test.database.clone(),
+        db_container_id: None,
+        db_host_port: None,
+        db_internal_port: None,
+        tls_test_types: test.get_tls_test_types(),
+        tls_internal_port: tls_ports.map(|(_, internal_port)| internal_port),
+        tls_port: test.get_tls_port(),
+    };
 
-    wait_for_container_to_exit(
-        container_id,
+    let verifier_container_id =
+        create_verifier_container(docker_config, &orchestration, Mode::Verify, test_type)?;
+    connect_container_to_network(
+        docker_config,
         &docker_config.client_docker_host,
-        docker_config.use_unix_socket,
-        Simple::new(),
+        &docker_config.client_network_id,
+        &verifier_container_id,
     )?;
 
+    start_verification_container(
+        docker_config,
+        project,
+        test,
+        test_type,
+        &verifier_container_id,
+        logger,
+    )
+}
+
+/// Starts the verification container and blocks until the database is
+/// accepting connections. A verifier which exits unsuccessfully is restarted
+/// after the configured backoff until the database is ready or the backoff's
+/// timeout would be exceeded. Each attempt is logged to `database.txt` at the
+/// debug level; whether the database became ready is logged to `logger`.
+pub fn block_until_database_is_ready(
+    docker_config: &DockerConfig,
+    container_id: &str,
+    database: &str,
+    logger: &Logger,
+) -> ToolsetResult<()> {
+    let mut attempts_logger = logger.clone();
+    attempts_logger.set_log_file("database.txt");
+    let backoff = docker_config.database_backoff;
+    let started = Instant::now();
+    let mut attempts = 0;
+    let ready = loop {
+        attempts += 1;
+        dockurl::container::start_container(
+            container_id,
+            &docker_config.client_docker_host,
+            docker_config.use_unix_socket,
+            Simple::new(),
+        )?;
+        wait_for_container_to_exit(
+            container_id,
+            &docker_config.client_docker_host,
+            docker_config.use_unix_socket,
+            Simple::new(),
+        )?;
+        let exit_code = inspect_container(
+            container_id,
+            &docker_config.client_docker_host,
+            docker_config.use_unix_socket,
+            Simple::new(),
+        )?
+        .state
+        .exit_code;
+        attempts_logger.debug(format!(
+            "Database readiness attempt {} exited with {}",
+            attempts, exit_code
+        ))?;
+        if exit_code == 0 {
+            break true;
+        }
+
+        let delay = backoff.delay(attempts - 1);
+        if started.elapsed() + delay > backoff.timeout {
+            break false;
+        }
+        thread::sleep(delay);
+    };
+
     if docker_config.clean_up {
         delete_container(
             container_id,
@@ -533,26 +911,75 @@ pub fn block_until_database_is_ready(
         )?;
     }
 
-    Ok(())
+    if ready {
+        logger.log(format!(
+            "{} ready after {} attempt(s) in {}s",
+            database,
+            attempts,
+            started.elapsed().as_secs()
+        ))?;
+        Ok(())
+    } else {
+        logger.error(format!(
+            "{} not ready after {} attempt(s) in {}s",
+            database,
+            attempts,
+            started.elapsed().as_secs()
+        ))?;
+        Err(DatabaseNeverReady {
+            database: database.to_string(),
+            attempts,
+        })
+    }
 }
 
 /// Polls until `container` is ready with either some `container_id` or `None`,
-/// then kills that `container_id`, and sets the internal `container_id` to
-/// `None`.
+/// then stops that `container_id`, and sets the internal `container_id` to
+/// `None`. The container is given `grace_period` to exit once signalled to
+/// stop before it is killed; with no grace period, it is killed outright.
+/// When `prune_images` is set, dangling toolset images are pruned after the
+/// container's image is deleted.
 ///
-/// Note: this function blocks until the given `container` is in a ready state.
+/// Note: this function blocks until the given `container` is in a ready state
+/// or `timeout` elapses, after which whatever `container_id` is present (if
+/// any) is stopped. It may safely be called any number of times; once torn
+/// down, a `container` is left alone.
 pub fn stop_docker_container_future(
     use_unix_socket: bool,
+    tls: Option<&DockerTls>,
     docker_clean_up: bool,
+    prune_images: bool,
+    timeout: Duration,
+    grace_period: Duration,
     container_id: &Arc<Mutex<DockerContainerIdFuture>>,
 ) {
-    let mut requires_wait_to_stop = false;
-    if let Ok(container) = container_id.lock() {
-        requires_wait_to_stop = container.requires_wait_to_stop;
-    }
+    // Everything torn down is cleared from `container_id`, so a repeated or
+    // concurrent teardown (e.g. by the ctrl-c handler and the normal flow
+    // both) finds nothing left to do and returns.
+    let requires_wait_to_stop = match container_id.lock() {
+        Ok(mut container) if container.is_torn_down() => {
+            container.release_slot();
+            return;
+        }
+        Ok(container) => container.requires_wait_to_stop,
+        Err(_) => return,
+    };
     if requires_wait_to_stop {
+        let started = Instant::now();
         let mut poll = Poll::Pending;
         while poll == Poll::Pending {
+            if started.elapsed() >= timeout {
+                Logger::default()
+                    .log(
+                        format!(
+                            "Container did not become ready to stop within {}s; forcing teardown",
+                            timeout.as_secs()
+                        )
+                        .yellow(),
+                    )
+                    .unwrap_or(());
+                break;
+            }
             if let Ok(container) = container_id.lock() {
                 poll = container.poll();
                 if poll == Poll::Pending {
@@ -560,36 +987,56 @@ pub fn stop_docker_container_future(
                 }
             }
         }
-        if let Ok(mut container) = container_id.lock() {
-            if let Some(container_id) = &container.container_id {
-                kill_container(
+    }
+    if let Ok(mut container) = container_id.lock() {
+        // Unregistering resets `keep` and the ID, so they are read up
+        // front.
+        let keep = container.keep;
+        let registered_id = container.container_id.clone();
+        if let Some(container_id) = &container.container_id {
+            stop_or_kill_container(
+                container_id,
+                &container.docker_host,
+                use_unix_socket,
+                tls,
+                grace_period,
+            );
+
+            if keep {
+                Logger::default()
+                    .log(format!("Keeping stopped container {}", container_id).yellow())
+                    .unwrap_or(());
+            } else if docker_clean_up {
+                delete_container(
                     container_id,
                     &container.docker_host,
                     use_unix_socket,
                     Simple::new(),
+                    true,
+                    true,
+                    false,
                 )
                 .unwrap_or(());
-                // ↑ specifically succeeds even if there is an error
-                // For instance, if an application container stops running because the application
-                // crashed, we want to call this and continue.
-
-                if docker_clean_up {
-                    delete_container(
-                        container_id,
-                        &container.docker_host,
-                        use_unix_socket,
-                        Simple::new(),
-                        true,
-                        true,
-                        false,
-                    )
-                    .unwrap_or(());
-                }
-
-                container.unregister();
             }
-            if let Some(image_id) = &container.image_id {
-                if docker_clean_up {
+
+            container.unregister();
+        }
+        container.release_slot();
+        if let Some(image_id) = &container.image_id {
+            if docker_clean_up && !keep {
+                // Force-deleting an image out from under another
+                // container would break it, so the image is left to
+                // whichever of its containers is torn down last. If
+                // those cannot be listed, the image is kept to be safe.
+                let in_use = get_other_containers_using_image(
+                    image_id,
+                    registered_id.as_deref(),
+                    &container.docker_host,
+                    use_unix_socket,
+                    tls,
+                )
+                .map_or(true, |containers| !containers.is_empty());
+                if !in_use {
                     delete_image(
                         image_id,
                         true,
@@ -599,18 +1046,481 @@ pub fn stop_docker_container_future(
                         Simple::new(),
                     )
                     .unwrap_or(None);
+                }
 
-                    // Todo - this is jank... do this better.
-                    delete_unused_images(
-                        "{\"dangling\":[\"true\"]}",
-                        &container.docker_host,
-                        use_unix_socket,
-                        Simple::new(),
-                    )
-                    .unwrap_or(());
+                if prune_images {
+                    prune_dangling_toolset_images(use_unix_socket, &container.docker_host)
+                        .unwrap_or(());
                 }
             }
-            container.image_id = None;
+        }
+        container.image_id = None;
+    }
+}
+
+//
+// PRIVATES
+//
+
+/// Stops the container given by `container_id`, giving it `grace_period` to
+/// exit once signalled before Docker kills it. Should the stop fail, or not
+/// return in good time, the container is killed outright so that teardown
+/// never hangs.
+///
+/// Note: this specifically succeeds even if there is an error; for instance,
+/// if an application container stops running because the application
+/// crashed, we want to call this and continue.
+fn stop_or_kill_container(
+    container_id: &str,
+    docker_host: &str,
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+    grace_period: Duration,
+) {
+    if grace_period.as_secs() > 0
+        && stop_container_within(
+            container_id,
+            docker_host,
+            use_unix_socket,
+            tls,
+            grace_period,
+        )
+        .is_ok()
+    {
+        return;
+    }
+    kill_container(container_id, docker_host, use_unix_socket, Simple::new()).unwrap_or(());
+}
+
+/// Asks Docker to stop the container given by `container_id`, which sends it
+/// SIGTERM and then SIGKILL should it not exit within `grace_period`.
+fn stop_container_within(
+    container_id: &str,
+    docker_host: &str,
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+    grace_period: Duration,
+) -> ToolsetResult<()> {
+    match docker_request(
+        use_unix_socket,
+        tls,
+        docker_host,
+        Method::Post(None),
+        &format!(
+            "/containers/{}/stop?t={}",
+            container_id,
+            grace_period.as_secs()
+        ),
+        // Docker only responds once the container has exited or been killed;
+        // the slack covers the kill itself.
+        Some(grace_period + STOP_REQUEST_SLACK),
+    ) {
+        // 304: the container was already stopped.
+        Ok(_) | Err(DockerRequestFailed { code: 304, .. }) => Ok(()),
+        Err(DockerRequestFailed { .. }) => {
+            Err(DockerError(StopContainerError(container_id.to_string())))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The binding of a container's exposed port to a host port.
+#[derive(Debug, PartialEq)]
+enum PortBinding {
+    /// The exposed port (inner) is bound to the host port.
+    Bound(String, String),
+    /// The exposed port is not yet bound to a host port.
+    Unbound,
+    /// The container does not expose a port matching the selection.
+    NotExposed,
+    /// The container exposes no ports at all; e.g. its image has no `EXPOSE`.
+    NoExposedPorts,
+}
+
+/// Finds the binding of the first of the `exposed_ports` of a container which
+/// matches `selection`, with the given port mappings (`ports`). In Host mode,
+/// the exposed port is the host port.
+fn find_port_binding(
+    network_mode: &NetworkMode,
+    exposed_ports: &Option<HashMap<String, ExposedPort>>,
+    ports: &HashMap<String, Vec<Port>>,
+    selection: PortSelection,
+) -> PortBinding {
+    let exposed_ports = match exposed_ports {
+        Some(exposed_ports) if !exposed_ports.is_empty() => exposed_ports,
+        _ => return PortBinding::NoExposedPorts,
+    };
+    let mut exposed = false;
+    for key in exposed_ports.keys() {
+        let inner_port = match parse_port_key(key) {
+            Some(inner_port) => inner_port.to_string(),
+            None => continue,
+        };
+        if !selection.matches(&inner_port) {
+            continue;
+        }
+        exposed = true;
+        match network_mode {
+            NetworkMode::Bridge => {
+                if let Some(port_mapping) = ports.get(key).and_then(|mappings| mappings.first()) {
+                    return PortBinding::Bound(port_mapping.host_port.clone(), inner_port);
+                }
+            }
+            NetworkMode::Host => return PortBinding::Bound(inner_port.clone(), inner_port),
+        }
+    }
+
+    if exposed {
+        PortBinding::Unbound
+    } else {
+        PortBinding::NotExposed
+    }
+}
+
+/// Gets the port of an exposed port `key` of the form `<port>/<protocol>`;
+/// e.g. `8080` of `8080/tcp`. Malformed keys have no port.
+fn parse_port_key(key: &str) -> Option<&str> {
+    let (port, protocol) = key.split_once('/')?;
+    if protocol.is_empty() || port.parse::<u16>().is_err() {
+        return None;
+    }
+
+    Some(port)
+}
+
+/// Gets the `command_strs` of a benchmark run against `server_host`, limited
+/// to the egress bandwidth `bandwidth_limit` if any; see `benchmarker_command`.
+fn resolve_benchmarker_command(
+    command_strs: &[String],
+    server_host: &str,
+    bandwidth_limit: Option<&str>,
+    network_mode: &NetworkMode,
+) -> ToolsetResult<Vec<String>> {
+    // The command_str we get back is an array of strings that make up the wrk
+    // command; we want to replace `tfb-server` with the IP address
+    let mut command = vec![];
+    for command_str in command_strs {
+        command.push(command_str.replace("tfb-server", server_host));
+    }
+    if let Some(rate) = bandwidth_limit {
+        // Shaping in host mode would shape the host's own interface.
+        if let NetworkMode::Host = network_mode {
+            return Err(BandwidthLimitRequiresBridgeNetworkError);
+        }
+        command = limit_egress_bandwidth(&command, rate);
+    }
+
+    Ok(command)
+}
+
+/// Wraps `command` such that the container first limits its egress bandwidth
+/// to the given `tc` `rate` and then runs `command` in its place.
+/// Note: this requires the container to have the `NET_ADMIN` capability.
+fn limit_egress_bandwidth(command: &[String], rate: &str) -> Vec<String> {
+    let mut wrapped = vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        format!(
+            "tc qdisc add dev eth0 root tbf rate {} burst 32kbit latency 400ms && exec \"$@\"",
+            rate
+        ),
+        "sh".to_string(),
+    ];
+    wrapped.extend_from_slice(command);
+
+    wrapped
+}
+
+/// Gets the command which exits successfully only if a TCP connection to
+/// `host` and `port` is made within `PREFLIGHT_CONNECT_TIMEOUT`. The host and
+/// port are passed as arguments rather than interpolated into the script.
+fn preflight_command(host: &str, port: &str) -> Vec<String> {
+    vec![
+        "timeout".to_string(),
+        PREFLIGHT_CONNECT_TIMEOUT.as_secs().to_string(),
+        "bash".to_string(),
+        "-c".to_string(),
+        "exec 3<>\"/dev/tcp/$0/$1\"".to_string(),
+        host.to_string(),
+        port.to_string(),
+    ]
+}
+
+/// Gets the placeholder values of the env vars which the verifier requires in
+/// database mode but does not use, other than those the image lists as no
+/// longer required in `optional_env`; see `VERIFIER_OPTIONAL_ENV_LABEL`.
+fn verifier_placeholder_env(optional_env: Option<&str>) -> Vec<(&'static str, String)> {
+    let optional: Vec<&str> = optional_env
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .collect();
+    VERIFIER_PLACEHOLDER_ENV
+        .iter()
+        .filter(|(key, _)| !optional.contains(key))
+        .map(|(key, value)| (*key, value.to_string()))
+        .collect()
+}
+
+/// Merges the variables loaded from the verifier env file into the `env` set
+/// for the verifier container; variables set in `env` win on conflict.
+fn merge_verifier_env<'a>(
+    env_file: &'a [(String, String)],
+    env: &'a [(&'a str, String)],
+) -> Vec<(&'a str, &'a str)> {
+    env_file
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .filter(|(key, _)| !env.iter().any(|(explicit, _)| explicit == key))
+        .chain(env.iter().map(|(key, value)| (*key, value.as_str())))
+        .collect()
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::container::{
+        find_port_binding, limit_egress_bandwidth, merge_verifier_env, preflight_command,
+        resolve_benchmarker_command, stop_docker_container_future, verifier_placeholder_env,
+        PortBinding, PortSelection,
+    };
+    use crate::docker::slots::ContainerSlots;
+    use crate::docker::DockerContainerIdFuture;
+    use crate::error::ToolsetError::BandwidthLimitRequiresBridgeNetworkError;
+    use dockurl::container::inspect::{ExposedPort, Port};
+    use dockurl::network::NetworkMode;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[test]
+    fn it_can_limit_egress_bandwidth() {
+        let command = vec!["wrk".to_string(), "http://10.0.0.1:8080/json".to_string()];
+        let wrapped = limit_egress_bandwidth(&command, "100mbit");
+
+        assert_eq!(wrapped[0], "sh");
+        assert!(wrapped[2].starts_with("tc qdisc add dev eth0 root tbf rate 100mbit "));
+        assert!(wrapped[2].ends_with("exec \"$@\""));
+        assert_eq!(&wrapped[4..], command.as_slice());
+    }
+
+    #[test]
+    fn it_prefers_explicit_verifier_env() {
+        let env_file = vec![
+            ("MODE".to_string(), "benchmark".to_string()),
+            ("VERIFY_TIMEOUT".to_string(), "30".to_string()),
+        ];
+        let env = vec![("MODE", "verify".to_string())];
+
+        assert_eq!(
+            merge_verifier_env(&env_file, &env),
+            vec![("VERIFY_TIMEOUT", "30"), ("MODE", "verify")]
+        );
+    }
+
+    #[test]
+    fn it_can_find_port_bindings() {
+        let mut exposed_ports = HashMap::new();
+        exposed_ports.insert("8080/tcp".to_string(), ExposedPort {});
+        let exposed_ports = Some(exposed_ports);
+        let mut ports = HashMap::new();
+        ports.insert("8080/tcp".to_string(), Vec::new());
+
+        // Docker has not yet assigned the host port.
+        assert_eq!(
+            find_port_binding(
+                &NetworkMode::Bridge,
+                &exposed_ports,
+                &ports,
+                PortSelection::Any
+            ),
+            PortBinding::Unbound
+        );
+        assert_eq!(
+            find_port_binding(
+                &NetworkMode::Host,
+                &exposed_ports,
+                &ports,
+                PortSelection::Any
+            ),
+            PortBinding::Bound("8080".to_string(), "8080".to_string())
+        );
+
+        ports.insert(
+            "8080/tcp".to_string(),
+            vec![Port {
+                host_ip: "0.0.0.0".to_string(),
+                host_port: "32768".to_string(),
+            }],
+        );
+        assert_eq!(
+            find_port_binding(
+                &NetworkMode::Bridge,
+                &exposed_ports,
+                &ports,
+                PortSelection::Any
+            ),
+            PortBinding::Bound("32768".to_string(), "8080".to_string())
+        );
+        assert_eq!(
+            find_port_binding(&NetworkMode::Bridge, &None, &ports, PortSelection::Any),
+            PortBinding::NoExposedPorts
+        );
+        assert_eq!(
+            find_port_binding(
+                &NetworkMode::Bridge,
+                &Some(HashMap::new()),
+                &ports,
+                PortSelection::Any
+            ),
+            PortBinding::NoExposedPorts
+        );
+    }
+
+    #[test]
+    fn it_skips_malformed_exposed_ports() {
+        let mut exposed_ports = HashMap::new();
+        exposed_ports.insert("8080".to_string(), ExposedPort {});
+        exposed_ports.insert("http/tcp".to_string(), ExposedPort {});
+        let ports = HashMap::new();
+        assert_eq!(
+            find_port_binding(
+                &NetworkMode::Host,
+                &Some(exposed_ports.clone()),
+                &ports,
+                PortSelection::Any
+            ),
+            PortBinding::NotExposed
+        );
+
+        exposed_ports.insert("8080/tcp".to_string(), ExposedPort {});
+        assert_eq!(
+            find_port_binding(
+                &NetworkMode::Host,
+                &Some(exposed_ports),
+                &ports,
+                PortSelection::Any
+            ),
+            PortBinding::Bound("8080".to_string(), "8080".to_string())
+        );
+    }
+
+    #[test]
+    fn it_can_select_which_port_to_bind() {
+        let mut exposed_ports = HashMap::new();
+        exposed_ports.insert("8080/tcp".to_string(), ExposedPort {});
+        exposed_ports.insert("8443/tcp".to_string(), ExposedPort {});
+        let exposed_ports = Some(exposed_ports);
+        let mut ports = HashMap::new();
+        for (inner_port, host_port) in &[("8080/tcp", "32768"), ("8443/tcp", "32769")] {
+            ports.insert(
+                inner_port.to_string(),
+                vec![Port {
+                    host_ip: "0.0.0.0".to_string(),
+                    host_port: host_port.to_string(),
+                }],
+            );
+        }
+
+        assert_eq!(
+            find_port_binding(
+                &NetworkMode::Bridge,
+                &exposed_ports,
+                &ports,
+                PortSelection::Only(8443)
+            ),
+            PortBinding::Bound("32769".to_string(), "8443".to_string())
+        );
+        assert_eq!(
+            find_port_binding(
+                &NetworkMode::Bridge,
+                &exposed_ports,
+                &ports,
+                PortSelection::AnyExcept(8443)
+            ),
+            PortBinding::Bound("32768".to_string(), "8080".to_string())
+        );
+        assert_eq!(
+            find_port_binding(
+                &NetworkMode::Bridge,
+                &exposed_ports,
+                &ports,
+                PortSelection::Only(9443)
+            ),
+            PortBinding::NotExposed
+        );
+    }
+
+    #[test]
+    fn it_can_stop_a_torn_down_container_repeatedly() {
+        let slots = ContainerSlots::new(1);
+        let mut container = DockerContainerIdFuture::new("localhost:2375");
+        container.hold(slots.acquire());
+        let container = Arc::new(Mutex::new(container));
+
+        for _ in 0..2 {
+            stop_docker_container_future(
+                false,
+                None,
+                true,
+                true,
+                Duration::from_secs(0),
+                Duration::from_secs(0),
+                &container,
+            );
+        }
+        assert!(container.lock().unwrap().is_torn_down());
+        drop(slots.acquire());
+    }
+
+    #[test]
+    fn it_omits_placeholders_the_verifier_no_longer_requires() {
+        let keys = |env: Vec<(&'static str, String)>| {
+            env.into_iter().map(|(key, _)| key).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            keys(verifier_placeholder_env(None)),
+            vec!["PORT", "ENDPOINT", "TEST_TYPE"]
+        );
+        assert_eq!(
+            keys(verifier_placeholder_env(Some("PORT, TEST_TYPE"))),
+            vec!["ENDPOINT"]
+        );
+    }
+
+    #[test]
+    fn it_probes_the_benchmark_target_without_interpolating_it() {
+        let command = preflight_command("tfb-server", "8443");
+        assert_eq!(command[..4], ["timeout", "5", "bash", "-c"]);
+        assert_eq!(command[4], "exec 3<>\"/dev/tcp/$0/$1\"");
+        assert_eq!(command[5..], ["tfb-server", "8443"]);
+    }
+
+    #[test]
+    fn it_resolves_the_command_each_benchmarker_runs() {
+        let wrk = vec!["wrk".to_string(), "http://tfb-server:8080/json".to_string()];
+        assert_eq!(
+            resolve_benchmarker_command(&wrk, "10.0.0.1", None, &NetworkMode::Host).unwrap(),
+            vec!["wrk", "http://10.0.0.1:8080/json"]
+        );
+
+        let limited =
+            resolve_benchmarker_command(&wrk, "10.0.0.1", Some("1gbit"), &NetworkMode::Bridge)
+                .unwrap();
+        assert_eq!(limited[..2], ["sh", "-c"]);
+        assert!(limited[2].starts_with("tc qdisc add dev eth0 root tbf rate 1gbit "));
+        assert_eq!(limited[4..], ["wrk", "http://10.0.0.1:8080/json"]);
+
+        match resolve_benchmarker_command(&wrk, "10.0.0.1", Some("1gbit"), &NetworkMode::Host) {
+            Err(BandwidthLimitRequiresBridgeNetworkError) => {}
+            result => panic!(
+                "expected BandwidthLimitRequiresBridgeNetworkError; got {:?}",
+                result
+            ),
         }
     }
 }
@@ -1,68 +1,246 @@
 use crate::benchmarker::modes;
-use crate::docker::network::{get_network_id, get_tfb_network_id};
+use crate::config::{Named, Test};
+use crate::docker::host_config::{GpuDevices, GpuRequest, PortRange, RestartPolicy};
+use crate::docker::listener::demultiplexer::AttachStreams;
+use crate::docker::listener::log_buffer::LogBufferLimits;
+use crate::docker::network::{
+    detach_stale_endpoints, get_network_id, get_tfb_network_id, parse_network_mode,
+};
+use crate::docker::readiness::{Backoff, ReadinessProbe};
+use crate::docker::wrk_command::WrkOverrides;
+use crate::error::ToolsetError::{
+    ConflictingDatabaseHosts, InvalidConcurrencyLevels, InvalidSeccompProfileError,
+    InvalidVerifierEnvFile, MemoryReservationExceedsLimitError, VolumeHostPathNotFoundError,
+};
+use crate::error::ToolsetResult;
 use crate::io::{create_results_dir, Logger};
 use crate::options;
 use dockurl::network::NetworkMode::{Bridge, Host};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{env, fs};
+
+/// A Docker daemon on which a benchmarker client container is run.
+#[derive(Debug, Clone)]
+pub struct BenchmarkerReplica {
+    pub docker_host: String,
+    pub network_id: String,
+}
+
+/// The certificates with which remote Docker daemons are reached over TLS;
+/// e.g. daemons run with `--tlsverify`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DockerTls {
+    /// The CA certificate which verifies the daemons.
+    pub ca_cert: PathBuf,
+    /// The client certificate presented to the daemons.
+    pub cert: PathBuf,
+    /// The key of the client certificate.
+    pub key: PathBuf,
+}
+
+/// The Docker daemons of a run and how they are reached, read from the
+/// arguments without contacting any of them.
+#[derive(Debug, Clone)]
+pub struct DockerEnvironment<'a> {
+    pub use_unix_socket: bool,
+    /// The certificates with which the Docker daemons are reached over TLS,
+    /// if they are; never when `use_unix_socket` is set.
+    pub docker_tls: Option<DockerTls>,
+    pub server_docker_host: String,
+    pub database_docker_host: String,
+    pub client_docker_host: String,
+    /// The Docker hosts on which benchmarker client containers are run.
+    pub replica_docker_hosts: Vec<String>,
+    pub network_mode: dockurl::network::NetworkMode,
+    /// The directory from which to build the verifier image, if any.
+    pub verifier_build_context: Option<&'a str>,
+}
+impl<'a> DockerEnvironment<'a> {
+    pub fn new(matches: &'a clap::ArgMatches) -> ToolsetResult<Self> {
+        // By default, we communicate with docker over a unix socket.
+        let use_unix_socket = if cfg!(windows) {
+            // Even if we want to run locally, Windows cannot communicate over a
+            // Unix socket, so don't bother or cURL will panic.
+            false
+        } else {
+            // However, in benchmarking with a multi-machine setup, we want to
+            // communicate over TCP (also, Windows can only communicate over
+            // TCP as of this writing).
+            matches.value_of(options::args::SERVER_HOST).unwrap()
+                == options::args::SERVER_HOST_DEFAULT
+        };
+        let docker_tls = if use_unix_socket {
+            None
+        } else {
+            parse_docker_tls(matches)
+        };
+        // Docker listens for TLS on its own port.
+        let docker_port = match docker_tls {
+            Some(_) => 2376,
+            None => 2375,
+        };
+
+        let server_docker_host = format!(
+            "{}:{}",
+            matches.value_of(options::args::SERVER_DOCKER_HOST).unwrap(),
+            docker_port
+        );
+        let database_docker_host = format!(
+            "{}:{}",
+            matches
+                .value_of(options::args::DATABASE_DOCKER_HOST)
+                .unwrap(),
+            docker_port
+        );
+        let client_docker_host = format!(
+            "{}:{}",
+            matches.value_of(options::args::CLIENT_DOCKER_HOST).unwrap(),
+            docker_port
+        );
+        let replica_docker_hosts = match matches.values_of(options::args::BENCHMARKER_REPLICAS) {
+            Some(hosts) => hosts
+                .map(|host| format!("{}:{}", host, docker_port))
+                .collect(),
+            None => vec![client_docker_host.clone()],
+        };
+
+        Ok(Self {
+            use_unix_socket,
+            docker_tls,
+            server_docker_host,
+            database_docker_host,
+            client_docker_host,
+            replica_docker_hosts,
+            network_mode: parse_network_mode(
+                matches.value_of(options::args::NETWORK_MODE).unwrap(),
+            )?,
+            verifier_build_context: matches.value_of(options::args::VERIFIER_BUILD_CONTEXT),
+        })
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct DockerConfig<'a> {
     pub use_unix_socket: bool,
+    /// The certificates with which the Docker daemons are reached over TLS,
+    /// if they are.
+    pub docker_tls: Option<DockerTls>,
     pub server_docker_host: String,
     pub server_host: &'a str,
+    /// The domain name of the application container; defaults to
+    /// `server_host`.
+    pub server_domain: Option<&'a str>,
+    /// Server hosts to use in place of `server_host` for given test types.
+    pub server_host_overrides: HashMap<String, String>,
     pub server_network_id: String,
     pub database_docker_host: String,
     pub database_host: &'a str,
+    /// Database hosts to use in place of `database_host` for given test
+    /// types.
+    pub database_host_overrides: HashMap<String, String>,
     pub database_network_id: String,
     pub client_docker_host: String,
     pub client_host: &'a str,
     pub client_network_id: String,
+    pub benchmarker_replicas: Vec<BenchmarkerReplica>,
     pub network_mode: dockurl::network::NetworkMode,
-    pub concurrency_levels: String,
-    pub pipeline_concurrency_levels: String,
+    pub concurrency_levels: Vec<u32>,
+    pub pipeline_concurrency_levels: Vec<u32>,
     pub query_levels: String,
     pub cached_query_levels: String,
     pub duration: u32,
     pub results_name: &'a str,
     pub results_environment: &'a str,
     pub results_upload_uri: Option<&'a str>,
+    /// Where to write the results of a benchmark run in the schema of the
+    /// official `results.json`, if anywhere.
+    pub export_tfb_results: Option<&'a str>,
     pub logger: Logger,
     pub clean_up: bool,
+    pub keep_failed: bool,
+    pub capture_logs: bool,
+    pub attach_streams: AttachStreams,
+    pub allocate_tty: bool,
+    pub prune_images: bool,
+    pub restart_policy: RestartPolicy,
+    /// The host ports within which to publish the ports of the application
+    /// and database containers; any ephemeral port when unset.
+    pub host_port_range: Option<PortRange>,
+    pub use_command_cache: bool,
+    pub refresh_command_cache: bool,
+    pub stop_timeout: Duration,
+    /// How long a container is given to exit once signalled to stop before it
+    /// is killed; it is killed outright when zero.
+    pub stop_grace_period: Duration,
+    pub port_binding_retries: u32,
+    pub port_binding_retry_interval: Duration,
+    pub database_backoff: Backoff,
+    pub log_buffer_limits: LogBufferLimits,
+    pub wrk_overrides: WrkOverrides,
+    pub benchmark_preflight: bool,
+    /// The most application container groups which may run at once.
+    pub max_concurrent: usize,
+    pub preflight_retries: u32,
+    pub preflight_retry_interval: Duration,
+    /// Variables loaded from the verifier env file, if any.
+    pub verifier_env: Vec<(String, String)>,
+    /// The directory from which to build the verifier image, if any.
+    pub verifier_build_context: Option<&'a str>,
+    /// Whether to omit the env vars which older verifier images require when
+    /// checking databases but do not use, regardless of the image's labels.
+    pub omit_verifier_placeholders: bool,
+    pub run_timeout: Option<Duration>,
+    pub max_socket_error_ratio: Option<f64>,
+    pub record_docker_events: bool,
+    pub readiness_probe: ReadinessProbe,
+    pub readiness_interval: Duration,
+    pub readiness_timeout: Duration,
+    pub bandwidth_limit: Option<&'a str>,
+    pub databases: Vec<String>,
+    pub volumes: Vec<(String, String)>,
+    pub gpus: Option<GpuRequest>,
+    pub cap_add: Vec<String>,
+    pub cap_drop: Vec<String>,
+    /// Security options; a `seccomp=<path>` option holds the profile read
+    /// from that path, as the Engine API only accepts an inline profile.
+    pub security_opt: Vec<String>,
+    pub memory_limit_bytes: Option<u64>,
+    pub memory_reservation_bytes: Option<u64>,
+    pub cpu_shares: Option<u64>,
+    /// The CPUs to which the benchmarker container is pinned, if any.
+    pub benchmarker_cpuset: Option<&'a str>,
 }
 impl<'a> DockerConfig<'a> {
-    pub fn new(matches: &'a clap::ArgMatches) -> Self {
-        let server_docker_host = format!(
-            "{}:2375",
-            matches.value_of(options::args::SERVER_DOCKER_HOST).unwrap()
-        );
-        let database_docker_host = format!(
-            "{}:2375",
-            matches
-                .value_of(options::args::DATABASE_DOCKER_HOST)
-                .unwrap()
-        );
-        let client_docker_host = format!(
-            "{}:2375",
-            matches.value_of(options::args::CLIENT_DOCKER_HOST).unwrap()
-        );
+    pub fn new(matches: &'a clap::ArgMatches) -> ToolsetResult<Self> {
+        let DockerEnvironment {
+            use_unix_socket,
+            docker_tls,
+            server_docker_host,
+            database_docker_host,
+            client_docker_host,
+            replica_docker_hosts,
+            network_mode,
+            verifier_build_context,
+        } = DockerEnvironment::new(matches)?;
         let server_host = matches.value_of(options::args::SERVER_HOST).unwrap();
         let database_host = matches.value_of(options::args::DATABASE_HOST).unwrap();
         let client_host = matches.value_of(options::args::CLIENT_HOST).unwrap();
-        let network_mode = match matches.value_of(options::args::NETWORK_MODE).unwrap() {
-            options::network_modes::HOST => Host,
-            _ => Bridge,
-        };
         let duration =
             str::parse::<u32>(matches.value_of(options::args::DURATION).unwrap()).unwrap();
-        let concurrency_levels = matches
-            .values_of(options::args::CONCURRENCY_LEVELS)
-            .unwrap()
-            .collect::<Vec<&str>>()
-            .join(",");
-        let pipeline_concurrency_levels = matches
-            .values_of(options::args::PIPELINE_CONCURRENCY_LEVELS)
-            .unwrap()
-            .collect::<Vec<&str>>()
-            .join(",");
+        let concurrency_levels = parse_concurrency_levels(
+            matches
+                .values_of(options::args::CONCURRENCY_LEVELS)
+                .unwrap()
+                .collect(),
+        )?;
+        let pipeline_concurrency_levels = parse_concurrency_levels(
+            matches
+                .values_of(options::args::PIPELINE_CONCURRENCY_LEVELS)
+                .unwrap()
+                .collect(),
+        )?;
 
         let query_levels = matches
             .values_of(options::args::QUERY_LEVELS)
@@ -75,41 +253,71 @@ impl<'a> DockerConfig<'a> {
             .collect::<Vec<&str>>()
             .join(",");
 
-        // By default, we communicate with docker over a unix socket.
-        let use_unix_socket = if cfg!(windows) {
-            // Even if we want to run locally, Windows cannot communicate over a
-            // Unix socket, so don't bother or cURL will panic.
-            false
-        } else {
-            // However, in benchmarking with a multi-machine setup, we want to
-            // communicate over TCP (also, Windows can only communicate over
-            // TCP as of this writing).
-            server_host == options::args::SERVER_HOST_DEFAULT
-        };
-
-        let logger = match matches.value_of(options::args::MODE).unwrap() {
+        let mut logger = match matches.value_of(options::args::MODE).unwrap() {
             // We don't want to log to disk in CICD.
             modes::CICD => Logger::default(),
-            &_ => Logger::in_dir(&create_results_dir().unwrap()),
+            &_ => Logger::in_dir(&create_results_dir()?),
         };
+        logger.verbose = matches.is_present(options::args::VERBOSE);
 
+        let mtu = matches
+            .value_of(options::args::MTU)
+            .map(|mtu| str::parse::<u32>(mtu).unwrap());
+        let tls = docker_tls.as_ref();
         // There is a chance this is a hack, but it seems that these two
         // networks are always available out of the box for Docker.
         let server_network_id = match &network_mode {
-            Bridge => get_tfb_network_id(use_unix_socket, &database_docker_host),
+            Bridge => get_tfb_network_id(use_unix_socket, tls, &database_docker_host, mtu),
             Host => get_network_id(use_unix_socket, &server_docker_host, "host"),
-        }
-        .unwrap();
+        }?;
         let database_network_id = match &network_mode {
-            Bridge => get_tfb_network_id(use_unix_socket, &database_docker_host),
+            Bridge => get_tfb_network_id(use_unix_socket, tls, &database_docker_host, mtu),
             Host => get_network_id(use_unix_socket, &database_docker_host, "host"),
-        }
-        .unwrap();
+        }?;
         let client_network_id = match &network_mode {
-            Bridge => get_tfb_network_id(use_unix_socket, &database_docker_host),
+            Bridge => get_tfb_network_id(use_unix_socket, tls, &database_docker_host, mtu),
             Host => get_network_id(use_unix_socket, &client_docker_host, "host"),
+        }?;
+        // The TFBNetwork is never removed, so a run which crashed before
+        // tearing down its containers may have left endpoints on it.
+        if let Bridge = &network_mode {
+            if matches.is_present(options::args::REUSE_NETWORK) {
+                for endpoint in detach_stale_endpoints(
+                    use_unix_socket,
+                    tls,
+                    &database_docker_host,
+                    &client_network_id,
+                )? {
+                    logger.log(format!(
+                        "Detached stale endpoint {} from TFBNetwork",
+                        endpoint
+                    ))?;
+                }
+            }
         }
-        .unwrap();
+
+        let benchmarker_replicas = if matches.is_present(options::args::BENCHMARKER_REPLICAS) {
+            replica_docker_hosts
+                .into_iter()
+                .map(|docker_host| {
+                    // Each replica joins the network on its own Docker host; a
+                    // network ID resolved on another host does not exist there.
+                    let network_id = match &network_mode {
+                        Bridge => get_tfb_network_id(use_unix_socket, tls, &docker_host, mtu)?,
+                        Host => get_network_id(use_unix_socket, &docker_host, "host")?,
+                    };
+                    Ok(BenchmarkerReplica {
+                        docker_host,
+                        network_id,
+                    })
+                })
+                .collect::<ToolsetResult<Vec<BenchmarkerReplica>>>()?
+        } else {
+            vec![BenchmarkerReplica {
+                docker_host: client_docker_host.clone(),
+                network_id: client_network_id.clone(),
+            }]
+        };
 
         let results_name = matches.value_of(options::args::RESULTS_NAME).unwrap();
         let results_environment = matches
@@ -120,11 +328,112 @@ impl<'a> DockerConfig<'a> {
             Some(str) => Some(str),
         };
         let clean_up = matches.is_present(options::args::DOCKER_CLEANUP);
+        let keep_failed = matches.is_present(options::args::KEEP_FAILED);
+        let capture_logs = matches.is_present(options::args::CAPTURE_LOGS);
+        let allocate_tty = !matches.is_present(options::args::NO_TTY);
+        let prune_images = !matches.is_present(options::args::SKIP_IMAGE_PRUNE);
+        let restart_policy = matches
+            .value_of(options::args::RESTART_POLICY)
+            .unwrap()
+            .parse::<RestartPolicy>()
+            .unwrap();
+        let use_command_cache = matches.is_present(options::args::USE_COMMAND_CACHE);
+        let refresh_command_cache = matches.is_present(options::args::REFRESH_COMMAND_CACHE);
+        let bandwidth_limit = matches.value_of(options::args::BANDWIDTH_LIMIT);
+        let databases = match matches.values_of(options::args::DATABASES) {
+            Some(databases) => databases.map(str::to_string).collect(),
+            None => Vec::default(),
+        };
+        let volumes = match matches.values_of(options::args::DATABASE_VOLUMES) {
+            Some(volumes) => parse_volumes(
+                volumes.collect(),
+                // Host paths can only be checked when the daemon is local.
+                matches.value_of(options::args::DATABASE_DOCKER_HOST)
+                    == Some(options::args::DOCKER_HOST_DEFAULT),
+            )?,
+            None => Vec::default(),
+        };
+        let memory_limit_bytes = matches
+            .value_of(options::args::MEMORY_LIMIT)
+            .map(|bytes| str::parse::<u64>(bytes).unwrap());
+        let memory_reservation_bytes = matches
+            .value_of(options::args::MEMORY_RESERVATION)
+            .map(|bytes| str::parse::<u64>(bytes).unwrap());
+        validate_memory_reservation(memory_reservation_bytes, memory_limit_bytes)?;
+        let verifier_env = match matches.value_of(options::args::VERIFIER_ENV_FILE) {
+            Some(path) => parse_env_file(path, &fs::read_to_string(path)?)?,
+            None => Vec::default(),
+        };
+        let gpus = matches
+            .value_of(options::args::GPUS)
+            .map(|devices| GpuRequest {
+                devices: devices.parse::<GpuDevices>().unwrap(),
+                capabilities: match matches.values_of(options::args::GPU_CAPABILITIES) {
+                    Some(capabilities) => capabilities.map(str::to_string).collect(),
+                    None => Vec::default(),
+                },
+            });
+        let record_docker_events = matches.is_present(options::args::RECORD_DOCKER_EVENTS);
+        let readiness_probe = matches
+            .value_of(options::args::READINESS_PROBE)
+            .unwrap()
+            .parse::<ReadinessProbe>()
+            .unwrap();
+        let readiness_interval = Duration::from_millis(
+            str::parse::<u64>(matches.value_of(options::args::READINESS_INTERVAL).unwrap())
+                .unwrap(),
+        );
+        let readiness_timeout = Duration::from_secs(
+            str::parse::<u64>(matches.value_of(options::args::READINESS_TIMEOUT).unwrap()).unwrap(),
+        );
+        let stop_timeout = Duration::from_secs(
+            str::parse::<u64>(matches.value_of(options::args::STOP_TIMEOUT).unwrap()).unwrap(),
+        );
+        let port_binding_retries = str::parse::<u32>(
+            matches
+                .value_of(options::args::PORT_BINDING_RETRIES)
+                .unwrap(),
+        )
+        .unwrap();
+        let port_binding_retry_interval = Duration::from_millis(
+            str::parse::<u64>(
+                matches
+                    .value_of(options::args::PORT_BINDING_RETRY_INTERVAL)
+                    .unwrap(),
+            )
+            .unwrap(),
+        );
+        let log_buffer_limits = LogBufferLimits {
+            max_bytes: str::parse::<usize>(
+                matches.value_of(options::args::LOG_BUFFER_BYTES).unwrap(),
+            )
+            .unwrap(),
+            max_lines: str::parse::<usize>(
+                matches.value_of(options::args::LOG_BUFFER_LINES).unwrap(),
+            )
+            .unwrap(),
+        };
+        let wrk_overrides = WrkOverrides {
+            duration: matches
+                .value_of(options::args::WRK_DURATION)
+                .map(|duration| str::parse::<u32>(duration).unwrap()),
+            threads: matches
+                .value_of(options::args::WRK_THREADS)
+                .map(|threads| str::parse::<u32>(threads).unwrap()),
+            connections: matches
+                .value_of(options::args::WRK_CONNECTIONS)
+                .map(|connections| str::parse::<u32>(connections).unwrap()),
+        };
+        let run_timeout = matches
+            .value_of(options::args::RUN_TIMEOUT)
+            .map(|timeout| Duration::from_secs(str::parse::<u64>(timeout).unwrap()));
 
-        Self {
+        Ok(Self {
             use_unix_socket,
+            docker_tls,
             server_docker_host,
             server_host,
+            server_domain: matches.value_of(options::args::SERVER_DOMAIN),
             server_network_id,
             database_docker_host,
             database_host,
@@ -132,6 +441,7 @@ impl<'a> DockerConfig<'a> {
             client_docker_host,
             client_host,
             client_network_id,
+            benchmarker_replicas,
             network_mode,
             concurrency_levels,
             pipeline_concurrency_levels,
@@ -142,7 +452,522 @@ impl<'a> DockerConfig<'a> {
             results_name,
             results_environment,
             results_upload_uri,
+            export_tfb_results: matches.value_of(options::args::EXPORT_TFB_RESULTS),
             clean_up,
+            keep_failed,
+            capture_logs,
+            attach_streams: matches
+                .value_of(options::args::ATTACH_STREAMS)
+                .unwrap()
+                .parse::<AttachStreams>()
+                .unwrap(),
+            allocate_tty,
+            prune_images,
+            restart_policy,
+            host_port_range: matches
+                .value_of(options::args::HOST_PORT_RANGE)
+                .map(|range| range.parse::<PortRange>().unwrap()),
+            use_command_cache,
+            refresh_command_cache,
+            stop_timeout,
+            stop_grace_period: Duration::from_secs(
+                str::parse::<u64>(matches.value_of(options::args::STOP_GRACE_PERIOD).unwrap())
+                    .unwrap(),
+            ),
+            port_binding_retries,
+            port_binding_retry_interval,
+            database_backoff: Backoff {
+                initial: Duration::from_millis(
+                    str::parse::<u64>(
+                        matches
+                            .value_of(options::args::DATABASE_READINESS_BACKOFF)
+                            .unwrap(),
+                    )
+                    .unwrap(),
+                ),
+                max: Duration::from_millis(
+                    str::parse::<u64>(
+                        matches
+                            .value_of(options::args::DATABASE_READINESS_MAX_BACKOFF)
+                            .unwrap(),
+                    )
+                    .unwrap(),
+                ),
+                timeout: Duration::from_secs(
+                    str::parse::<u64>(
+                        matches
+                            .value_of(options::args::DATABASE_READINESS_TIMEOUT)
+                            .unwrap(),
+                    )
+                    .unwrap(),
+                ),
+            },
+            log_buffer_limits,
+            wrk_overrides,
+            benchmark_preflight: matches.is_present(options::args::BENCHMARK_PREFLIGHT),
+            max_concurrent: str::parse::<usize>(
+                matches.value_of(options::args::MAX_CONCURRENT).unwrap(),
+            )
+            .unwrap(),
+            preflight_retries: str::parse::<u32>(
+                matches.value_of(options::args::PREFLIGHT_RETRIES).unwrap(),
+            )
+            .unwrap(),
+            preflight_retry_interval: Duration::from_millis(
+                str::parse::<u64>(
+                    matches
+                        .value_of(options::args::PREFLIGHT_RETRY_INTERVAL)
+                        .unwrap(),
+                )
+                .unwrap(),
+            ),
+            verifier_env,
+            verifier_build_context,
+            omit_verifier_placeholders: matches
+                .is_present(options::args::OMIT_VERIFIER_PLACEHOLDERS),
+            run_timeout,
+            max_socket_error_ratio: matches
+                .value_of(options::args::MAX_SOCKET_ERROR_RATIO)
+                .map(|ratio| str::parse::<f64>(ratio).unwrap()),
+            record_docker_events,
+            readiness_probe,
+            readiness_interval,
+            readiness_timeout,
+            bandwidth_limit,
+            databases,
+            volumes,
+            gpus,
+            cap_add: values_of(matches, options::args::CAP_ADD),
+            cap_drop: values_of(matches, options::args::CAP_DROP),
+            security_opt: parse_security_opts(values_of(matches, options::args::SECURITY_OPT))?,
+            memory_limit_bytes,
+            memory_reservation_bytes,
+            cpu_shares: matches
+                .value_of(options::args::CPU_SHARES)
+                .map(|shares| str::parse::<u64>(shares).unwrap()),
+            benchmarker_cpuset: matches.value_of(options::args::BENCHMARKER_CPUSET),
+            server_host_overrides: parse_host_overrides(values_of(
+                matches,
+                options::args::SERVER_HOST_OVERRIDES,
+            )),
+            database_host_overrides: parse_host_overrides(values_of(
+                matches,
+                options::args::DATABASE_HOST_OVERRIDES,
+            )),
+        })
+    }
+
+    /// Gets the host of the application server for the given `test_type`.
+    pub fn server_host_for(&self, test_type: &str) -> &str {
+        self.server_host_overrides
+            .get(test_type)
+            .map_or(self.server_host, String::as_str)
+    }
+
+    /// Gets the host of the database server for the given `test_type`; e.g. a
+    /// read replica for `query` and the primary for `update`.
+    pub fn database_host_for(&self, test_type: &str) -> &str {
+        self.database_host_overrides
+            .get(test_type)
+            .map_or(self.database_host, String::as_str)
+    }
+
+    /// Gets the host of the database server for the application container of
+    /// `test`. In the host network mode, the container reaches the database
+    /// as `tfb-database`, which names a single host for all of the test's
+    /// test types; so those test types must not be given different hosts. In
+    /// the bridge network mode, the container reaches the database container
+    /// itself.
+    pub fn database_host_for_test(&self, test: &Test) -> ToolsetResult<&str> {
+        match self.network_mode {
+            Bridge => Ok(self.database_host),
+            Host => shared_host(
+                &test.get_name(),
+                test.urls
+                    .keys()
+                    .map(|test_type| self.database_host_for(test_type))
+                    .collect(),
+            )
+            .map(|host| host.unwrap_or(self.database_host)),
+        }
+    }
+}
+
+/// Serializes the given concurrency `levels` into the comma-separated form
+/// expected by the verifier's environment variables.
+pub fn levels_to_env(levels: &[u32]) -> String {
+    levels
+        .iter()
+        .map(|level| level.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+//
+// PRIVATES
+//
+
+/// Gets the values given for the option `name`, if any.
+fn values_of(matches: &clap::ArgMatches, name: &str) -> Vec<String> {
+    match matches.values_of(name) {
+        Some(values) => values.map(str::to_string).collect(),
+        None => Vec::default(),
+    }
+}
+
+/// Gets the certificates with which to reach the Docker daemons over TLS, if
+/// it is requested by any of the TLS options or, as with the docker CLI, by
+/// `DOCKER_TLS_VERIFY`. Those not given as options are read by their usual
+/// names from `DOCKER_CERT_PATH`, or else from `~/.docker`.
+fn parse_docker_tls(matches: &clap::ArgMatches) -> Option<DockerTls> {
+    let tls_verify = matches!(
+        env::var("DOCKER_TLS_VERIFY"),
+        Ok(verify) if !verify.is_empty() && verify != "0"
+    );
+    let tls_options = [
+        options::args::TLS_CA_CERT,
+        options::args::TLS_CERT,
+        options::args::TLS_KEY,
+    ];
+    if !tls_verify && !tls_options.iter().any(|name| matches.is_present(name)) {
+        return None;
+    }
+
+    let cert_path = match env::var("DOCKER_CERT_PATH") {
+        Ok(cert_path) if !cert_path.is_empty() => PathBuf::from(cert_path),
+        _ => dirs::home_dir().unwrap_or_default().join(".docker"),
+    };
+    let path_of = |name: &str, file_name: &str| match matches.value_of(name) {
+        Some(path) => PathBuf::from(path),
+        None => cert_path.join(file_name),
+    };
+
+    Some(DockerTls {
+        ca_cert: path_of(options::args::TLS_CA_CERT, "ca.pem"),
+        cert: path_of(options::args::TLS_CERT, "cert.pem"),
+        key: path_of(options::args::TLS_KEY, "key.pem"),
+    })
+}
+
+/// Gets the one host among `hosts`, if any, which the test types of the test
+/// named `test` were given; different hosts are an error.
+fn shared_host<'h>(test: &str, mut hosts: Vec<&'h str>) -> ToolsetResult<Option<&'h str>> {
+    hosts.sort_unstable();
+    hosts.dedup();
+    match hosts.as_slice() {
+        [] => Ok(None),
+        [host] => Ok(Some(host)),
+        _ => Err(ConflictingDatabaseHosts {
+            test: test.to_string(),
+            hosts: hosts.join(", "),
+        }),
+    }
+}
+
+/// Parses the given concurrency level `values`, each of which may itself be a
+/// comma-separated list, into positive integers.
+fn parse_concurrency_levels(values: Vec<&str>) -> ToolsetResult<Vec<u32>> {
+    let mut levels = Vec::new();
+    for value in values {
+        for token in value.split(',') {
+            match token.trim().parse::<u32>() {
+                Ok(level) if level > 0 => levels.push(level),
+                _ => {
+                    return Err(InvalidConcurrencyLevels {
+                        value: if token.trim().is_empty() {
+                            value.to_string()
+                        } else {
+                            token.to_string()
+                        },
+                    })
+                }
+            }
+        }
+    }
+
+    Ok(levels)
+}
+
+/// Parses the given `host_path:container_path` volume `values`; the format
+/// is validated when the options are parsed. When `check_host_paths` is set,
+/// every host path must exist, because Docker would otherwise silently
+/// create an empty directory in its place.
+fn parse_volumes(
+    values: Vec<&str>,
+    check_host_paths: bool,
+) -> ToolsetResult<Vec<(String, String)>> {
+    let mut volumes = Vec::new();
+    for value in values {
+        if let Some((host_path, container_path)) = value.split_once(':') {
+            if check_host_paths && !Path::new(host_path).exists() {
+                return Err(VolumeHostPathNotFoundError(host_path.to_string()));
+            }
+            volumes.push((host_path.to_string(), container_path.to_string()));
+        }
+    }
+
+    Ok(volumes)
+}
+
+/// Validates that the memory `reservation`, a soft limit, does not exceed the
+/// hard memory `limit`; Docker would otherwise refuse to create the
+/// container.
+fn validate_memory_reservation(reservation: Option<u64>, limit: Option<u64>) -> ToolsetResult<()> {
+    match (reservation, limit) {
+        (Some(reservation), Some(limit)) if reservation > limit => {
+            Err(MemoryReservationExceedsLimitError { reservation, limit })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Parses the given `test_type=host` override `values`; the format is
+/// validated when the options are parsed.
+fn parse_host_overrides(values: Vec<String>) -> HashMap<String, String> {
+    values
+        .iter()
+        .filter_map(|value| value.split_once('='))
+        .map(|(test_type, host)| (test_type.to_string(), host.to_string()))
+        .collect()
+}
+
+/// Parses the `KEY=VALUE` lines of the env file at `path` with the given
+/// `contents`. As with Docker's `--env-file`, blank lines and lines starting
+/// with `#` are ignored and values are taken verbatim.
+fn parse_env_file(path: &str, contents: &str) -> ToolsetResult<Vec<(String, String)>> {
+    let mut env = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        match trimmed.split_once('=') {
+            Some((key, value)) if is_env_key(key) => env.push((key.to_string(), value.to_string())),
+            _ => {
+                return Err(InvalidVerifierEnvFile {
+                    path: path.to_string(),
+                    line_number: index + 1,
+                    line: line.to_string(),
+                })
+            }
+        }
+    }
+
+    Ok(env)
+}
+
+/// Parses the given security option `values`, replacing the profile path of a
+/// `seccomp=<path>` option with the contents of that profile, as the docker
+/// CLI does. `seccomp=unconfined` is passed through.
+fn parse_security_opts(values: Vec<String>) -> ToolsetResult<Vec<String>> {
+    values
+        .into_iter()
+        .map(|value| match value.strip_prefix("seccomp=") {
+            Some(path) if path != "unconfined" => {
+                let invalid = |reason: String| InvalidSeccompProfileError {
+                    path: path.to_string(),
+                    reason,
+                };
+                let contents = fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+                let profile = serde_json::from_str::<serde_json::Value>(&contents)
+                    .map_err(|e| invalid(e.to_string()))?;
+                Ok(format!("seccomp={}", profile))
+            }
+            _ => Ok(value),
+        })
+        .collect()
+}
+
+/// Whether `key` is a valid environment variable name.
+fn is_env_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::docker_config::{
+        levels_to_env, parse_concurrency_levels, parse_docker_tls, parse_env_file,
+        parse_host_overrides, parse_security_opts, parse_volumes, shared_host,
+        validate_memory_reservation, DockerTls,
+    };
+    use crate::error::ToolsetError::{
+        ConflictingDatabaseHosts, InvalidConcurrencyLevels, InvalidSeccompProfileError,
+        InvalidVerifierEnvFile, MemoryReservationExceedsLimitError, VolumeHostPathNotFoundError,
+    };
+    use crate::options;
+    use std::env;
+    use std::path::PathBuf;
+
+    #[test]
+    fn it_can_parse_concurrency_levels() {
+        let levels = parse_concurrency_levels(vec!["16", "32,64"]).unwrap();
+        assert_eq!(levels, vec![16, 32, 64]);
+        assert_eq!(levels_to_env(&levels), "16,32,64");
+    }
+
+    #[test]
+    fn it_will_reject_invalid_concurrency_levels() {
+        match parse_concurrency_levels(vec!["16", "3x2"]) {
+            Err(InvalidConcurrencyLevels { value }) => assert_eq!(value, "3x2"),
+            other => panic!("expected InvalidConcurrencyLevels; got {:?}", other),
+        }
+        match parse_concurrency_levels(vec!["16,32,"]) {
+            Err(InvalidConcurrencyLevels { value }) => assert_eq!(value, "16,32,"),
+            other => panic!("expected InvalidConcurrencyLevels; got {:?}", other),
+        }
+        assert!(parse_concurrency_levels(vec!["0"]).is_err());
+    }
+
+    #[test]
+    fn it_requires_volume_host_paths_to_exist() {
+        let host_path = env::temp_dir();
+        let host_path = host_path.to_str().unwrap();
+        let volume = format!("{}:/var/lib/postgresql/data", host_path);
+        assert_eq!(
+            parse_volumes(vec![&volume], true).unwrap(),
+            vec![(
+                host_path.to_string(),
+                "/var/lib/postgresql/data".to_string()
+            )]
+        );
+
+        let missing = "/tfb/does/not/exist:/var/lib/postgresql/data";
+        match parse_volumes(vec![missing], true) {
+            Err(VolumeHostPathNotFoundError(path)) => assert_eq!(path, "/tfb/does/not/exist"),
+            _ => panic!("expected VolumeHostPathNotFoundError"),
+        }
+        assert!(parse_volumes(vec![missing], false).is_ok());
+    }
+
+    #[test]
+    fn it_can_parse_an_env_file() {
+        let contents = "# Verifier tuning\nVERIFY_TIMEOUT=30\n\nEXTRA_ARGS=--a=b\n";
+        assert_eq!(
+            parse_env_file("verifier.env", contents).unwrap(),
+            vec![
+                ("VERIFY_TIMEOUT".to_string(), "30".to_string()),
+                ("EXTRA_ARGS".to_string(), "--a=b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_the_bad_line_of_an_env_file() {
+        for contents in &[
+            "VERIFY_TIMEOUT=30\nVERIFY TIMEOUT\n",
+            "VERIFY_TIMEOUT=30\n1KEY=1\n",
+        ] {
+            match parse_env_file("verifier.env", contents) {
+                Err(InvalidVerifierEnvFile {
+                    path, line_number, ..
+                }) => {
+                    assert_eq!(path, "verifier.env");
+                    assert_eq!(line_number, 2);
+                }
+                _ => panic!("expected InvalidVerifierEnvFile"),
+            }
+        }
+    }
+
+    #[test]
+    fn it_can_parse_host_overrides() {
+        let overrides = parse_host_overrides(vec![
+            "query=10.0.0.2".to_string(),
+            "update=10.0.0.3".to_string(),
+        ]);
+
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(overrides["query"], "10.0.0.2");
+        assert_eq!(overrides["update"], "10.0.0.3");
+    }
+
+    #[test]
+    fn it_requires_memory_reservations_within_the_limit() {
+        assert!(validate_memory_reservation(Some(512), None).is_ok());
+        assert!(validate_memory_reservation(Some(512), Some(512)).is_ok());
+        match validate_memory_reservation(Some(1024), Some(512)) {
+            Err(MemoryReservationExceedsLimitError { reservation, limit }) => {
+                assert_eq!((reservation, limit), (1024, 512))
+            }
+            _ => panic!("expected MemoryReservationExceedsLimitError"),
+        }
+    }
+
+    #[test]
+    fn it_inlines_seccomp_profiles() {
+        let mut profile = env::temp_dir();
+        profile.push(format!("tfb_seccomp_{}.json", std::process::id()));
+        std::fs::write(&profile, "{\n  \"defaultAction\": \"SCMP_ACT_ERRNO\"\n}\n").unwrap();
+        let profile = profile.to_str().unwrap();
+
+        let opts = parse_security_opts(vec![
+            format!("seccomp={}", profile),
+            "seccomp=unconfined".to_string(),
+            "no-new-privileges".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(
+            opts,
+            vec![
+                r#"seccomp={"defaultAction":"SCMP_ACT_ERRNO"}"#.to_string(),
+                "seccomp=unconfined".to_string(),
+                "no-new-privileges".to_string(),
+            ]
+        );
+        std::fs::remove_file(profile).unwrap();
+
+        match parse_security_opts(vec!["seccomp=/tfb/does/not/exist.json".to_string()]) {
+            Err(InvalidSeccompProfileError { path, .. }) => {
+                assert_eq!(path, "/tfb/does/not/exist.json")
+            }
+            _ => panic!("expected InvalidSeccompProfileError"),
+        }
+    }
+
+    #[test]
+    fn it_reads_the_tls_certificates_from_the_options() {
+        let matches = options::parse().get_matches_from(vec![
+            "tfb",
+            "--tls-ca-cert",
+            "/certs/ca.pem",
+            "--tls-cert",
+            "/certs/client.pem",
+            "--tls-key",
+            "/certs/client-key.pem",
+        ]);
+        assert_eq!(
+            parse_docker_tls(&matches),
+            Some(DockerTls {
+                ca_cert: PathBuf::from("/certs/ca.pem"),
+                cert: PathBuf::from("/certs/client.pem"),
+                key: PathBuf::from("/certs/client-key.pem"),
+            })
+        );
+    }
+
+    #[test]
+    fn it_requires_the_test_types_of_a_test_to_share_a_database_host() {
+        assert_eq!(shared_host("gemini", Vec::new()).unwrap(), None);
+        assert_eq!(
+            shared_host("gemini", vec!["replica.db", "replica.db"]).unwrap(),
+            Some("replica.db")
+        );
+        match shared_host("gemini", vec!["replica.db", "primary.db", "replica.db"]) {
+            Err(ConflictingDatabaseHosts { test, hosts }) => {
+                assert_eq!(test, "gemini");
+                assert_eq!(hosts, "primary.db, replica.db");
+            }
+            other => panic!("expected ConflictingDatabaseHosts; got {:?}", other),
         }
     }
 }
@@ -0,0 +1,101 @@
+//! Records Docker container events for the duration of a run so that
+//! containers which die unexpectedly can be debugged after the fact.
+
+use crate::docker::listener::events::{DockerEvent, Events};
+use crate::error::ToolsetResult;
+use curl::easy::Easy2;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+
+/// The container events which are recorded.
+const RECORDED_EVENTS: [&str; 4] = ["die", "oom", "kill", "health_status"];
+
+#[derive(Debug)]
+pub struct EventRecorder {
+    events: Arc<Mutex<Vec<DockerEvent>>>,
+    stop: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+impl EventRecorder {
+    /// Starts recording container events from each of the given
+    /// `docker_hosts` in the background.
+    pub fn start(use_unix_socket: bool, docker_hosts: &[&str]) -> Self {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut docker_hosts = docker_hosts.to_vec();
+        docker_hosts.sort_unstable();
+        docker_hosts.dedup();
+
+        let handles = docker_hosts
+            .iter()
+            .map(|docker_host| {
+                let docker_host = docker_host.to_string();
+                let events = Arc::clone(&events);
+                let stop = Arc::clone(&stop);
+                thread::spawn(move || {
+                    // The stream only ends when aborted by `stop`, so the
+                    // resulting error is expected and ignored.
+                    subscribe(use_unix_socket, &docker_host, events, stop).unwrap_or(());
+                })
+            })
+            .collect();
+
+        Self {
+            events,
+            stop,
+            handles,
+        }
+    }
+
+    /// Stops recording and returns the events recorded, ordered by time.
+    pub fn stop(self) -> Vec<DockerEvent> {
+        self.stop.store(true, Ordering::Release);
+        for handle in self.handles {
+            handle.join().unwrap_or(());
+        }
+
+        let mut events = match self.events.lock() {
+            Ok(events) => events.clone(),
+            Err(_) => Vec::default(),
+        };
+        events.sort_by_key(|event| event.time);
+
+        events
+    }
+}
+
+//
+// PRIVATES
+//
+
+/// Subscribes to the container events stream of `docker_host`, blocking until
+/// `stop` is set.
+fn subscribe(
+    use_unix_socket: bool,
+    docker_host: &str,
+    events: Arc<Mutex<Vec<DockerEvent>>>,
+    stop: Arc<AtomicBool>,
+) -> ToolsetResult<()> {
+    let mut easy = Easy2::new(Events::new(events, stop));
+    if use_unix_socket {
+        easy.unix_socket("/var/run/docker.sock")?;
+    }
+    let filters = serde_json::json!({
+        "type": ["container"],
+        "event": RECORDED_EVENTS,
+    });
+    let filters = easy.url_encode(filters.to_string().as_bytes());
+
+    easy.get(true)?;
+    // Progress callbacks are how the request is aborted once stopped.
+    easy.progress(true)?;
+    easy.url(&format!(
+        "http://{}/events?filters={}",
+        docker_host, filters
+    ))?;
+    easy.perform()?;
+
+    Ok(())
+}
@@ -0,0 +1,507 @@
+//! Helpers for `HostConfig` fields which `dockurl` does not yet expose a
+//! working setter for.
+
+use dockurl::container::create::host_config::HostConfig;
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// The restart policy Docker applies to a container when it exits.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RestartPolicy {
+    No,
+    Always,
+    UnlessStopped,
+    OnFailure(u32),
+}
+impl RestartPolicy {
+    fn to_json(&self) -> Value {
+        match self {
+            RestartPolicy::No => json!({ "Name": "no" }),
+            RestartPolicy::Always => json!({ "Name": "always" }),
+            RestartPolicy::UnlessStopped => json!({ "Name": "unless-stopped" }),
+            RestartPolicy::OnFailure(retries) => json!({
+                "Name": "on-failure",
+                "MaximumRetryCount": retries
+            }),
+        }
+    }
+}
+impl FromStr for RestartPolicy {
+    type Err = String;
+
+    /// Parses the Docker CLI form of a restart policy; e.g. `no`, `always`,
+    /// `unless-stopped`, `on-failure` or `on-failure:3`.
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        let mut split = policy.splitn(2, ':');
+        match (split.next(), split.next()) {
+            (Some("no"), None) => Ok(RestartPolicy::No),
+            (Some("always"), None) => Ok(RestartPolicy::Always),
+            (Some("unless-stopped"), None) => Ok(RestartPolicy::UnlessStopped),
+            (Some("on-failure"), None) => Ok(RestartPolicy::OnFailure(0)),
+            (Some("on-failure"), Some(retries)) => match retries.parse::<u32>() {
+                Ok(retries) => Ok(RestartPolicy::OnFailure(retries)),
+                Err(_) => Err(format!("Invalid maximum retry count: {}", retries)),
+            },
+            _ => Err(format!("Unknown restart policy: {}", policy)),
+        }
+    }
+}
+impl fmt::Display for RestartPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestartPolicy::No => write!(f, "no"),
+            RestartPolicy::Always => write!(f, "always"),
+            RestartPolicy::UnlessStopped => write!(f, "unless-stopped"),
+            RestartPolicy::OnFailure(retries) => write!(f, "on-failure:{}", retries),
+        }
+    }
+}
+
+/// The GPU devices requested for a container.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GpuDevices {
+    All,
+    Count(u32),
+    Ids(Vec<String>),
+}
+impl FromStr for GpuDevices {
+    type Err = String;
+
+    /// Parses the Docker CLI form of the requested devices; e.g. `all`, `2`,
+    /// or `device=0,1`.
+    fn from_str(devices: &str) -> Result<Self, Self::Err> {
+        if devices == "all" {
+            Ok(GpuDevices::All)
+        } else if let Some(ids) = devices.strip_prefix("device=") {
+            let ids: Vec<String> = ids
+                .split(',')
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .collect();
+            if ids.is_empty() {
+                Err(format!("No GPU device ids given: {}", devices))
+            } else {
+                Ok(GpuDevices::Ids(ids))
+            }
+        } else {
+            match devices.parse::<u32>() {
+                Ok(count) if count > 0 => Ok(GpuDevices::Count(count)),
+                _ => Err(format!(
+                    "GPUs must be `all`, a count, or `device=<id>,...`: {}",
+                    devices
+                )),
+            }
+        }
+    }
+}
+
+/// The GPUs requested for a container and the driver capabilities required
+/// of them; e.g. `compute` or `utility`.
+///
+/// Note: this requires the NVIDIA Container Toolkit (the `nvidia` runtime)
+/// to be installed on the Docker host; without it, the daemon rejects the
+/// request when the container is started.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuRequest {
+    pub devices: GpuDevices,
+    pub capabilities: Vec<String>,
+}
+impl GpuRequest {
+    fn to_json(&self) -> Value {
+        // As with the Docker CLI, the `gpu` capability is always required.
+        let mut capabilities = vec!["gpu".to_string()];
+        for capability in &self.capabilities {
+            if !capabilities.contains(capability) {
+                capabilities.push(capability.clone());
+            }
+        }
+        let (count, device_ids) = match &self.devices {
+            GpuDevices::All => (-1, Vec::default()),
+            GpuDevices::Count(count) => (i64::from(*count), Vec::default()),
+            GpuDevices::Ids(ids) => (0, ids.clone()),
+        };
+
+        json!([{
+            "Driver": "",
+            "Count": count,
+            "DeviceIDs": device_ids,
+            "Capabilities": [capabilities],
+        }])
+    }
+}
+
+/// A range of host ports, inclusive, within which Docker publishes the ports
+/// of a container; e.g. `30000-30100`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+impl FromStr for PortRange {
+    type Err = String;
+
+    /// Parses the Docker CLI form of a port range; e.g. `30000-30100`, or
+    /// `30000` for a single port.
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let mut split = range.splitn(2, '-');
+        let start = split.next().unwrap_or_default();
+        let end = split.next().unwrap_or(start);
+        match (start.parse::<u16>(), end.parse::<u16>()) {
+            (Ok(start), Ok(end)) if start > 0 && start <= end => Ok(PortRange { start, end }),
+            _ => Err(format!(
+                "Port range must be `<start>-<end>` with 0 < start <= end <= 65535: {}",
+                range
+            )),
+        }
+    }
+}
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// The `HostConfig` settings which differ between the containers created by
+/// `create_container`.
+#[derive(Debug, Clone)]
+pub struct ContainerHostSettings {
+    /// The host to which `tfb-database` resolves within the container in the
+    /// host network mode.
+    pub database_host: String,
+    pub restart_policy: RestartPolicy,
+    /// Host paths bind mounted into the container at container paths.
+    pub volumes: Vec<(String, String)>,
+    pub gpus: Option<GpuRequest>,
+    pub cap_add: Vec<String>,
+    pub cap_drop: Vec<String>,
+    /// Security options; e.g. `no-new-privileges` or `seccomp=<profile>`,
+    /// where the profile is the JSON profile itself rather than its path.
+    pub security_opt: Vec<String>,
+    /// The hard memory limit; the container is killed if it exceeds it.
+    pub memory_limit_bytes: Option<u64>,
+    /// The soft memory limit; enforced only when the host is short of
+    /// memory. Must not exceed `memory_limit_bytes`.
+    pub memory_reservation_bytes: Option<u64>,
+    /// The relative weight of the container's CPU time under contention;
+    /// Docker's default is 1024.
+    pub cpu_shares: Option<u64>,
+}
+impl ContainerHostSettings {
+    /// Applies these settings to `host_config`.
+    pub fn apply(&self, host_config: &mut HostConfig) {
+        set_restart_policy(host_config, &self.restart_policy);
+        if !self.volumes.is_empty() {
+            set_binds(host_config, &self.volumes);
+        }
+        if let Some(gpus) = &self.gpus {
+            set_device_requests(host_config, gpus);
+        }
+        if let Some(memory) = self.memory_limit_bytes {
+            set_field(host_config, "Memory", json!(memory));
+        }
+        if let Some(memory) = self.memory_reservation_bytes {
+            set_field(host_config, "MemoryReservation", json!(memory));
+        }
+        if let Some(cpu_shares) = self.cpu_shares {
+            set_field(host_config, "CpuShares", json!(cpu_shares));
+        }
+        if self.is_hardened() {
+            // A privileged container is granted every capability and is not
+            // confined by seccomp, which would defeat these settings.
+            set_field(host_config, "Privileged", json!(false));
+            set_capabilities(host_config, &self.cap_drop, &self.cap_add);
+            if !self.security_opt.is_empty() {
+                set_field(host_config, "SecurityOpt", json!(self.security_opt));
+            }
+        }
+    }
+
+    /// Whether the container's capabilities or security options are
+    /// restricted.
+    fn is_hardened(&self) -> bool {
+        !self.cap_add.is_empty() || !self.cap_drop.is_empty() || !self.security_opt.is_empty()
+    }
+}
+
+/// Sets the given `restart_policy` on `host_config`.
+pub fn set_restart_policy(host_config: &mut HostConfig, restart_policy: &RestartPolicy) {
+    set_field(host_config, "RestartPolicy", restart_policy.to_json());
+}
+
+/// Adds the given kernel `capabilities` to the container; e.g. `NET_ADMIN`.
+pub fn set_cap_add(host_config: &mut HostConfig, capabilities: &[&str]) {
+    set_field(host_config, "CapAdd", json!(capabilities));
+}
+
+/// Pins the container to the given `cpus`; e.g. `0-3,8`.
+pub fn set_cpuset(host_config: &mut HostConfig, cpus: &str) {
+    set_field(host_config, "CpusetCpus", json!(cpus));
+}
+
+/// Publishes each of the `exposed_ports` (e.g. `8080/tcp`) of the container
+/// on a free host port within `range`, in place of `PublishAllPorts`, which
+/// publishes on any ephemeral port.
+pub fn set_port_bindings(
+    host_config: &mut HostConfig,
+    exposed_ports: &[String],
+    range: &PortRange,
+) {
+    let bindings: serde_json::Map<String, Value> = exposed_ports
+        .iter()
+        .map(|port| {
+            (
+                port.clone(),
+                json!([{ "HostIp": "", "HostPort": range.to_string() }]),
+            )
+        })
+        .collect();
+    set_field(host_config, "PortBindings", Value::Object(bindings));
+}
+
+/// Drops the given `cap_drop` capabilities from the container and then adds
+/// the given `cap_add` capabilities; e.g. dropping `ALL` and adding back
+/// `NET_BIND_SERVICE`.
+///
+/// Docker applies drops before adds, but rejects a capability which is both
+/// added and dropped by name, so any such capability is only added.
+pub fn set_capabilities(host_config: &mut HostConfig, cap_drop: &[String], cap_add: &[String]) {
+    let cap_drop: Vec<&String> = cap_drop
+        .iter()
+        .filter(|capability| !cap_add.contains(capability))
+        .collect();
+    set_field(host_config, "CapDrop", json!(cap_drop));
+    set_field(host_config, "CapAdd", json!(cap_add));
+}
+
+/// Bind mounts each host path into the container at its container path.
+pub fn set_binds(host_config: &mut HostConfig, volumes: &[(String, String)]) {
+    let binds: Vec<String> = volumes
+        .iter()
+        .map(|(host_path, container_path)| format!("{}:{}", host_path, container_path))
+        .collect();
+    set_field(host_config, "Binds", json!(binds));
+}
+
+/// Requests the given `gpus` for the container.
+pub fn set_device_requests(host_config: &mut HostConfig, gpus: &GpuRequest) {
+    set_field(host_config, "DeviceRequests", gpus.to_json());
+}
+
+/// Sets the field `name` to `value` on `host_config`.
+///
+/// `HostConfig` keeps its fields private, so this round-trips the config
+/// through its serialized form in order to insert the field.
+pub fn set_field(host_config: &mut HostConfig, name: &str, value: Value) {
+    let mut fields = host_config.clone().consume();
+    fields.insert(name.to_string(), value);
+    // HostConfig is a single map of fields; this cannot fail.
+    *host_config = serde_json::from_value(json!({ "Fields": fields })).unwrap();
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::host_config::{
+        set_binds, set_cap_add, set_capabilities, set_device_requests, set_port_bindings,
+        set_restart_policy, ContainerHostSettings, GpuDevices, GpuRequest, PortRange,
+        RestartPolicy,
+    };
+    use dockurl::container::create::host_config::HostConfig;
+    use std::str::FromStr;
+
+    #[test]
+    fn it_can_parse_restart_policies() {
+        assert_eq!(RestartPolicy::from_str("no"), Ok(RestartPolicy::No));
+        assert_eq!(
+            RestartPolicy::from_str("unless-stopped"),
+            Ok(RestartPolicy::UnlessStopped)
+        );
+        assert_eq!(
+            RestartPolicy::from_str("on-failure:3"),
+            Ok(RestartPolicy::OnFailure(3))
+        );
+        assert!(RestartPolicy::from_str("on-failure:x").is_err());
+        assert!(RestartPolicy::from_str("sometimes").is_err());
+    }
+
+    #[test]
+    fn it_can_set_restart_policy() {
+        let mut host_config = HostConfig::new();
+        host_config.privileged(true);
+        set_restart_policy(&mut host_config, &RestartPolicy::OnFailure(2));
+
+        let fields = host_config.consume();
+        assert_eq!(fields["Privileged"], true);
+        assert_eq!(fields["RestartPolicy"]["Name"], "on-failure");
+        assert_eq!(fields["RestartPolicy"]["MaximumRetryCount"], 2);
+    }
+
+    #[test]
+    fn it_can_add_capabilities() {
+        let mut host_config = HostConfig::new();
+        set_cap_add(&mut host_config, &["NET_ADMIN"]);
+
+        assert_eq!(host_config.consume()["CapAdd"][0], "NET_ADMIN");
+    }
+
+    #[test]
+    fn it_drops_capabilities_before_adding_them() {
+        let mut host_config = HostConfig::new();
+        set_capabilities(
+            &mut host_config,
+            &["ALL".to_string(), "NET_BIND_SERVICE".to_string()],
+            &["NET_BIND_SERVICE".to_string()],
+        );
+
+        let fields = host_config.consume();
+        assert_eq!(fields["CapDrop"], serde_json::json!(["ALL"]));
+        assert_eq!(fields["CapAdd"], serde_json::json!(["NET_BIND_SERVICE"]));
+    }
+
+    #[test]
+    fn it_is_not_privileged_when_hardened() {
+        let mut settings = ContainerHostSettings {
+            database_host: String::default(),
+            restart_policy: RestartPolicy::No,
+            volumes: Vec::default(),
+            gpus: None,
+            cap_add: Vec::default(),
+            cap_drop: Vec::default(),
+            security_opt: Vec::default(),
+            memory_limit_bytes: None,
+            memory_reservation_bytes: None,
+            cpu_shares: None,
+        };
+        let mut host_config = HostConfig::new();
+        host_config.privileged(true);
+        settings.apply(&mut host_config);
+        assert_eq!(host_config.consume()["Privileged"], true);
+
+        settings.cap_drop = vec!["ALL".to_string()];
+        let seccomp = r#"seccomp={"defaultAction":"SCMP_ACT_ERRNO"}"#;
+        settings.security_opt = vec![seccomp.to_string()];
+        let mut host_config = HostConfig::new();
+        host_config.privileged(true);
+        settings.apply(&mut host_config);
+        let fields = host_config.consume();
+        assert_eq!(fields["Privileged"], false);
+        assert_eq!(fields["CapDrop"][0], "ALL");
+        assert_eq!(fields["SecurityOpt"][0], seccomp);
+    }
+
+    #[test]
+    fn it_can_set_binds() {
+        let mut host_config = HostConfig::new();
+        set_binds(
+            &mut host_config,
+            &[(
+                "/mnt/nvme/postgres".to_string(),
+                "/var/lib/postgresql/data".to_string(),
+            )],
+        );
+
+        let fields = host_config.consume();
+        assert_eq!(
+            fields["Binds"][0],
+            "/mnt/nvme/postgres:/var/lib/postgresql/data"
+        );
+    }
+
+    #[test]
+    fn it_can_parse_gpu_devices() {
+        assert_eq!(GpuDevices::from_str("all"), Ok(GpuDevices::All));
+        assert_eq!(GpuDevices::from_str("2"), Ok(GpuDevices::Count(2)));
+        assert_eq!(
+            GpuDevices::from_str("device=0,1"),
+            Ok(GpuDevices::Ids(vec!["0".to_string(), "1".to_string()]))
+        );
+        assert!(GpuDevices::from_str("0").is_err());
+        assert!(GpuDevices::from_str("device=").is_err());
+        assert!(GpuDevices::from_str("some").is_err());
+    }
+
+    #[test]
+    fn it_can_request_gpus() {
+        let mut host_config = HostConfig::new();
+        set_device_requests(
+            &mut host_config,
+            &GpuRequest {
+                devices: GpuDevices::Ids(vec!["0".to_string()]),
+                capabilities: vec!["compute".to_string(), "gpu".to_string()],
+            },
+        );
+
+        let fields = host_config.consume();
+        let request = &fields["DeviceRequests"][0];
+        assert_eq!(request["Count"], 0);
+        assert_eq!(request["DeviceIDs"][0], "0");
+        assert_eq!(request["Capabilities"][0][0], "gpu");
+        assert_eq!(request["Capabilities"][0][1], "compute");
+        assert_eq!(request["Capabilities"][0].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn it_can_set_resource_limits() {
+        let settings = ContainerHostSettings {
+            database_host: String::default(),
+            restart_policy: RestartPolicy::No,
+            volumes: Vec::default(),
+            gpus: None,
+            cap_add: Vec::default(),
+            cap_drop: Vec::default(),
+            security_opt: Vec::default(),
+            memory_limit_bytes: Some(4_294_967_296),
+            memory_reservation_bytes: Some(2_147_483_648),
+            cpu_shares: Some(2048),
+        };
+        let mut host_config = HostConfig::new();
+        settings.apply(&mut host_config);
+
+        let fields = host_config.consume();
+        assert_eq!(fields["Memory"], 4_294_967_296u64);
+        assert_eq!(fields["MemoryReservation"], 2_147_483_648u64);
+        assert_eq!(fields["CpuShares"], 2048);
+    }
+
+    #[test]
+    fn it_can_parse_port_ranges() {
+        assert_eq!(
+            "30000-30100".parse::<PortRange>(),
+            Ok(PortRange {
+                start: 30000,
+                end: 30100
+            })
+        );
+        assert_eq!(
+            "30000".parse::<PortRange>(),
+            Ok(PortRange {
+                start: 30000,
+                end: 30000
+            })
+        );
+        assert!("30100-30000".parse::<PortRange>().is_err());
+        assert!("0-100".parse::<PortRange>().is_err());
+        assert!("30000-70000".parse::<PortRange>().is_err());
+        assert!("30000-".parse::<PortRange>().is_err());
+    }
+
+    #[test]
+    fn it_can_publish_ports_within_a_range() {
+        let mut host_config = HostConfig::new();
+        let range = PortRange {
+            start: 30000,
+            end: 30100,
+        };
+        set_port_bindings(&mut host_config, &["8080/tcp".to_string()], &range);
+
+        let fields = host_config.consume();
+        assert_eq!(
+            fields["PortBindings"]["8080/tcp"][0]["HostPort"],
+            "30000-30100"
+        );
+    }
+}
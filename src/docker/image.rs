@@ -1,11 +1,26 @@
 use crate::config::{Named, Project, Test};
-use crate::docker::docker_config::DockerConfig;
+use crate::docker::docker_config::{DockerConfig, DockerTls};
 use crate::docker::listener::build_image::BuildImage;
 use crate::docker::listener::simple::Simple;
-use crate::error::ToolsetError::DockerError;
+use crate::docker::request::{docker_request, docker_stream, encode_query, Method};
+use crate::error::ToolsetError::{DockerError, DockerRequestFailed};
 use crate::error::ToolsetResult;
 use crate::io::Logger;
-use std::path::PathBuf;
+use dockurl::error::DockerError::{DockerImageCreateError, FailedToCreateDockerImageError};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Label identifying images which belong to the toolset. Only dangling images
+/// carrying this label are pruned during teardown.
+pub const TOOLSET_IMAGE_LABEL: &str = "tfb.toolset";
+
+/// The image run as the verifier and the benchmarker.
+pub const VERIFIER_IMAGE: &str = "techempower/tfb.verifier";
+
+/// Label by which a verifier image lists, comma-separated, the env vars it
+/// no longer requires; e.g. `PORT,ENDPOINT,TEST_TYPE`.
+pub const VERIFIER_OPTIONAL_ENV_LABEL: &str = "tfb.verifier.optional-env";
 
 /// Takes a `framework_dir` and the `Test` to run and instructs docker to
 /// build the image.
@@ -23,16 +38,35 @@ pub fn build_image(
         dockerfile.push_str(".dockerfile");
     }
 
-    let image_id = dockurl::image::build_image(
+    build_labelled_image(
         &test.get_tag(),
-        &PathBuf::from(dockerfile),
+        &dockerfile,
         &project.get_path()?,
         &config.server_docker_host,
         config.use_unix_socket,
-        BuildImage::new(logger),
-    )?;
+        config.docker_tls.as_ref(),
+        logger,
+    )
+}
 
-    Ok(image_id)
+/// Builds the verifier image from the `Dockerfile` in the given `context`
+/// directory on `docker_host`, tagging it as `VERIFIER_IMAGE` in place of any
+/// pulled image, and returns the image id.
+pub fn build_verifier_image(
+    config: &DockerConfig,
+    context: &str,
+    docker_host: &str,
+    logger: &Logger,
+) -> ToolsetResult<String> {
+    build_labelled_image(
+        VERIFIER_IMAGE,
+        "Dockerfile",
+        &PathBuf::from(context),
+        docker_host,
+        config.use_unix_socket,
+        config.docker_tls.as_ref(),
+        logger,
+    )
 }
 
 /// Pulls the given `image_name`.
@@ -48,3 +82,334 @@ pub fn pull_image(config: &DockerConfig, docker_host: &str, image_name: &str) ->
         Err(e) => Err(DockerError(e)),
     }
 }
+
+/// Typed builder for the `filters` query parameter accepted by Docker's
+/// image prune endpoint.
+#[derive(Debug, Clone, Default)]
+pub struct PruneFilters {
+    dangling: Option<bool>,
+    labels: Vec<String>,
+}
+impl PruneFilters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts the prune to dangling (untagged) images when `true`.
+    pub fn dangling(&mut self, dangling: bool) {
+        self.dangling = Some(dangling);
+    }
+
+    /// Restricts the prune to images carrying the given `label`, either as
+    /// `key` or `key=value`.
+    pub fn label(&mut self, label: &str) {
+        self.labels.push(label.to_string());
+    }
+
+    /// Serializes these filters into the JSON form Docker expects; e.g.
+    /// `{"dangling":["true"],"label":["tfb.toolset"]}`.
+    pub fn to_json(&self) -> String {
+        let mut filters = HashMap::new();
+        if let Some(dangling) = self.dangling {
+            filters.insert("dangling", vec![dangling.to_string()]);
+        }
+        if !self.labels.is_empty() {
+            filters.insert("label", self.labels.clone());
+        }
+
+        serde_json::to_string(&filters).unwrap()
+    }
+}
+
+/// Prunes the unused images on `docker_host` matching the given `filters`.
+pub fn prune_images(
+    use_unix_socket: bool,
+    docker_host: &str,
+    filters: &PruneFilters,
+) -> ToolsetResult<()> {
+    match dockurl::image::delete_unused_images(
+        &filters.to_json(),
+        docker_host,
+        use_unix_socket,
+        Simple::new(),
+    ) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(DockerError(e)),
+    }
+}
+
+/// Prunes the dangling images on `docker_host` which were labeled as
+/// belonging to the toolset, leaving other dangling images on a shared host
+/// alone.
+pub fn prune_dangling_toolset_images(
+    use_unix_socket: bool,
+    docker_host: &str,
+) -> ToolsetResult<()> {
+    let mut filters = PruneFilters::new();
+    filters.dangling(true);
+    filters.label(TOOLSET_IMAGE_LABEL);
+
+    prune_images(use_unix_socket, docker_host, &filters)
+}
+
+/// Gets the IDs of the containers on `docker_host`, running or not, other
+/// than `container_id` which were created from the image given by `image_id`
+/// (or from an image built on top of it).
+pub fn get_other_containers_using_image(
+    image_id: &str,
+    container_id: Option<&str>,
+    docker_host: &str,
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+) -> ToolsetResult<Vec<String>> {
+    let filters = encode_query(json!({ "ancestor": [image_id] }));
+    let body = docker_request(
+        use_unix_socket,
+        tls,
+        docker_host,
+        Method::Get,
+        &format!("/containers/json?all=true&filters={}", filters),
+        None,
+    )?;
+
+    Ok(other_container_ids(&body, container_id))
+}
+
+/// Gets the ports exposed by the image given by `image_id` on `docker_host`;
+/// e.g. `8080/tcp`.
+pub fn get_exposed_ports(
+    image_id: &str,
+    docker_host: &str,
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+) -> ToolsetResult<Vec<String>> {
+    let body = inspect_image(image_id, docker_host, use_unix_socket, tls)?;
+
+    Ok(exposed_ports(&body))
+}
+
+/// Whether the image given by `image_id` is present on `docker_host`.
+pub fn image_exists(
+    image_id: &str,
+    docker_host: &str,
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+) -> ToolsetResult<bool> {
+    match inspect_image(image_id, docker_host, use_unix_socket, tls) {
+        Ok(_) => Ok(true),
+        Err(DockerRequestFailed { code: 404, .. }) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether `docker_host` can pull the image named `image_name` from its
+/// registry; the daemon fetches the image's manifest with its own
+/// credentials, without pulling the image.
+pub fn image_is_pullable(
+    image_name: &str,
+    docker_host: &str,
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+) -> ToolsetResult<bool> {
+    match docker_request(
+        use_unix_socket,
+        tls,
+        docker_host,
+        Method::Get,
+        &format!("/distribution/{}/json", image_name),
+        None,
+    ) {
+        Ok(_) => Ok(true),
+        Err(DockerRequestFailed { .. }) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Gets the value of the label `name` of the image given by `image_id` on
+/// `docker_host`, if it has the label.
+pub fn get_image_label(
+    image_id: &str,
+    name: &str,
+    docker_host: &str,
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+) -> ToolsetResult<Option<String>> {
+    let body = inspect_image(image_id, docker_host, use_unix_socket, tls)?;
+
+    Ok(image_label(&body, name))
+}
+
+//
+// PRIVATES
+//
+
+/// Builds the image tagged `tag` from `dockerfile` in the `context` directory
+/// on `docker_host`, labelled as belonging to the toolset, and returns the
+/// image id.
+fn build_labelled_image(
+    tag: &str,
+    dockerfile: &str,
+    context: &Path,
+    docker_host: &str,
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+    logger: &Logger,
+) -> ToolsetResult<String> {
+    let mut archive = tar::Builder::new(Vec::new());
+    archive.append_dir_all("", context)?;
+    let mut easy = docker_stream(
+        use_unix_socket,
+        tls,
+        docker_host,
+        Method::PostArchive(archive.into_inner()?),
+        &build_path(tag, dockerfile),
+        None,
+        BuildImage::new(logger),
+    )?;
+    let code = easy.response_code()?;
+    let build = easy.get_mut();
+
+    match (build.image_id.take(), build.error_message.take()) {
+        (Some(image_id), None) if (200..300).contains(&code) => Ok(image_id),
+        (_, Some(message)) => Err(DockerError(FailedToCreateDockerImageError(message))),
+        _ => Err(DockerError(DockerImageCreateError)),
+    }
+}
+
+/// Gets the path of the request to build the image tagged `tag` from
+/// `dockerfile`, labelled as belonging to the toolset.
+fn build_path(tag: &str, dockerfile: &str) -> String {
+    format!(
+        "/build?dockerfile={}&t={}&labels={}",
+        encode_query(dockerfile),
+        encode_query(tag),
+        encode_query(json!({ TOOLSET_IMAGE_LABEL: "" }))
+    )
+}
+
+/// Inspects the image given by `image_id` on `docker_host`, returning the
+/// response body.
+fn inspect_image(
+    image_id: &str,
+    docker_host: &str,
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+) -> ToolsetResult<Vec<u8>> {
+    docker_request(
+        use_unix_socket,
+        tls,
+        docker_host,
+        Method::Get,
+        &format!("/images/{}/json", image_id),
+        None,
+    )
+}
+
+/// Gets the value of the label `name` of the image inspected in the given
+/// response `body`.
+fn image_label(body: &[u8], name: &str) -> Option<String> {
+    let image = serde_json::from_slice::<Value>(body).ok()?;
+
+    image["Config"]["Labels"][name].as_str().map(str::to_string)
+}
+
+/// Gets the ports exposed by the image inspected in the given response
+/// `body`.
+fn exposed_ports(body: &[u8]) -> Vec<String> {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(image) => match image["Config"]["ExposedPorts"].as_object() {
+            Some(ports) => ports.keys().cloned().collect(),
+            None => Vec::default(),
+        },
+        _ => Vec::default(),
+    }
+}
+
+/// Gets the IDs of the containers listed in the given response `body` other
+/// than `container_id`, which may be given in its short form.
+fn other_container_ids(body: &[u8], container_id: Option<&str>) -> Vec<String> {
+    let containers = match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Array(containers)) => containers,
+        _ => return Vec::default(),
+    };
+    containers
+        .iter()
+        .filter_map(|container| container["Id"].as_str())
+        .filter(|id| match container_id {
+            Some(container_id) => !id.starts_with(container_id),
+            None => true,
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::image::{
+        build_path, exposed_ports, image_label, other_container_ids, PruneFilters,
+        TOOLSET_IMAGE_LABEL, VERIFIER_IMAGE, VERIFIER_OPTIONAL_ENV_LABEL,
+    };
+    use serde_json::Value;
+
+    #[test]
+    fn it_can_serialize_empty_prune_filters() {
+        assert_eq!(PruneFilters::new().to_json(), "{}");
+    }
+
+    #[test]
+    fn it_can_serialize_dangling_toolset_prune_filters() {
+        let mut filters = PruneFilters::new();
+        filters.dangling(true);
+        filters.label(TOOLSET_IMAGE_LABEL);
+
+        let json: Value = serde_json::from_str(&filters.to_json()).unwrap();
+        assert_eq!(json["dangling"][0], "true");
+        assert_eq!(json["label"][0], TOOLSET_IMAGE_LABEL);
+    }
+
+    #[test]
+    fn it_keeps_an_image_until_its_last_container_is_gone() {
+        // Two containers share an image; the first is torn down while the
+        // second still uses it.
+        let both = br#"[{"Id":"aaaaaaaaaaaa1111"},{"Id":"bbbbbbbbbbbb2222"}]"#;
+        assert_eq!(
+            other_container_ids(both, Some("aaaaaaaaaaaa")),
+            vec!["bbbbbbbbbbbb2222".to_string()]
+        );
+
+        // The first container has since been removed.
+        let last = br#"[{"Id":"bbbbbbbbbbbb2222"}]"#;
+        assert!(other_container_ids(last, Some("bbbbbbbbbbbb")).is_empty());
+        assert!(other_container_ids(b"[]", None).is_empty());
+    }
+
+    #[test]
+    fn it_labels_built_images_as_belonging_to_the_toolset() {
+        assert_eq!(
+            build_path(VERIFIER_IMAGE, "Dockerfile"),
+            "/build?dockerfile=Dockerfile&t=techempower%2Ftfb.verifier\
+            &labels=%7B%22tfb.toolset%22%3A%22%22%7D"
+        );
+    }
+
+    #[test]
+    fn it_can_find_the_ports_exposed_by_an_image() {
+        let body = br#"{ "Id": "sha256:abc", "Config": { "ExposedPorts": { "8080/tcp": {} } } }"#;
+        assert_eq!(exposed_ports(body), vec!["8080/tcp"]);
+        assert!(exposed_ports(br#"{ "Config": { "ExposedPorts": null } }"#).is_empty());
+    }
+
+    #[test]
+    fn it_can_find_the_labels_of_an_image() {
+        let body = br#"{ "Config": { "Labels": { "tfb.verifier.optional-env": "PORT" } } }"#;
+        assert_eq!(
+            image_label(body, VERIFIER_OPTIONAL_ENV_LABEL),
+            Some("PORT".to_string())
+        );
+        assert!(image_label(br#"{ "Config": { "Labels": null } }"#, "other").is_none());
+    }
+}
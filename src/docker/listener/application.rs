@@ -2,6 +2,7 @@
 //! containers running in Docker. The module should not be called except by the
 //! `docker` module in practice.
 
+use crate::docker::listener::demultiplexer::{tag_lines, OutputStream, StreamHandler};
 use crate::io::Logger;
 use curl::easy::{Handler, WriteError};
 
@@ -9,6 +10,7 @@ use curl::easy::{Handler, WriteError};
 pub struct Application {
     pub error_message: Option<String>,
     pub logger: Logger,
+    stream: Option<OutputStream>,
 }
 impl Application {
     pub fn new(logger: &Logger) -> Self {
@@ -18,15 +20,21 @@ impl Application {
         Self {
             error_message: None,
             logger,
+            stream: None,
         }
     }
 }
 impl Handler for Application {
     fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
         if let Ok(logs) = std::str::from_utf8(data) {
-            self.logger.log(logs).unwrap();
+            self.logger.log(tag_lines(self.stream, logs)).unwrap();
         }
 
         Ok(data.len())
     }
 }
+impl StreamHandler for Application {
+    fn set_stream(&mut self, stream: OutputStream) {
+        self.stream = Some(stream);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::docker::listener::demultiplexer::{tag_lines, OutputStream, StreamHandler};
 use crate::docker::BenchmarkCommands;
 use crate::io::Logger;
 use curl::easy::{Handler, WriteError};
@@ -7,6 +8,7 @@ pub struct BenchmarkCommandListener {
     logger: Logger,
     pub error_message: Option<String>,
     pub benchmark_commands: Option<BenchmarkCommands>,
+    stream: Option<OutputStream>,
 }
 impl BenchmarkCommandListener {
     pub fn new(test_type: &(&String, &String), logger: &Logger) -> Self {
@@ -18,6 +20,7 @@ impl BenchmarkCommandListener {
             logger,
             error_message: None,
             benchmark_commands: None,
+            stream: None,
         }
     }
 }
@@ -29,7 +32,9 @@ impl Handler for BenchmarkCommandListener {
                     if let Ok(commands) = serde_json::from_str::<BenchmarkCommands>(line) {
                         self.benchmark_commands = Some(commands);
                     } else {
-                        self.logger.log(line.trim_end()).unwrap();
+                        self.logger
+                            .log(tag_lines(self.stream, line.trim_end()))
+                            .unwrap();
                     }
                 }
             }
@@ -38,3 +43,8 @@ impl Handler for BenchmarkCommandListener {
         Ok(data.len())
     }
 }
+impl StreamHandler for BenchmarkCommandListener {
+    fn set_stream(&mut self, stream: OutputStream) {
+        self.stream = Some(stream);
+    }
+}
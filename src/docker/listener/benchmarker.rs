@@ -1,19 +1,34 @@
-use crate::error::ToolsetError::BenchmarkDataParseError;
+use crate::docker::listener::demultiplexer::{tag_lines, OutputStream, StreamHandler};
+use crate::docker::listener::log_buffer::{LogBuffer, LogBufferLimits};
+use crate::error::ToolsetError::{
+    BenchmarkClientFailed, BenchmarkDataParseError, SocketErrorThresholdExceeded,
+};
 use crate::error::ToolsetResult;
 use crate::io::Logger;
 use curl::easy::{Handler, WriteError};
 use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+lazy_static! {
+    /// The header wrk prints before anything else once it starts benchmarking.
+    static ref THREADS_CONNECTIONS: Regex =
+        Regex::new(r"([0-9]+) threads and ([0-9]+) connections").unwrap();
+}
+
 #[derive(Clone)]
 pub struct Benchmarker {
     logger: Logger,
-    data: Vec<u8>,
+    output: LogBuffer,
     start_time: u128,
     pub error_message: Option<String>,
+    stream: Option<OutputStream>,
 }
 impl Benchmarker {
-    pub fn new(logger: &Logger) -> Self {
+    /// Creates a `Benchmarker` which logs all of the client's output, but
+    /// retains only as much of it for parsing as `limits` allows.
+    pub fn new(logger: &Logger, limits: LogBufferLimits) -> Self {
         Self {
             start_time: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -21,12 +36,60 @@ impl Benchmarker {
                 .as_millis(),
             logger: logger.clone(),
             error_message: None,
-            data: vec![],
+            output: LogBuffer::new(limits),
+            stream: None,
         }
     }
+    /// Gets the results of the benchmark client which exited with the given
+    /// `exit_code`. If the client never started benchmarking (e.g. it could
+    /// not connect to the application), this is a `BenchmarkClientFailed`
+    /// rather than a failure to parse its output.
+    pub fn results(&self, exit_code: Option<usize>) -> ToolsetResult<BenchmarkResults> {
+        if self.emitted_wrk_header() {
+            return self.parse_wrk_output();
+        }
+
+        let output = self.output.contents();
+        let output = String::from_utf8_lossy(&output);
+        let last_line = output
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|line| !line.is_empty());
+        let exited = match exit_code {
+            Some(exit_code) => format!("exited with code {}", exit_code),
+            None => "exited".to_string(),
+        };
+        Err(BenchmarkClientFailed {
+            reason: match last_line {
+                Some(line) => format!("{} before benchmarking: {}", exited, line),
+                None => format!("{} without any output", exited),
+            },
+        })
+    }
+
+    /// Gets the retained output of the benchmark client.
+    pub fn logs(&self) -> String {
+        String::from_utf8_lossy(&self.output.contents()).to_string()
+    }
+
+    /// Whether wrk started benchmarking; it reports its threads and
+    /// connections before anything else.
+    fn emitted_wrk_header(&self) -> bool {
+        THREADS_CONNECTIONS.is_match(&String::from_utf8_lossy(&self.output.contents()))
+    }
+
+    /// Parses the output of the benchmark client.
     pub fn parse_wrk_output(&self) -> ToolsetResult<BenchmarkResults> {
+        let output = self.output.contents();
+        let data = std::str::from_utf8(&output).map_err(|_| BenchmarkDataParseError)?;
+
+        self.parse_wrk_run(data)
+    }
+
+    /// Parses the output of a single `wrk` run.
+    fn parse_wrk_run(&self, data: &str) -> ToolsetResult<BenchmarkResults> {
         lazy_static! {
-            static ref THREADS_CONNECTIONS: Regex = Regex::new(r"([0-9]+) threads and ([0-9]+) connections").unwrap();
             static ref LATENCY: Regex = Regex::new(r"Latency(\s)*([0-9]+\.*[0-9]*[us|ms|s|m|%]+)(\s)*([0-9]+\.*[0-9]*[us|ms|s|m|%]+)(\s)*([0-9]+\.*[0-9]*[us|ms|s|m|%]+)(\s)*([0-9]+\.*[0-9]*[us|ms|s|m|%]+)").unwrap();
             static ref REQ_SEC: Regex = Regex::new(r"Req/Sec(\s)*([0-9]+\.*[0-9]*[k|m|%]*)(\s)*([0-9]+\.*[0-9]*[k|m|%]*)(\s)*([0-9]+\.*[0-9]*[k|m|%]*)(\s)*([0-9]+\.*[0-9]*[k|m|%]*)").unwrap();
             static ref TOTAL_REQUESTS: Regex = Regex::new(r"([0-9]+) requests in ([0-9]+\.*[0-9]*)s, ([0-9]+\.*[0-9]*[B|KB|MB|GB]+) read").unwrap();
@@ -44,129 +107,134 @@ impl Benchmarker {
             static ref WRITE: Regex = Regex::new(r"write ([0-9]+)").unwrap();
             static ref TIMEOUT: Regex = Regex::new(r"timeout ([0-9]+)").unwrap();
         }
-        if let Ok(data) = std::str::from_utf8(&self.data) {
-            let mut threads = 0;
-            let mut connections = 0;
-            let mut latency_average = String::default();
-            let mut latency_stddev = String::default();
-            let mut latency_max = String::default();
-            let mut latency_plus_minus = String::default();
-            let mut req_sec_average = String::default();
-            let mut req_sec_stddev = String::default();
-            let mut req_sec_max = String::default();
-            let mut req_sec_plus_minus = String::default();
-            let mut total_requests = 0;
-            let mut duration = 0f32;
-            let mut data_read = String::default();
-            let mut socket_errors = None;
-            let mut non_2xx_3xx = None;
-            let mut requests_per_second = 0f32;
-            let mut transfer_per_second = String::default();
-            let mut percentile_50 = String::default();
-            let mut percentile_75 = String::default();
-            let mut percentile_90 = String::default();
-            let mut percentile_99 = String::default();
-            for line in data.lines() {
-                if let Some(captures) = THREADS_CONNECTIONS.captures(line) {
-                    threads = str::parse::<u32>(captures.get(1).unwrap().as_str()).unwrap();
-                    connections = str::parse::<u32>(captures.get(2).unwrap().as_str()).unwrap();
-                }
-                if let Some(captures) = &LATENCY.captures(line) {
-                    latency_average = captures.get(2).unwrap().as_str().to_string();
-                    latency_stddev = captures.get(4).unwrap().as_str().to_string();
-                    latency_max = captures.get(6).unwrap().as_str().to_string();
-                    latency_plus_minus = captures.get(8).unwrap().as_str().to_string();
-                }
-                if let Some(captures) = &REQ_SEC.captures(line) {
-                    req_sec_average = captures.get(2).unwrap().as_str().to_string();
-                    req_sec_stddev = captures.get(4).unwrap().as_str().to_string();
-                    req_sec_max = captures.get(6).unwrap().as_str().to_string();
-                    req_sec_plus_minus = captures.get(8).unwrap().as_str().to_string();
-                }
-                if let Some(captures) = TOTAL_REQUESTS.captures(line) {
-                    total_requests = str::parse::<u32>(captures.get(1).unwrap().as_str()).unwrap();
-                    duration = str::parse::<f32>(captures.get(2).unwrap().as_str()).unwrap();
-                    data_read = captures.get(3).unwrap().as_str().to_string();
-                }
-                if let Some(captures) = SOCKET_ERRORS.captures(line) {
-                    // todo - test this; Gemini exercise these.
-                    socket_errors = Some(SocketErrors {
-                        connect: str::parse::<u32>(captures.get(1).unwrap().as_str()).unwrap(),
-                        read: str::parse::<u32>(captures.get(2).unwrap().as_str()).unwrap(),
-                        write: str::parse::<u32>(captures.get(3).unwrap().as_str()).unwrap(),
-                        timeout: str::parse::<u32>(captures.get(4).unwrap().as_str()).unwrap(),
-                    });
-                }
-                if let Some(captures) = NON_2XX_3XX.captures(line) {
-                    non_2xx_3xx =
-                        Some(str::parse::<u32>(captures.get(1).unwrap().as_str()).unwrap());
-                }
-                if let Some(captures) = REQUESTS_PER_SECOND.captures(line) {
-                    requests_per_second =
-                        str::parse::<f32>(captures.get(2).unwrap().as_str()).unwrap();
-                }
-                if let Some(captures) = TRANSFER_PER_SECOND.captures(line) {
-                    transfer_per_second = captures.get(2).unwrap().as_str().to_string();
-                }
-                if let Some(captures) = LATENCY_DIST_50.captures(line) {
-                    percentile_50 = captures.get(2).unwrap().as_str().to_string();
-                }
-                if let Some(captures) = LATENCY_DIST_75.captures(line) {
-                    percentile_75 = captures.get(2).unwrap().as_str().to_string();
-                }
-                if let Some(captures) = LATENCY_DIST_90.captures(line) {
-                    percentile_90 = captures.get(2).unwrap().as_str().to_string();
-                }
-                if let Some(captures) = LATENCY_DIST_99.captures(line) {
-                    percentile_99 = captures.get(2).unwrap().as_str().to_string();
-                }
+        let mut threads = 0;
+        let mut connections = 0;
+        let mut latency_average = String::default();
+        let mut latency_stddev = String::default();
+        let mut latency_max = String::default();
+        let mut latency_plus_minus = String::default();
+        let mut req_sec_average = String::default();
+        let mut req_sec_stddev = String::default();
+        let mut req_sec_max = String::default();
+        let mut req_sec_plus_minus = String::default();
+        let mut total_requests = 0;
+        let mut duration = 0f32;
+        let mut data_read = String::default();
+        let mut socket_errors = SocketErrors::default();
+        let mut non_2xx_3xx = None;
+        let mut requests_per_second = 0f32;
+        let mut transfer_per_second = String::default();
+        let mut percentile_50 = String::default();
+        let mut percentile_75 = String::default();
+        let mut percentile_90 = String::default();
+        let mut percentile_99 = String::default();
+        let mut completed = false;
+        for line in data.lines() {
+            if let Some(captures) = THREADS_CONNECTIONS.captures(line) {
+                threads = str::parse::<u32>(captures.get(1).unwrap().as_str()).unwrap();
+                connections = str::parse::<u32>(captures.get(2).unwrap().as_str()).unwrap();
+            }
+            if let Some(captures) = &LATENCY.captures(line) {
+                latency_average = captures.get(2).unwrap().as_str().to_string();
+                latency_stddev = captures.get(4).unwrap().as_str().to_string();
+                latency_max = captures.get(6).unwrap().as_str().to_string();
+                latency_plus_minus = captures.get(8).unwrap().as_str().to_string();
+            }
+            if let Some(captures) = &REQ_SEC.captures(line) {
+                req_sec_average = captures.get(2).unwrap().as_str().to_string();
+                req_sec_stddev = captures.get(4).unwrap().as_str().to_string();
+                req_sec_max = captures.get(6).unwrap().as_str().to_string();
+                req_sec_plus_minus = captures.get(8).unwrap().as_str().to_string();
+            }
+            if let Some(captures) = TOTAL_REQUESTS.captures(line) {
+                total_requests = str::parse::<u32>(captures.get(1).unwrap().as_str()).unwrap();
+                duration = str::parse::<f32>(captures.get(2).unwrap().as_str()).unwrap();
+                data_read = captures.get(3).unwrap().as_str().to_string();
+                completed = true;
+            }
+            if let Some(captures) = SOCKET_ERRORS.captures(line) {
+                socket_errors = SocketErrors {
+                    connect: str::parse::<u32>(captures.get(1).unwrap().as_str()).unwrap(),
+                    read: str::parse::<u32>(captures.get(2).unwrap().as_str()).unwrap(),
+                    write: str::parse::<u32>(captures.get(3).unwrap().as_str()).unwrap(),
+                    timeout: str::parse::<u32>(captures.get(4).unwrap().as_str()).unwrap(),
+                };
+            }
+            if let Some(captures) = NON_2XX_3XX.captures(line) {
+                non_2xx_3xx = Some(str::parse::<u32>(captures.get(1).unwrap().as_str()).unwrap());
+            }
+            if let Some(captures) = REQUESTS_PER_SECOND.captures(line) {
+                requests_per_second = str::parse::<f32>(captures.get(2).unwrap().as_str()).unwrap();
+            }
+            if let Some(captures) = TRANSFER_PER_SECOND.captures(line) {
+                transfer_per_second = captures.get(2).unwrap().as_str().to_string();
+            }
+            if let Some(captures) = LATENCY_DIST_50.captures(line) {
+                percentile_50 = captures.get(2).unwrap().as_str().to_string();
+            }
+            if let Some(captures) = LATENCY_DIST_75.captures(line) {
+                percentile_75 = captures.get(2).unwrap().as_str().to_string();
+            }
+            if let Some(captures) = LATENCY_DIST_90.captures(line) {
+                percentile_90 = captures.get(2).unwrap().as_str().to_string();
             }
-            Ok(BenchmarkResults {
-                start_time: self.start_time,
-                end_time: self.start_time + (duration * 1_000f32) as u128,
-                threads,
-                connections,
-                thread_stats: ThreadStats {
-                    latency: Latency {
-                        average: latency_average,
-                        standard_deviation: latency_stddev,
-                        max: latency_max,
-                        plus_minus_std_dev: latency_plus_minus,
-                    },
-                    requests_per_second: RequestsPerSecond {
-                        average: req_sec_average,
-                        standard_deviation: req_sec_stddev,
-                        max: req_sec_max,
-                        plus_minus_std_dev: req_sec_plus_minus,
-                    },
+            if let Some(captures) = LATENCY_DIST_99.captures(line) {
+                percentile_99 = captures.get(2).unwrap().as_str().to_string();
+            }
+        }
+        // wrk reports the totals once it completes; without them the
+        // output was truncated.
+        if !completed {
+            return Err(BenchmarkDataParseError);
+        }
+        Ok(BenchmarkResults {
+            start_time: self.start_time,
+            end_time: self.start_time + (duration * 1_000f32) as u128,
+            threads,
+            connections,
+            thread_stats: ThreadStats {
+                latency: Latency {
+                    average: latency_average,
+                    standard_deviation: latency_stddev,
+                    max: latency_max,
+                    plus_minus_std_dev: latency_plus_minus,
                 },
-                latency_distribution: LatencyDistribution {
-                    percentile_50,
-                    percentile_75,
-                    percentile_90,
-                    percentile_99,
+                requests_per_second: RequestsPerSecond {
+                    average: req_sec_average,
+                    standard_deviation: req_sec_stddev,
+                    max: req_sec_max,
+                    plus_minus_std_dev: req_sec_plus_minus,
                 },
-                total_requests,
-                duration,
-                data_read,
-                socket_errors,
-                requests_per_second,
-                transfer_per_second,
-                non_2xx_3xx,
-            })
-        } else {
-            Err(BenchmarkDataParseError)
-        }
+            },
+            latency_distribution: LatencyDistribution {
+                percentile_50,
+                percentile_75,
+                percentile_90,
+                percentile_99,
+            },
+            total_requests,
+            duration,
+            data_read,
+            socket_errors,
+            requests_per_second,
+            transfer_per_second,
+            non_2xx_3xx,
+            logs: None,
+            levels: HashMap::new(),
+            executed_command: Vec::new(),
+        })
     }
 }
 impl Handler for Benchmarker {
     fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
-        self.data.extend_from_slice(data);
+        self.output.push(data);
 
         if let Ok(logs) = std::str::from_utf8(&data) {
             for line in logs.lines() {
                 if !line.trim().is_empty() {
-                    self.logger.log(line.trim_end()).unwrap();
+                    self.logger
+                        .log(tag_lines(self.stream, line.trim_end()))
+                        .unwrap();
                 }
             }
         }
@@ -174,8 +242,13 @@ impl Handler for Benchmarker {
         Ok(data.len())
     }
 }
+impl StreamHandler for Benchmarker {
+    fn set_stream(&mut self, stream: OutputStream) {
+        self.stream = Some(stream);
+    }
+}
 
-#[derive(Debug)]
+#[derive(Serialize, Clone, Debug)]
 pub struct BenchmarkResults {
     pub start_time: u128,
     pub end_time: u128,
@@ -186,19 +259,56 @@ pub struct BenchmarkResults {
     pub total_requests: u32,
     pub duration: f32,
     pub data_read: String,
-    pub socket_errors: Option<SocketErrors>,
+    /// wrk only reports socket errors when there were some; all zero
+    /// otherwise.
+    pub socket_errors: SocketErrors,
     pub requests_per_second: f32,
     pub transfer_per_second: String,
     pub non_2xx_3xx: Option<u32>,
+    /// The benchmark client's output, when configured to capture it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logs: Option<String>,
+    /// The results of this run keyed by the concurrency (or query) level its
+    /// command benchmarked, once known; empty otherwise.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub levels: HashMap<u32, LevelResult>,
+    /// The command handed to the benchmarker container, after substituting
+    /// the server host and rewriting flags, so that the run can be repeated
+    /// by hand.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub executed_command: Vec<String>,
 }
 
-#[derive(Debug)]
+/// The results of the `wrk` run at a single concurrency level.
+#[derive(Serialize, Clone, Debug)]
+pub struct LevelResult {
+    pub connections: u32,
+    pub total_requests: u32,
+    pub requests_per_second: f32,
+    pub latency: Latency,
+    pub latency_distribution: LatencyDistribution,
+    pub socket_errors: SocketErrors,
+}
+impl From<&BenchmarkResults> for LevelResult {
+    fn from(results: &BenchmarkResults) -> Self {
+        Self {
+            connections: results.connections,
+            total_requests: results.total_requests,
+            requests_per_second: results.requests_per_second,
+            latency: results.thread_stats.latency.clone(),
+            latency_distribution: results.latency_distribution.clone(),
+            socket_errors: results.socket_errors.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
 pub struct ThreadStats {
     pub latency: Latency,
     pub requests_per_second: RequestsPerSecond,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Clone, Debug)]
 pub struct Latency {
     pub average: String,
     pub standard_deviation: String,
@@ -206,7 +316,7 @@ pub struct Latency {
     pub plus_minus_std_dev: String,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Clone, Debug)]
 pub struct RequestsPerSecond {
     pub average: String,
     pub standard_deviation: String,
@@ -214,7 +324,7 @@ pub struct RequestsPerSecond {
     pub plus_minus_std_dev: String,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Clone, Debug)]
 pub struct LatencyDistribution {
     pub percentile_50: String,
     pub percentile_75: String,
@@ -222,10 +332,539 @@ pub struct LatencyDistribution {
     pub percentile_99: String,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
 pub struct SocketErrors {
     pub connect: u32,
     pub read: u32,
     pub write: u32,
     pub timeout: u32,
 }
+impl SocketErrors {
+    /// Gets the total number of socket errors of every kind.
+    pub fn total(&self) -> u64 {
+        self.connect as u64 + self.read as u64 + self.write as u64 + self.timeout as u64
+    }
+}
+
+impl BenchmarkResults {
+    /// Fails with a `SocketErrorThresholdExceeded` if the socket errors of
+    /// these results exceed `max_ratio` of their total requests.
+    pub fn check_socket_errors(&self, max_ratio: f64) -> ToolsetResult<()> {
+        let errors = self.socket_errors.total();
+        // A run which made no requests but had errors is entirely errors.
+        let ratio = errors as f64 / self.total_requests.max(1) as f64;
+        if ratio > max_ratio {
+            Err(SocketErrorThresholdExceeded {
+                errors,
+                total_requests: self.total_requests,
+                max_ratio,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Merges the results of several benchmarker replicas which ran the same
+    /// command concurrently into a single combined result.
+    ///
+    /// Counts (requests, threads, connections, errors, bytes) and throughput
+    /// are summed. Latency averages are weighted by each replica's total
+    /// requests and standard deviations are pooled; the per-thread request
+    /// rate statistics are combined the same way, weighted by threads. The
+    /// latency distribution cannot be recomputed without the raw samples, so
+    /// each percentile is approximated by the request-weighted mean of the
+    /// replicas' percentiles.
+    pub fn merge(mut results: Vec<BenchmarkResults>) -> ToolsetResult<BenchmarkResults> {
+        if results.len() <= 1 {
+            return results.pop().ok_or(BenchmarkDataParseError);
+        }
+
+        let requests: Vec<f64> = results.iter().map(|r| r.total_requests as f64).collect();
+        let threads: Vec<f64> = results.iter().map(|r| r.threads as f64).collect();
+        let latencies: Vec<&Latency> = results.iter().map(|r| &r.thread_stats.latency).collect();
+        let distributions: Vec<&LatencyDistribution> =
+            results.iter().map(|r| &r.latency_distribution).collect();
+        let count = |field: fn(&BenchmarkResults) -> &String| -> ToolsetResult<Vec<f64>> {
+            results
+                .iter()
+                .map(|result| parse_wrk_count(field(result)))
+                .collect::<Option<Vec<f64>>>()
+                .ok_or(BenchmarkDataParseError)
+        };
+        let req_sec_averages = count(|r| &r.thread_stats.requests_per_second.average)?;
+        let req_sec_stdevs = count(|r| &r.thread_stats.requests_per_second.standard_deviation)?;
+        let req_sec_maxes = count(|r| &r.thread_stats.requests_per_second.max)?;
+        let percentage =
+            |field: fn(&BenchmarkResults) -> &String, weights: &[f64]| -> ToolsetResult<String> {
+                let percentages = results
+                    .iter()
+                    .map(|result| field(result).trim_end_matches('%').parse::<f64>().ok())
+                    .collect::<Option<Vec<f64>>>()
+                    .ok_or(BenchmarkDataParseError)?;
+                Ok(format!("{:.2}%", weighted_mean(&percentages, weights)))
+            };
+        let size = |field: fn(&BenchmarkResults) -> &String| -> ToolsetResult<String> {
+            let sizes = results
+                .iter()
+                .map(|result| parse_wrk_size(field(result)))
+                .collect::<Option<Vec<f64>>>()
+                .ok_or(BenchmarkDataParseError)?;
+            Ok(format_wrk_size(sizes.iter().sum()))
+        };
+
+        let socket_errors = sum_socket_errors(results.iter().map(|result| &result.socket_errors));
+        // Each replica's output is bounded on its own, so the combined output
+        // is at most one bound per replica.
+        let logs: Vec<&str> = results
+            .iter()
+            .filter_map(|result| result.logs.as_deref())
+            .collect();
+        let logs = if logs.is_empty() {
+            None
+        } else {
+            Some(logs.join("\n"))
+        };
+        let non_2xx_3xx = results
+            .iter()
+            .filter_map(|result| result.non_2xx_3xx)
+            .fold(None, |total: Option<u32>, count| {
+                Some(total.unwrap_or(0) + count)
+            });
+
+        Ok(BenchmarkResults {
+            start_time: results.iter().map(|r| r.start_time).min().unwrap_or(0),
+            end_time: results.iter().map(|r| r.end_time).max().unwrap_or(0),
+            threads: results.iter().map(|r| r.threads).sum(),
+            connections: results.iter().map(|r| r.connections).sum(),
+            thread_stats: ThreadStats {
+                latency: merge_latencies(&latencies, &requests)?,
+                requests_per_second: RequestsPerSecond {
+                    average: format_wrk_count(weighted_mean(&req_sec_averages, &threads)),
+                    standard_deviation: format_wrk_count(pooled_standard_deviation(
+                        &req_sec_averages,
+                        &req_sec_stdevs,
+                        &threads,
+                    )),
+                    max: format_wrk_count(req_sec_maxes.iter().cloned().fold(0f64, f64::max)),
+                    plus_minus_std_dev: percentage(
+                        |r| &r.thread_stats.requests_per_second.plus_minus_std_dev,
+                        &threads,
+                    )?,
+                },
+            },
+            latency_distribution: merge_latency_distributions(&distributions, &requests)?,
+            total_requests: results.iter().map(|r| r.total_requests).sum(),
+            duration: results.iter().map(|r| r.duration).fold(0f32, f32::max),
+            data_read: size(|r| &r.data_read)?,
+            socket_errors,
+            requests_per_second: results.iter().map(|r| r.requests_per_second).sum(),
+            transfer_per_second: size(|r| &r.transfer_per_second)?,
+            non_2xx_3xx,
+            logs,
+            // The merged run is keyed by its level afterwards.
+            levels: HashMap::new(),
+            // Every replica runs the same command.
+            executed_command: Vec::new(),
+        })
+    }
+}
+
+//
+// PRIVATES
+//
+
+/// Combines the `latencies` of several replicas; averages are weighted by
+/// each replica's `requests` and standard deviations are pooled.
+fn merge_latencies(latencies: &[&Latency], requests: &[f64]) -> ToolsetResult<Latency> {
+    let durations = |field: fn(&Latency) -> &String| -> ToolsetResult<Vec<f64>> {
+        latencies
+            .iter()
+            .map(|latency| parse_wrk_duration(field(latency)))
+            .collect::<Option<Vec<f64>>>()
+            .ok_or(BenchmarkDataParseError)
+    };
+    let averages = durations(|l| &l.average)?;
+    let standard_deviations = durations(|l| &l.standard_deviation)?;
+    let maxes = durations(|l| &l.max)?;
+    let plus_minus = latencies
+        .iter()
+        .map(|latency| {
+            latency
+                .plus_minus_std_dev
+                .trim_end_matches('%')
+                .parse::<f64>()
+                .ok()
+        })
+        .collect::<Option<Vec<f64>>>()
+        .ok_or(BenchmarkDataParseError)?;
+
+    Ok(Latency {
+        average: format_wrk_duration(weighted_mean(&averages, requests)),
+        standard_deviation: format_wrk_duration(pooled_standard_deviation(
+            &averages,
+            &standard_deviations,
+            requests,
+        )),
+        max: format_wrk_duration(maxes.iter().cloned().fold(0f64, f64::max)),
+        plus_minus_std_dev: format!("{:.2}%", weighted_mean(&plus_minus, requests)),
+    })
+}
+
+/// Approximates the combined latency distribution of several replicas by the
+/// mean of each percentile weighted by each replica's `requests`.
+fn merge_latency_distributions(
+    distributions: &[&LatencyDistribution],
+    requests: &[f64],
+) -> ToolsetResult<LatencyDistribution> {
+    let percentile = |field: fn(&LatencyDistribution) -> &String| -> ToolsetResult<String> {
+        let durations = distributions
+            .iter()
+            .map(|distribution| parse_wrk_duration(field(distribution)))
+            .collect::<Option<Vec<f64>>>()
+            .ok_or(BenchmarkDataParseError)?;
+        Ok(format_wrk_duration(weighted_mean(&durations, requests)))
+    };
+
+    Ok(LatencyDistribution {
+        percentile_50: percentile(|d| &d.percentile_50)?,
+        percentile_75: percentile(|d| &d.percentile_75)?,
+        percentile_90: percentile(|d| &d.percentile_90)?,
+        percentile_99: percentile(|d| &d.percentile_99)?,
+    })
+}
+
+/// Sums the given socket `errors` of every kind.
+fn sum_socket_errors<'a>(errors: impl Iterator<Item = &'a SocketErrors>) -> SocketErrors {
+    errors.fold(SocketErrors::default(), |total, errors| SocketErrors {
+        connect: total.connect + errors.connect,
+        read: total.read + errors.read,
+        write: total.write + errors.write,
+        timeout: total.timeout + errors.timeout,
+    })
+}
+
+/// Parses a `wrk` duration such as `1.23ms` into microseconds.
+fn parse_wrk_duration(duration: &str) -> Option<f64> {
+    let index = duration.find(|c: char| c.is_ascii_alphabetic())?;
+    let (value, unit) = duration.split_at(index);
+    let value = value.parse::<f64>().ok()?;
+    match unit {
+        "us" => Some(value),
+        "ms" => Some(value * 1_000f64),
+        "s" => Some(value * 1_000_000f64),
+        "m" => Some(value * 60_000_000f64),
+        "h" => Some(value * 3_600_000_000f64),
+        _ => None,
+    }
+}
+
+/// Formats the given microseconds the way `wrk` does; e.g. `1.23ms`.
+fn format_wrk_duration(micros: f64) -> String {
+    if micros < 1_000f64 {
+        format!("{:.2}us", micros)
+    } else if micros < 1_000_000f64 {
+        format!("{:.2}ms", micros / 1_000f64)
+    } else {
+        format!("{:.2}s", micros / 1_000_000f64)
+    }
+}
+
+/// Parses a `wrk` count such as `12.34k` into a number.
+fn parse_wrk_count(count: &str) -> Option<f64> {
+    if let Some(value) = count.strip_suffix('k') {
+        value.parse::<f64>().ok().map(|value| value * 1_000f64)
+    } else if let Some(value) = count.strip_suffix('M') {
+        value.parse::<f64>().ok().map(|value| value * 1_000_000f64)
+    } else {
+        count.parse::<f64>().ok()
+    }
+}
+
+/// Formats the given count the way `wrk` does; e.g. `12.34k`.
+fn format_wrk_count(count: f64) -> String {
+    if count < 1_000f64 {
+        format!("{:.2}", count)
+    } else if count < 1_000_000f64 {
+        format!("{:.2}k", count / 1_000f64)
+    } else {
+        format!("{:.2}M", count / 1_000_000f64)
+    }
+}
+
+/// Parses a `wrk` byte size such as `1.23MB` into bytes.
+fn parse_wrk_size(size: &str) -> Option<f64> {
+    let index = size.find(|c: char| c.is_ascii_alphabetic())?;
+    let (value, unit) = size.split_at(index);
+    let value = value.parse::<f64>().ok()?;
+    match unit {
+        "B" => Some(value),
+        "KB" => Some(value * 1024f64),
+        "MB" => Some(value * 1024f64 * 1024f64),
+        "GB" => Some(value * 1024f64 * 1024f64 * 1024f64),
+        _ => None,
+    }
+}
+
+/// Formats the given bytes the way `wrk` does; e.g. `1.23MB`.
+fn format_wrk_size(bytes: f64) -> String {
+    if bytes < 1024f64 {
+        format!("{:.2}B", bytes)
+    } else if bytes < 1024f64 * 1024f64 {
+        format!("{:.2}KB", bytes / 1024f64)
+    } else if bytes < 1024f64 * 1024f64 * 1024f64 {
+        format!("{:.2}MB", bytes / (1024f64 * 1024f64))
+    } else {
+        format!("{:.2}GB", bytes / (1024f64 * 1024f64 * 1024f64))
+    }
+}
+
+/// The mean of `values` weighted by `weights`.
+fn weighted_mean(values: &[f64], weights: &[f64]) -> f64 {
+    let total_weight: f64 = weights.iter().sum();
+    if total_weight == 0f64 {
+        return values.iter().sum::<f64>() / values.len().max(1) as f64;
+    }
+
+    values
+        .iter()
+        .zip(weights)
+        .map(|(value, weight)| value * weight)
+        .sum::<f64>()
+        / total_weight
+}
+
+/// The standard deviation of the union of several populations given each
+/// population's mean, standard deviation, and size.
+fn pooled_standard_deviation(means: &[f64], standard_deviations: &[f64], sizes: &[f64]) -> f64 {
+    let total_size: f64 = sizes.iter().sum();
+    if total_size == 0f64 {
+        return 0f64;
+    }
+    let mean = weighted_mean(means, sizes);
+    let second_moment = means
+        .iter()
+        .zip(standard_deviations)
+        .zip(sizes)
+        .map(|((mean, standard_deviation), size)| {
+            size * (standard_deviation * standard_deviation + mean * mean)
+        })
+        .sum::<f64>()
+        / total_size;
+
+    (second_moment - mean * mean).max(0f64).sqrt()
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+pub mod tests {
+    use crate::docker::listener::benchmarker::{
+        parse_wrk_duration, BenchmarkResults, Benchmarker, Latency, LatencyDistribution,
+        LevelResult, RequestsPerSecond, SocketErrors, ThreadStats,
+    };
+    use crate::docker::listener::demultiplexer::tests::multiplex;
+    use crate::docker::listener::demultiplexer::{AttachStreams, Demultiplexer};
+    use crate::docker::listener::log_buffer::LogBufferLimits;
+    use crate::error::ToolsetError::{
+        BenchmarkClientFailed, BenchmarkDataParseError, SocketErrorThresholdExceeded,
+    };
+    use crate::io::Logger;
+    use curl::easy::Handler;
+    use std::collections::HashMap;
+
+    const WRK_OUTPUT: [&str; 14] = [
+        "Running 15s test @ http://tfb-server:8080/json",
+        "  8 threads and 256 connections",
+        "  Thread Stats   Avg      Stdev     Max   +/- Stdev",
+        "    Latency     1.23ms    2.34ms  45.67ms   90.12%",
+        "    Req/Sec    10.00k     1.00k    12.00k    70.00%",
+        "  Latency Distribution",
+        "     50%    1.00ms",
+        "     75%    1.50ms",
+        "     90%    2.00ms",
+        "     99%    9.00ms",
+        "  1200000 requests in 15.00s, 512.00MB read",
+        "  Socket errors: connect 0, read 1, write 0, timeout 2",
+        "Requests/sec:  80000.00",
+        "Transfer/sec:     34.13MB",
+    ];
+
+    /// Gets the results of a 15 second run of 256 connections with the given
+    /// `total_requests` and latencies.
+    pub fn results(total_requests: u32, latency_average: &str, max: &str) -> BenchmarkResults {
+        BenchmarkResults {
+            start_time: 1000,
+            end_time: 16000,
+            threads: 8,
+            connections: 256,
+            thread_stats: ThreadStats {
+                latency: Latency {
+                    average: latency_average.to_string(),
+                    standard_deviation: "0.00us".to_string(),
+                    max: max.to_string(),
+                    plus_minus_std_dev: "90.00%".to_string(),
+                },
+                requests_per_second: RequestsPerSecond {
+                    average: "10.00k".to_string(),
+                    standard_deviation: "1.00k".to_string(),
+                    max: "12.00k".to_string(),
+                    plus_minus_std_dev: "70.00%".to_string(),
+                },
+            },
+            latency_distribution: LatencyDistribution {
+                percentile_50: latency_average.to_string(),
+                percentile_75: latency_average.to_string(),
+                percentile_90: latency_average.to_string(),
+                percentile_99: max.to_string(),
+            },
+            total_requests,
+            duration: 15f32,
+            data_read: "512.00KB".to_string(),
+            socket_errors: SocketErrors {
+                connect: 0,
+                read: 1,
+                write: 0,
+                timeout: 2,
+            },
+            requests_per_second: total_requests as f32 / 15f32,
+            transfer_per_second: "1.00MB".to_string(),
+            non_2xx_3xx: None,
+            logs: None,
+            levels: HashMap::new(),
+            executed_command: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn it_can_parse_wrk_durations() {
+        assert_eq!(parse_wrk_duration("250.00us"), Some(250f64));
+        assert_eq!(parse_wrk_duration("1.50ms"), Some(1_500f64));
+        assert_eq!(parse_wrk_duration("2.00s"), Some(2_000_000f64));
+        assert_eq!(parse_wrk_duration("fast"), None);
+    }
+
+    #[test]
+    fn it_can_merge_replica_results() {
+        let merged = BenchmarkResults::merge(vec![
+            results(300_000, "1.00ms", "10.00ms"),
+            results(100_000, "2.00ms", "40.00ms"),
+        ])
+        .unwrap();
+
+        assert_eq!(merged.total_requests, 400_000);
+        assert_eq!(merged.threads, 16);
+        assert_eq!(merged.connections, 512);
+        assert_eq!(merged.thread_stats.latency.average, "1.25ms");
+        assert_eq!(merged.thread_stats.latency.max, "40.00ms");
+        assert_eq!(merged.thread_stats.requests_per_second.average, "10.00k");
+        assert_eq!(merged.latency_distribution.percentile_50, "1.25ms");
+        assert_eq!(merged.data_read, "1.00MB");
+        assert_eq!(merged.transfer_per_second, "2.00MB");
+        assert_eq!(merged.socket_errors.timeout, 4);
+        assert!(merged.non_2xx_3xx.is_none());
+        assert!(merged.logs.is_none());
+    }
+
+    #[test]
+    fn it_will_not_merge_no_results() {
+        assert!(BenchmarkResults::merge(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn it_can_parse_tty_and_non_tty_wrk_output() {
+        let tty_output = format!("{}\r\n", WRK_OUTPUT.join("\r\n")).into_bytes();
+        let non_tty_output = multiplex(&WRK_OUTPUT);
+        for (output, allocate_tty) in &[(tty_output, true), (non_tty_output, false)] {
+            let mut logger = Logger::default();
+            logger.quiet = true;
+            let mut listener = Demultiplexer::new(
+                Benchmarker::new(&logger, LogBufferLimits::default()),
+                *allocate_tty,
+                AttachStreams::Both,
+            );
+            listener.write(output).unwrap();
+
+            let results = listener.inner.parse_wrk_output().unwrap();
+            assert!(listener.inner.logs().contains("Requests/sec:  80000.00"));
+            assert_eq!(results.threads, 8);
+            assert_eq!(results.connections, 256);
+            assert_eq!(results.thread_stats.latency.average, "1.23ms");
+            assert_eq!(results.latency_distribution.percentile_99, "9.00ms");
+            assert_eq!(results.total_requests, 1200000);
+            assert_eq!(results.data_read, "512.00MB");
+            assert_eq!(results.socket_errors.timeout, 2);
+            assert_eq!(results.requests_per_second, 80000f32);
+            assert_eq!(results.transfer_per_second, "34.13MB");
+        }
+    }
+
+    #[test]
+    fn it_reports_a_client_which_never_ran() {
+        let mut logger = Logger::default();
+        logger.quiet = true;
+        let listener = Benchmarker::new(&logger, LogBufferLimits::default());
+        match listener.results(Some(1)) {
+            Err(BenchmarkClientFailed { reason }) => {
+                assert_eq!(reason, "exited with code 1 without any output")
+            }
+            _ => panic!("expected BenchmarkClientFailed"),
+        }
+
+        let mut listener = Benchmarker::new(&logger, LogBufferLimits::default());
+        listener
+            .write(b"unable to connect to tfb-server:8080 Connection refused\n")
+            .unwrap();
+        match listener.results(Some(1)) {
+            Err(BenchmarkClientFailed { reason }) => assert_eq!(
+                reason,
+                "exited with code 1 before benchmarking: unable to connect to tfb-server:8080 Connection refused"
+            ),
+            _ => panic!("expected BenchmarkClientFailed"),
+        }
+    }
+
+    #[test]
+    fn it_will_not_parse_truncated_wrk_output() {
+        let mut logger = Logger::default();
+        logger.quiet = true;
+        let mut listener = Benchmarker::new(&logger, LogBufferLimits::default());
+        listener
+            .write(format!("{}\n", WRK_OUTPUT[..6].join("\n")).as_bytes())
+            .unwrap();
+        match listener.results(Some(0)) {
+            Err(BenchmarkDataParseError) => {}
+            _ => panic!("expected BenchmarkDataParseError"),
+        }
+    }
+
+    #[test]
+    fn it_defaults_socket_errors_to_zero() {
+        let mut logger = Logger::default();
+        logger.quiet = true;
+        let mut listener = Benchmarker::new(&logger, LogBufferLimits::default());
+        let output: Vec<&str> = WRK_OUTPUT
+            .iter()
+            .filter(|line| !line.contains("Socket errors"))
+            .cloned()
+            .collect();
+        listener
+            .write(format!("{}\n", output.join("\n")).as_bytes())
+            .unwrap();
+
+        let results = listener.parse_wrk_output().unwrap();
+        assert_eq!(results.socket_errors, SocketErrors::default());
+    }
+
+    #[test]
+    fn it_can_check_socket_errors_against_a_threshold() {
+        let results = results(300, "1.00ms", "10.00ms");
+        assert_eq!(results.socket_errors.total(), 3);
+        assert!(results.check_socket_errors(0.01).is_ok());
+        match results.check_socket_errors(0.001) {
+            Err(SocketErrorThresholdExceeded { errors, .. }) => assert_eq!(errors, 3),
+            _ => panic!("expected SocketErrorThresholdExceeded"),
+        }
+    }
+}
@@ -0,0 +1,14 @@
+use curl::easy::{Handler, WriteError};
+
+/// Collects the body of a response, which may be split across several writes.
+#[derive(Default)]
+pub struct Body {
+    pub body: Vec<u8>,
+}
+impl Handler for Body {
+    fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
+        self.body.extend_from_slice(data);
+
+        Ok(data.len())
+    }
+}
@@ -0,0 +1,222 @@
+use curl::easy::{Handler, WriteError};
+use std::fmt;
+use std::str::FromStr;
+
+/// The length of the header Docker prefixes to each frame of a multiplexed
+/// stream: one byte for the stream type, three bytes of padding, and the
+/// big-endian `u32` size of the frame.
+const HEADER_LENGTH: usize = 8;
+
+/// The stream of a container to which a frame of its output was written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+impl OutputStream {
+    /// Gets the stream given by the stream type of a frame header; stdin is
+    /// only ever echoed to stdout.
+    fn from_stream_type(stream_type: u8) -> Self {
+        match stream_type {
+            2 => OutputStream::Stderr,
+            _ => OutputStream::Stdout,
+        }
+    }
+}
+impl fmt::Display for OutputStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputStream::Stdout => write!(f, "stdout"),
+            OutputStream::Stderr => write!(f, "stderr"),
+        }
+    }
+}
+
+/// The streams of a container's output to capture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttachStreams {
+    Stdout,
+    Stderr,
+    Both,
+}
+impl AttachStreams {
+    fn includes(&self, stream: OutputStream) -> bool {
+        match self {
+            AttachStreams::Both => true,
+            AttachStreams::Stdout => stream == OutputStream::Stdout,
+            AttachStreams::Stderr => stream == OutputStream::Stderr,
+        }
+    }
+}
+impl FromStr for AttachStreams {
+    type Err = String;
+
+    /// Parses `stdout`, `stderr`, or `both`.
+    fn from_str(streams: &str) -> Result<Self, Self::Err> {
+        match streams {
+            "stdout" => Ok(AttachStreams::Stdout),
+            "stderr" => Ok(AttachStreams::Stderr),
+            "both" => Ok(AttachStreams::Both),
+            _ => Err(format!(
+                "Streams must be `stdout`, `stderr`, or `both`: {}",
+                streams
+            )),
+        }
+    }
+}
+
+/// A `Handler` which is told the stream to which the output it is about to
+/// receive was written, so that it can tag the lines it logs.
+pub trait StreamHandler: Handler {
+    fn set_stream(&mut self, _stream: OutputStream) {}
+}
+
+/// Tags each line of `text` with the `stream` it was written to, if known;
+/// e.g. `[stderr] Connection refused`.
+pub fn tag_lines(stream: Option<OutputStream>, text: &str) -> String {
+    match stream {
+        Some(stream) => text
+            .lines()
+            .map(|line| format!("[{}] {}", stream, line))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        None => text.to_string(),
+    }
+}
+
+/// Wraps a `Handler` receiving a container's attached or logged output.
+///
+/// When a container is created without a TTY, Docker multiplexes its stdout
+/// and stderr into a single stream of frames, each prefixed with a header.
+/// This strips the headers and passes only the output of the selected
+/// `streams` on to `inner`, telling it which stream each frame came from and
+/// buffering frames which are split across writes. When the container has a
+/// TTY, Docker merges its streams, so the output is passed on untouched.
+#[derive(Clone)]
+pub struct Demultiplexer<H: StreamHandler> {
+    pub inner: H,
+    multiplexed: bool,
+    streams: AttachStreams,
+    buffer: Vec<u8>,
+}
+impl<H: StreamHandler> Demultiplexer<H> {
+    /// Creates a `Demultiplexer` for the given `streams` of the output of a
+    /// container which was created with (`allocate_tty`) or without a TTY.
+    pub fn new(inner: H, allocate_tty: bool, streams: AttachStreams) -> Self {
+        Self {
+            inner,
+            multiplexed: !allocate_tty,
+            streams,
+            buffer: Vec::new(),
+        }
+    }
+}
+impl<H: StreamHandler> Handler for Demultiplexer<H> {
+    fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
+        if !self.multiplexed {
+            return self.inner.write(data);
+        }
+
+        self.buffer.extend_from_slice(data);
+        while self.buffer.len() >= HEADER_LENGTH {
+            let stream = OutputStream::from_stream_type(self.buffer[0]);
+            let mut size = [0u8; 4];
+            size.copy_from_slice(&self.buffer[4..HEADER_LENGTH]);
+            let frame_length = HEADER_LENGTH + u32::from_be_bytes(size) as usize;
+            if self.buffer.len() < frame_length {
+                break;
+            }
+            let frame: Vec<u8> = self
+                .buffer
+                .drain(..frame_length)
+                .skip(HEADER_LENGTH)
+                .collect();
+            if self.streams.includes(stream) {
+                self.inner.set_stream(stream);
+                self.inner.write(&frame)?;
+            }
+        }
+
+        Ok(data.len())
+    }
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+pub mod tests {
+    use crate::docker::listener::demultiplexer::{
+        tag_lines, AttachStreams, Demultiplexer, OutputStream, StreamHandler,
+    };
+    use curl::easy::{Handler, WriteError};
+
+    /// Captures everything written to it.
+    #[derive(Clone, Default)]
+    struct Capture {
+        data: Vec<u8>,
+    }
+    impl Handler for Capture {
+        fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
+            self.data.extend_from_slice(data);
+            Ok(data.len())
+        }
+    }
+    impl StreamHandler for Capture {}
+
+    /// Multiplexes the given `lines` the way Docker does for a container
+    /// without a TTY; even lines on stdout and odd lines on stderr.
+    pub fn multiplex(lines: &[&str]) -> Vec<u8> {
+        let mut stream = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            let payload = format!("{}\n", line);
+            stream.push(if index % 2 == 0 { 1 } else { 2 });
+            stream.extend_from_slice(&[0, 0, 0]);
+            stream.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+            stream.extend_from_slice(payload.as_bytes());
+        }
+
+        stream
+    }
+
+    #[test]
+    fn it_can_demultiplex_split_frames() {
+        let stream = multiplex(&["first line", "second line"]);
+        let mut demultiplexer = Demultiplexer::new(Capture::default(), false, AttachStreams::Both);
+        for chunk in stream.chunks(5) {
+            assert_eq!(demultiplexer.write(chunk).unwrap(), chunk.len());
+        }
+
+        assert_eq!(demultiplexer.inner.data, b"first line\nsecond line\n");
+    }
+
+    #[test]
+    fn it_passes_tty_output_through() {
+        let mut demultiplexer = Demultiplexer::new(Capture::default(), true, AttachStreams::Both);
+        demultiplexer.write(b"first line\r\n").unwrap();
+
+        assert_eq!(demultiplexer.inner.data, b"first line\r\n");
+    }
+    #[test]
+    fn it_can_select_a_stream() {
+        // Even lines are written to stdout and odd lines to stderr.
+        let stream = multiplex(&["first line", "second line"]);
+        let mut demultiplexer =
+            Demultiplexer::new(Capture::default(), false, AttachStreams::Stderr);
+        demultiplexer.write(&stream).unwrap();
+
+        assert_eq!(demultiplexer.inner.data, b"second line\n");
+        assert_eq!("stdout".parse::<AttachStreams>(), Ok(AttachStreams::Stdout));
+        assert!("stdin".parse::<AttachStreams>().is_err());
+    }
+
+    #[test]
+    fn it_can_tag_lines_with_their_stream() {
+        assert_eq!(
+            tag_lines(Some(OutputStream::Stderr), "first\nsecond\n"),
+            "[stderr] first\n[stderr] second"
+        );
+        assert_eq!(tag_lines(None, "first"), "first");
+    }
+}
@@ -0,0 +1,117 @@
+use crate::docker::image::TOOLSET_IMAGE_LABEL;
+use curl::easy::{Handler, WriteError};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A container event reported by the Docker daemon.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerEvent {
+    /// Unix timestamp in seconds at which the event occurred.
+    pub time: i64,
+    pub action: String,
+    pub container_id: String,
+    pub attributes: HashMap<String, String>,
+}
+
+/// Listens to the Docker events stream and records the events for
+/// containers started by the toolset.
+pub struct Events {
+    buffer: Vec<u8>,
+    events: Arc<Mutex<Vec<DockerEvent>>>,
+    stop: Arc<AtomicBool>,
+}
+impl Events {
+    pub fn new(events: Arc<Mutex<Vec<DockerEvent>>>, stop: Arc<AtomicBool>) -> Self {
+        Self {
+            buffer: Vec::new(),
+            events,
+            stop,
+        }
+    }
+
+    /// Parses a single line of the events stream into a `DockerEvent`, if it
+    /// describes a container belonging to the toolset.
+    fn parse_event(line: &str) -> Option<DockerEvent> {
+        let json = serde_json::from_str::<Value>(line).ok()?;
+        let attributes: HashMap<String, String> = json["Actor"]["Attributes"]
+            .as_object()?
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+            .collect();
+
+        let is_toolset_container = attributes.contains_key(TOOLSET_IMAGE_LABEL)
+            || matches!(attributes.get("image"), Some(image) if image.starts_with("techempower/tfb."));
+        if !is_toolset_container {
+            return None;
+        }
+
+        Some(DockerEvent {
+            time: json["time"].as_i64()?,
+            action: json["Action"].as_str()?.to_string(),
+            container_id: json["Actor"]["ID"].as_str()?.to_string(),
+            attributes,
+        })
+    }
+}
+impl Handler for Events {
+    fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
+        self.buffer.extend_from_slice(data);
+        // Events are newline-delimited and may be split across writes, so
+        // only consume complete lines.
+        while let Some(index) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=index).collect();
+            if let Ok(line) = std::str::from_utf8(&line) {
+                if let Some(event) = Events::parse_event(line.trim()) {
+                    if let Ok(mut events) = self.events.lock() {
+                        events.push(event);
+                    }
+                }
+            }
+        }
+
+        Ok(data.len())
+    }
+
+    fn progress(&mut self, _dltotal: f64, _dlnow: f64, _ultotal: f64, _ulnow: f64) -> bool {
+        // Returning false aborts the otherwise never-ending events request.
+        !self.stop.load(Ordering::Acquire)
+    }
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::listener::events::Events;
+    use curl::easy::Handler;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn it_can_record_toolset_container_events() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut listener = Events::new(Arc::clone(&events), Arc::new(AtomicBool::new(false)));
+
+        let die = r#"{"status":"die","id":"abc","Type":"container","Action":"die","Actor":{"ID":"abc","Attributes":{"exitCode":"137","image":"tfb.test.gemini","tfb.toolset":""}},"time":1600000000}"#;
+        let other = r#"{"status":"die","id":"def","Type":"container","Action":"die","Actor":{"ID":"def","Attributes":{"exitCode":"0","image":"postgres"}},"time":1600000001}"#;
+        let oom = r#"{"status":"oom","id":"ghi","Type":"container","Action":"oom","Actor":{"ID":"ghi","Attributes":{"image":"techempower/tfb.database.postgres"}},"time":1600000002}"#;
+        let stream = format!("{}\n{}\n{}\n", die, other, oom);
+        let (first, second) = stream.split_at(40);
+        listener.write(first.as_bytes()).unwrap();
+        listener.write(second.as_bytes()).unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].action, "die");
+        assert_eq!(events[0].container_id, "abc");
+        assert_eq!(events[0].attributes["exitCode"], "137");
+        assert_eq!(events[1].action, "oom");
+        assert_eq!(events[1].time, 1600000002);
+    }
+}
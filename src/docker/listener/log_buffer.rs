@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+
+/// The line which replaces output dropped from a `LogBuffer`.
+const TRUNCATED_MARKER: &[u8] = b"[truncated]\n";
+
+/// The maximum amount of a container's output retained in memory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LogBufferLimits {
+    pub max_bytes: usize,
+    pub max_lines: usize,
+}
+impl Default for LogBufferLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 16 * 1024 * 1024,
+            max_lines: 100_000,
+        }
+    }
+}
+
+/// Retains the most recent output of a container, up to the given
+/// `LogBufferLimits`. Once either limit is exceeded, the oldest lines are
+/// dropped and replaced by a `[truncated]` marker, keeping memory bounded no
+/// matter how chatty the container is.
+#[derive(Clone, Debug)]
+pub struct LogBuffer {
+    limits: LogBufferLimits,
+    lines: VecDeque<Vec<u8>>,
+    bytes: usize,
+    truncated: bool,
+}
+impl LogBuffer {
+    pub fn new(limits: LogBufferLimits) -> Self {
+        Self {
+            limits,
+            lines: VecDeque::new(),
+            bytes: 0,
+            truncated: false,
+        }
+    }
+
+    /// Appends `data` to the retained output, dropping the oldest lines if
+    /// this exceeds the limits.
+    pub fn push(&mut self, data: &[u8]) {
+        for line in data.split_inclusive(|byte| *byte == b'\n') {
+            match self.lines.back_mut() {
+                Some(last) if !last.ends_with(b"\n") => last.extend_from_slice(line),
+                _ => self.lines.push_back(line.to_vec()),
+            }
+            self.bytes += line.len();
+        }
+
+        while self.bytes > self.limits.max_bytes || self.lines.len() > self.limits.max_lines {
+            match self.lines.pop_front() {
+                Some(line) => {
+                    self.bytes -= line.len();
+                    self.truncated = true;
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Gets the retained output, prefixed by a `[truncated]` marker if any
+    /// output was dropped.
+    pub fn contents(&self) -> Vec<u8> {
+        let mut contents = Vec::with_capacity(self.bytes + TRUNCATED_MARKER.len());
+        if self.truncated {
+            contents.extend_from_slice(TRUNCATED_MARKER);
+        }
+        for line in &self.lines {
+            contents.extend_from_slice(line);
+        }
+
+        contents
+    }
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::listener::log_buffer::{LogBuffer, LogBufferLimits};
+
+    #[test]
+    fn it_retains_output_within_its_limits() {
+        let mut buffer = LogBuffer::new(LogBufferLimits::default());
+        buffer.push(b"first li");
+        buffer.push(b"ne\nsecond line\n");
+
+        assert_eq!(buffer.contents(), b"first line\nsecond line\n");
+    }
+
+    #[test]
+    fn it_drops_the_oldest_lines_beyond_its_line_limit() {
+        let mut buffer = LogBuffer::new(LogBufferLimits {
+            max_bytes: 1024,
+            max_lines: 2,
+        });
+        buffer.push(b"one\ntwo\nthree\n");
+
+        assert_eq!(buffer.contents(), b"[truncated]\ntwo\nthree\n");
+    }
+
+    #[test]
+    fn it_drops_the_oldest_lines_beyond_its_byte_limit() {
+        let mut buffer = LogBuffer::new(LogBufferLimits {
+            max_bytes: 8,
+            max_lines: 100,
+        });
+        buffer.push(b"one\ntwo\nthree\n");
+
+        assert_eq!(buffer.contents(), b"[truncated]\nthree\n");
+    }
+}
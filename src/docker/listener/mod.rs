@@ -1,8 +1,12 @@
 pub mod application;
 pub mod benchmark_command_listener;
 pub mod benchmarker;
+pub mod body;
 pub mod build_container;
 pub mod build_image;
 pub mod build_network;
+pub mod demultiplexer;
+pub mod events;
+pub mod log_buffer;
 pub mod simple;
 pub mod verifier;
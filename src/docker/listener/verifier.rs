@@ -1,28 +1,45 @@
 // use crate::config::{Named, Project, Test};
+use crate::docker::listener::demultiplexer::{tag_lines, OutputStream, StreamHandler};
+use crate::docker::listener::log_buffer::LogBuffer;
 use crate::docker::Verification;
 use crate::io::Logger;
 use curl::easy::{Handler, WriteError};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 
 #[derive(Clone, Debug)]
 pub struct Verifier {
     pub verification: Arc<Mutex<Verification>>,
     logger: Logger,
+    output: Option<Arc<Mutex<LogBuffer>>>,
+    stream: Option<OutputStream>,
 }
 impl Verifier {
-    pub fn new(verification: Arc<Mutex<Verification>>, logger: &Logger) -> Self {
+    /// Creates a `Verifier` which parses the verifier's messages into
+    /// `verification` and, if given, also retains its raw output in `output`.
+    pub fn new(
+        verification: Arc<Mutex<Verification>>,
+        logger: &Logger,
+        output: Option<Arc<Mutex<LogBuffer>>>,
+    ) -> Self {
         let mut logger = logger.clone();
         logger.set_log_file("verifications.txt");
 
         Self {
             logger,
             verification,
+            output,
+            stream: None,
         }
     }
 }
 impl Handler for Verifier {
     fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
+        if let Some(output) = &self.output {
+            if let Ok(mut output) = output.lock() {
+                output.push(data);
+            }
+        }
         if let Ok(logs) = std::str::from_utf8(&data) {
             for line in logs.lines() {
                 if !line.trim().is_empty() {
@@ -35,7 +52,9 @@ impl Handler for Verifier {
                             verification.errors.push(error.error);
                         }
                     } else {
-                        self.logger.log(line.trim_end()).unwrap();
+                        self.logger
+                            .log(tag_lines(self.stream, line.trim_end()))
+                            .unwrap();
                     }
                 }
             }
@@ -44,13 +63,18 @@ impl Handler for Verifier {
         Ok(data.len())
     }
 }
+impl StreamHandler for Verifier {
+    fn set_stream(&mut self, stream: OutputStream) {
+        self.stream = Some(stream);
+    }
+}
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Warning {
     pub message: String,
     pub short_message: String,
 }
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Error {
     pub message: String,
     pub short_message: String,
@@ -64,3 +88,81 @@ struct WarningMessage {
 struct ErrorMessage {
     error: Error,
 }
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::listener::demultiplexer::tests::multiplex;
+    use crate::docker::listener::demultiplexer::{AttachStreams, Demultiplexer};
+    use crate::docker::listener::log_buffer::{LogBuffer, LogBufferLimits};
+    use crate::docker::listener::verifier::Verifier;
+    use crate::docker::Verification;
+    use crate::io::Logger;
+    use curl::easy::Handler;
+    use std::sync::{Arc, Mutex};
+
+    const VERIFIER_OUTPUT: [&str; 3] = [
+        "Verifying http://tfb-server:8080/json",
+        r#"{"warning":{"message":"Content-Type is missing charset","short_message":"Missing charset"}}"#,
+        r#"{"error":{"message":"Response body was empty","short_message":"Empty body"}}"#,
+    ];
+
+    #[test]
+    fn it_can_parse_tty_and_non_tty_verifier_output() {
+        let tty_output = format!("{}\r\n", VERIFIER_OUTPUT.join("\r\n")).into_bytes();
+        let non_tty_output = multiplex(&VERIFIER_OUTPUT);
+        for (output, allocate_tty) in &[(tty_output, true), (non_tty_output, false)] {
+            let verification = Arc::new(Mutex::new(Verification {
+                framework_name: "Gemini".to_string(),
+                test_name: "gemini".to_string(),
+                type_name: "json".to_string(),
+                database: None,
+                warnings: Vec::default(),
+                errors: Vec::default(),
+                logs: None,
+            }));
+            let mut logger = Logger::default();
+            logger.quiet = true;
+            let mut listener = Demultiplexer::new(
+                Verifier::new(Arc::clone(&verification), &logger, None),
+                *allocate_tty,
+                AttachStreams::Both,
+            );
+            listener.write(output).unwrap();
+
+            let verification = verification.lock().unwrap();
+            assert_eq!(verification.warnings.len(), 1);
+            assert_eq!(verification.warnings[0].short_message, "Missing charset");
+            assert_eq!(verification.errors.len(), 1);
+            assert_eq!(verification.errors[0].message, "Response body was empty");
+        }
+    }
+
+    #[test]
+    fn it_can_capture_verifier_output() {
+        let verification = Arc::new(Mutex::new(Verification {
+            framework_name: "Gemini".to_string(),
+            test_name: "gemini".to_string(),
+            type_name: "json".to_string(),
+            database: None,
+            warnings: Vec::default(),
+            errors: Vec::default(),
+            logs: None,
+        }));
+        let output = Arc::new(Mutex::new(LogBuffer::new(LogBufferLimits::default())));
+        let mut logger = Logger::default();
+        logger.quiet = true;
+        let mut listener = Verifier::new(verification, &logger, Some(Arc::clone(&output)));
+        listener
+            .write(b"Verifying http://tfb-server:8080/json\n")
+            .unwrap();
+
+        assert_eq!(
+            output.lock().unwrap().contents(),
+            b"Verifying http://tfb-server:8080/json\n"
+        );
+    }
+}
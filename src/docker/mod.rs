@@ -2,16 +2,32 @@
 //! This includes actions like building `Test` images, building containers for
 //! those images, and running containers in Docker.
 
+use crate::docker::listener::simple::Simple;
 use crate::docker::listener::verifier::Error;
 use crate::docker::listener::verifier::Warning;
-use serde::Deserialize;
+use crate::docker::slots::ContainerSlot;
+use crate::error::ToolsetError::TlsPortNotExposedError;
+use crate::error::ToolsetResult;
+use dockurl::container::inspect::ContainerInspection;
+use dockurl::container::inspect_container;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
 use std::task::Poll;
 
+pub mod command_cache;
 pub mod container;
 pub mod docker_config;
+pub mod events;
+pub mod host_config;
 pub mod image;
 pub mod listener;
 pub mod network;
+pub mod preflight;
+pub mod readiness;
+pub mod request;
+pub mod slots;
+pub mod wrk_command;
 
 #[derive(Debug)]
 pub struct DockerOrchestration {
@@ -22,30 +38,173 @@ pub struct DockerOrchestration {
     pub db_container_id: Option<String>,
     pub db_host_port: Option<String>,
     pub db_internal_port: Option<String>,
+    /// The test types to be run against `tls_internal_port` rather than
+    /// `host_internal_port`.
+    pub tls_test_types: Vec<String>,
+    /// The internal port serving TLS; `None` when the test has no TLS test
+    /// types or the container does not expose its TLS port.
+    pub tls_internal_port: Option<String>,
+    pub tls_port: u16,
 }
+impl DockerOrchestration {
+    /// Gets the internal port of the application to run `test_type` against;
+    /// its TLS port for a TLS test type. Fails rather than falling back to
+    /// the plaintext port, which would make for misleading results.
+    pub fn internal_port_for(&self, test_type: &str) -> ToolsetResult<String> {
+        if !self.tls_test_types.iter().any(|tls| tls == test_type) {
+            return Ok(self.host_internal_port.clone());
+        }
+        match &self.tls_internal_port {
+            Some(port) => Ok(port.clone()),
+            None => Err(TlsPortNotExposedError {
+                test_type: test_type.to_string(),
+                port: self.tls_port,
+            }),
+        }
+    }
 
-#[derive(Clone, Debug)]
+    /// Gets the URL scheme with which `test_type` is requested; `https` for a
+    /// TLS test type.
+    pub fn scheme_for(&self, test_type: &str) -> &'static str {
+        if self.tls_test_types.iter().any(|tls| tls == test_type) {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// Rewrites the plaintext URLs of the given `commands`, retrieved for
+    /// `test_type`, to its scheme; the verifier is given the TLS port, but
+    /// may still build `http://` URLs against it.
+    pub fn commands_for(&self, test_type: &str, commands: BenchmarkCommands) -> BenchmarkCommands {
+        if self.scheme_for(test_type) == "http" {
+            return commands;
+        }
+        let rewrite = |command: &[String]| -> Vec<String> {
+            command
+                .iter()
+                .map(|arg| match arg.strip_prefix("http://") {
+                    Some(rest) => format!("https://{}", rest),
+                    None => arg.clone(),
+                })
+                .collect()
+        };
+
+        BenchmarkCommands {
+            primer_command: rewrite(&commands.primer_command),
+            warmup_command: rewrite(&commands.warmup_command),
+            benchmark_commands: commands
+                .benchmark_commands
+                .iter()
+                .map(|command| rewrite(command))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
 pub struct Verification {
     pub framework_name: String,
     pub test_name: String,
     pub type_name: String,
+    /// The database the test was run against when sweeping databases.
+    pub database: Option<String>,
     pub warnings: Vec<Warning>,
     pub errors: Vec<Error>,
+    /// The verifier's output, when configured to capture it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logs: Option<String>,
+}
+impl Verification {
+    /// Gets the name of the test, tagged with the database it was run
+    /// against when sweeping databases; e.g. `gemini (postgres)`.
+    pub fn tagged_test_name(&self) -> String {
+        tag_with_database(&self.test_name, &self.database)
+    }
 }
 
-#[derive(Deserialize, Clone, Debug)]
+/// Tags the given `name` with the `database` being swept, if any.
+pub fn tag_with_database(name: &str, database: &Option<String>) -> String {
+    match database {
+        Some(database) => format!("{} ({})", name, database),
+        None => name.to_string(),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BenchmarkCommands {
     pub primer_command: Vec<String>,
     pub warmup_command: Vec<String>,
     pub benchmark_commands: Vec<Vec<String>>,
 }
 
+/// The details of a container captured at the point of a failure, for
+/// inclusion in the resulting error.
+#[derive(Clone, Debug, Default)]
+pub struct ContainerSnapshot {
+    pub container_id: String,
+    pub image: Option<String>,
+    pub command: Option<Vec<String>>,
+    pub status: Option<String>,
+    pub exit_code: Option<usize>,
+    pub error: Option<String>,
+}
+impl ContainerSnapshot {
+    /// Captures a snapshot of the container given by `container_id` by
+    /// inspecting it. If the inspection fails, only the `container_id` is
+    /// captured.
+    pub fn capture(container_id: &str, docker_host: &str, use_unix_socket: bool) -> Self {
+        match inspect_container(container_id, docker_host, use_unix_socket, Simple::new()) {
+            Ok(inspection) => Self::from_inspection(&inspection),
+            Err(_) => Self {
+                container_id: container_id.to_string(),
+                ..Self::default()
+            },
+        }
+    }
+
+    /// Captures a snapshot from an existing `inspection`.
+    pub fn from_inspection(inspection: &ContainerInspection) -> Self {
+        Self {
+            container_id: inspection.id.clone(),
+            image: Some(inspection.config.image.clone()),
+            command: inspection.config.cmd.clone(),
+            status: Some(inspection.state.status.clone()),
+            exit_code: Some(inspection.state.exit_code),
+            error: Some(inspection.state.error.clone()).filter(|error| !error.is_empty()),
+        }
+    }
+}
+impl fmt::Display for ContainerSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "container {}", self.container_id)?;
+        if let Some(image) = &self.image {
+            write!(f, "; image: {}", image)?;
+        }
+        if let Some(command) = &self.command {
+            write!(f, "; command: {}", command.join(" "))?;
+        }
+        if let Some(status) = &self.status {
+            write!(f, "; status: {}", status)?;
+        }
+        if let Some(exit_code) = &self.exit_code {
+            write!(f, "; exit code: {}", exit_code)?;
+        }
+        if let Some(error) = &self.error {
+            write!(f, "; error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DockerContainerIdFuture {
     requires_wait_to_stop: bool,
     container_id: Option<String>,
     image_id: Option<String>,
     docker_host: String,
+    keep: bool,
+    slot: Option<Arc<ContainerSlot>>,
 }
 impl DockerContainerIdFuture {
     pub fn new(docker_host: &str) -> Self {
@@ -54,6 +213,8 @@ impl DockerContainerIdFuture {
             container_id: None,
             image_id: None,
             docker_host: docker_host.to_string(),
+            keep: false,
+            slot: None,
         }
     }
 
@@ -66,9 +227,40 @@ impl DockerContainerIdFuture {
         self.container_id = Some(container_id.to_string());
     }
 
+    /// Gets the ID of the registered container, if any.
+    pub fn registered_id(&self) -> Option<String> {
+        self.container_id.clone()
+    }
+
+    /// Holds the given container `slot` until this container is torn down.
+    pub fn hold(&mut self, slot: ContainerSlot) {
+        self.slot = Some(Arc::new(slot));
+    }
+
     pub fn unregister(&mut self) {
         self.requires_wait_to_stop = false;
         self.container_id = None;
+        self.keep = false;
+        self.slot = None;
+    }
+
+    /// Marks the registered container (and its image) to be kept rather than
+    /// removed when it is stopped; e.g. so that a container which failed
+    /// verification can be investigated.
+    pub fn keep(&mut self) {
+        self.keep = true;
+    }
+
+    /// Whether there is neither a registered container nor an image left to
+    /// tear down.
+    fn is_torn_down(&self) -> bool {
+        self.container_id.is_none() && self.image_id.is_none()
+    }
+
+    /// Releases the held container slot, if any, without unregistering; e.g.
+    /// for a group torn down before its container was registered.
+    fn release_slot(&mut self) {
+        self.slot = None;
     }
 
     fn poll(&self) -> Poll<()> {
@@ -83,3 +275,122 @@ impl DockerContainerIdFuture {
         }
     }
 }
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::slots::ContainerSlots;
+    use crate::docker::{
+        tag_with_database, BenchmarkCommands, ContainerSnapshot, DockerContainerIdFuture,
+        DockerOrchestration,
+    };
+    use crate::error::ToolsetError::TlsPortNotExposedError;
+
+    #[test]
+    fn it_can_display_a_container_snapshot() {
+        let snapshot = ContainerSnapshot {
+            container_id: "abc".to_string(),
+            image: Some("tfb.test.gemini".to_string()),
+            command: Some(vec!["sh".to_string(), "start.sh".to_string()]),
+            status: Some("exited".to_string()),
+            exit_code: Some(137),
+            error: None,
+        };
+        assert_eq!(
+            snapshot.to_string(),
+            "container abc; image: tfb.test.gemini; command: sh start.sh; status: exited; exit code: 137"
+        );
+
+        let snapshot = ContainerSnapshot {
+            container_id: "def".to_string(),
+            ..ContainerSnapshot::default()
+        };
+        assert_eq!(snapshot.to_string(), "container def");
+    }
+
+    #[test]
+    fn it_forgets_to_keep_a_container_once_unregistered() {
+        let mut container = DockerContainerIdFuture::new("localhost:2375");
+        container.register("abc");
+        container.keep();
+        assert!(container.keep);
+
+        container.unregister();
+        assert!(!container.keep);
+    }
+
+    #[test]
+    fn it_releases_its_slot_once_unregistered() {
+        let slots = ContainerSlots::new(1);
+        let mut container = DockerContainerIdFuture::new("localhost:2375");
+        container.hold(slots.acquire());
+        container.register("abc");
+        assert!(container.slot.is_some());
+
+        container.unregister();
+        assert!(container.slot.is_none());
+        drop(slots.acquire());
+    }
+
+    #[test]
+    fn it_tags_names_with_the_swept_database() {
+        assert_eq!(tag_with_database("gemini", &None), "gemini");
+        assert_eq!(
+            tag_with_database("gemini", &Some("postgres".to_string())),
+            "gemini (postgres)"
+        );
+    }
+
+    #[test]
+    fn it_runs_tls_test_types_against_the_tls_port() {
+        let mut orchestration = DockerOrchestration {
+            host_container_id: "abc".to_string(),
+            host_port: "32768".to_string(),
+            host_internal_port: "8080".to_string(),
+            database_name: None,
+            db_container_id: None,
+            db_host_port: None,
+            db_internal_port: None,
+            tls_test_types: vec!["json-tls".to_string()],
+            tls_internal_port: Some("8443".to_string()),
+            tls_port: 8443,
+        };
+        assert_eq!(orchestration.internal_port_for("json").unwrap(), "8080");
+        assert_eq!(orchestration.internal_port_for("json-tls").unwrap(), "8443");
+
+        assert_eq!(orchestration.scheme_for("json"), "http");
+        assert_eq!(orchestration.scheme_for("json-tls"), "https");
+
+        // The verifier is given the TLS port, and the URLs of the commands it
+        // returns are requested over TLS.
+        let commands = BenchmarkCommands {
+            primer_command: vec!["wrk".to_string(), "http://tfb-server:8443/json".to_string()],
+            warmup_command: vec!["wrk".to_string(), "-H".to_string(), "Host: x".to_string()],
+            benchmark_commands: vec![vec![
+                "wrk".to_string(),
+                "http://tfb-server:8443/json".to_string(),
+            ]],
+        };
+        let tls_commands = orchestration.commands_for("json-tls", commands.clone());
+        assert_eq!(
+            tls_commands.primer_command[1],
+            "https://tfb-server:8443/json"
+        );
+        assert_eq!(tls_commands.warmup_command, commands.warmup_command);
+        assert_eq!(
+            tls_commands.benchmark_commands[0][1],
+            "https://tfb-server:8443/json"
+        );
+        let plaintext_commands = orchestration.commands_for("json", commands.clone());
+        assert_eq!(plaintext_commands.primer_command, commands.primer_command);
+
+        orchestration.tls_internal_port = None;
+        match orchestration.internal_port_for("json-tls") {
+            Err(TlsPortNotExposedError { port, .. }) => assert_eq!(port, 8443),
+            _ => panic!("expected TlsPortNotExposedError"),
+        }
+    }
+}
@@ -1,9 +1,24 @@
-use crate::docker::docker_config::DockerConfig;
+use crate::docker::docker_config::{DockerConfig, DockerTls};
 use crate::docker::listener::build_network::BuildNetwork;
 use crate::docker::listener::simple::Simple;
-use crate::error::ToolsetError::DockerError;
+use crate::docker::request::{docker_request, Method};
+use crate::error::ToolsetError::{
+    DockerError, DockerRequestFailed, FailedToDetachStaleEndpoint, InvalidNetworkMode,
+};
 use crate::error::ToolsetResult;
+use crate::io::Logger;
+use crate::options;
+use colored::Colorize;
+use dockurl::error::DockerError::{
+    DockerNetworkAlreadyExistsCreateError, DockerNetworkCreateError,
+    FailedToCreateDockerNetworkError,
+};
 use dockurl::network::NetworkMode;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// The bridge driver option which sets the MTU of a network.
+const MTU_OPTION: &str = "com.docker.network.driver.mtu";
 
 /// Gets the network id for the given `docker_host` and `network_name`.
 pub fn get_network_id(
@@ -24,11 +39,31 @@ pub fn get_network_id(
 
 /// Gets the network id for the "TFBNetwork" on the given `docker_host`.
 /// Will create the network if it does not already exist.
-pub fn get_tfb_network_id(use_unix_socket: bool, docker_host: &str) -> ToolsetResult<String> {
+///
+/// Note: Docker only allows the MTU to be set on a network when it is
+/// created; `mtu` has no effect on an existing "TFBNetwork", which must be
+/// removed for a new `mtu` to take effect.
+pub fn get_tfb_network_id(
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+    docker_host: &str,
+    mtu: Option<u32>,
+) -> ToolsetResult<String> {
     if let Ok(network) =
         dockurl::network::inspect_network("TFBNetwork", docker_host, use_unix_socket, Simple::new())
     {
+        if let Some(mtu) = mtu {
+            Logger::default().log(
+                format!(
+                    "TFBNetwork already exists; remove it for an MTU of {} to take effect",
+                    mtu
+                )
+                .yellow(),
+            )?;
+        }
         Ok(network.id)
+    } else if let Some(mtu) = mtu {
+        create_bridge_network_with_mtu("TFBNetwork", mtu, docker_host, use_unix_socket, tls)
     } else {
         match dockurl::network::create_network(
             "TFBNetwork",
@@ -43,6 +78,20 @@ pub fn get_tfb_network_id(use_unix_socket: bool, docker_host: &str) -> ToolsetRe
     }
 }
 
+/// Parses the given network mode case-insensitively; `bridge` or `host`.
+///
+/// Note: `NetworkMode` belongs to `dockurl`, so this cannot be a `FromStr`
+/// implementation.
+pub fn parse_network_mode(value: &str) -> ToolsetResult<NetworkMode> {
+    match value.to_lowercase().as_str() {
+        options::network_modes::BRIDGE => Ok(NetworkMode::Bridge),
+        options::network_modes::HOST => Ok(NetworkMode::Host),
+        _ => Err(InvalidNetworkMode {
+            value: value.to_string(),
+        }),
+    }
+}
+
 /// Attaches the container given by `container_id` to the network given by
 /// `network_id` on the given `docker_host`.
 pub fn connect_container_to_network(
@@ -63,3 +112,194 @@ pub fn connect_container_to_network(
         Err(error) => Err(DockerError(error)),
     }
 }
+
+/// Force-detaches the endpoints of the network given by `network_id` on
+/// `docker_host` whose containers are no longer running; e.g. those left
+/// behind by a run which crashed before tearing its containers down, which
+/// would otherwise collide with the endpoints of this run. Returns the names
+/// of the detached endpoints.
+pub fn detach_stale_endpoints(
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+    docker_host: &str,
+    network_id: &str,
+) -> ToolsetResult<Vec<String>> {
+    let network = docker_request(
+        use_unix_socket,
+        tls,
+        docker_host,
+        Method::Get,
+        &format!("/networks/{}", network_id),
+        None,
+    )?;
+    let running_containers = docker_request(
+        use_unix_socket,
+        tls,
+        docker_host,
+        Method::Get,
+        "/containers/json",
+        None,
+    )?;
+
+    let endpoints = stale_endpoints(&network, &running_containers);
+    for endpoint in &endpoints {
+        disconnect_endpoint(use_unix_socket, tls, docker_host, network_id, endpoint)?;
+    }
+
+    Ok(endpoints)
+}
+
+//
+// PRIVATES
+//
+
+/// Force-disconnects the endpoint named `endpoint` from the network given by
+/// `network_id`; forcing allows endpoints whose containers no longer exist to
+/// be disconnected.
+fn disconnect_endpoint(
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+    docker_host: &str,
+    network_id: &str,
+    endpoint: &str,
+) -> ToolsetResult<()> {
+    match docker_request(
+        use_unix_socket,
+        tls,
+        docker_host,
+        Method::Post(Some(
+            serde_json::json!({ "Container": endpoint, "Force": true }),
+        )),
+        &format!("/networks/{}/disconnect", network_id),
+        None,
+    ) {
+        Ok(_) => Ok(()),
+        Err(DockerRequestFailed { .. }) => Err(FailedToDetachStaleEndpoint {
+            network_id: network_id.to_string(),
+            endpoint: endpoint.to_string(),
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+/// Gets the names of the endpoints of the inspected `network` whose
+/// containers are not among the listed `running_containers`.
+fn stale_endpoints(network: &[u8], running_containers: &[u8]) -> Vec<String> {
+    let running = match serde_json::from_slice::<Value>(running_containers) {
+        Ok(Value::Array(containers)) => containers
+            .iter()
+            .filter_map(|container| container["Id"].as_str())
+            .map(str::to_string)
+            .collect::<HashSet<String>>(),
+        _ => return Vec::default(),
+    };
+    let endpoints = match serde_json::from_slice::<Value>(network) {
+        Ok(network) => match network["Containers"].as_object() {
+            Some(endpoints) => endpoints.clone(),
+            None => return Vec::default(),
+        },
+        _ => return Vec::default(),
+    };
+
+    endpoints
+        .iter()
+        .filter(|(container_id, _)| !running.contains(*container_id))
+        .filter_map(|(_, endpoint)| endpoint["Name"].as_str())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Creates a bridge network named `network_name` whose MTU is `mtu`.
+fn create_bridge_network_with_mtu(
+    network_name: &str,
+    mtu: u32,
+    docker_host: &str,
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+) -> ToolsetResult<String> {
+    match docker_request(
+        use_unix_socket,
+        tls,
+        docker_host,
+        Method::Post(Some(network_creation_options(network_name, mtu))),
+        "/networks/create",
+        None,
+    ) {
+        Ok(body) => match serde_json::from_slice::<Value>(&body) {
+            Ok(network) => match network["Id"].as_str() {
+                Some(network_id) => Ok(network_id.chars().take(12).collect()),
+                None => Err(DockerError(DockerNetworkCreateError)),
+            },
+            Err(_) => Err(DockerError(DockerNetworkCreateError)),
+        },
+        Err(DockerRequestFailed { code: 409, .. }) => Err(DockerError(
+            DockerNetworkAlreadyExistsCreateError(network_name.to_string()),
+        )),
+        Err(DockerRequestFailed { message, .. }) => {
+            Err(DockerError(FailedToCreateDockerNetworkError(message)))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Gets the body of the request to create a bridge network named
+/// `network_name` whose MTU is `mtu`.
+fn network_creation_options(network_name: &str, mtu: u32) -> Value {
+    serde_json::json!({
+        "Name": network_name,
+        "Driver": NetworkMode::Bridge.to_string(),
+        "Internal": false,
+        "CheckDuplicate": true,
+        "Options": {
+            MTU_OPTION: mtu.to_string(),
+        },
+    })
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::network::{network_creation_options, parse_network_mode, stale_endpoints};
+    use crate::error::ToolsetError::InvalidNetworkMode;
+    use dockurl::network::NetworkMode;
+
+    #[test]
+    fn it_sets_the_mtu_when_creating_a_network() {
+        let options = network_creation_options("TFBNetwork", 1450);
+        assert_eq!(options["Name"], "TFBNetwork");
+        assert_eq!(options["Driver"], "bridge");
+        assert_eq!(options["Options"]["com.docker.network.driver.mtu"], "1450");
+    }
+
+    #[test]
+    fn it_parses_network_modes_case_insensitively() {
+        assert!(matches!(
+            parse_network_mode("bridge"),
+            Ok(NetworkMode::Bridge)
+        ));
+        assert!(matches!(parse_network_mode("Host"), Ok(NetworkMode::Host)));
+        match parse_network_mode("bridged") {
+            Err(InvalidNetworkMode { value }) => assert_eq!(value, "bridged"),
+            _ => panic!("expected InvalidNetworkMode"),
+        }
+    }
+
+    #[test]
+    fn it_finds_endpoints_whose_containers_are_not_running() {
+        let network = br#"{
+            "Id": "7d86d31b1478",
+            "Containers": {
+                "19a4d5d687db": { "Name": "running", "EndpointID": "628cadb8bcb9" },
+                "ep-b8c2a6b9f4c1": { "Name": "crashed", "EndpointID": "b8c2a6b9f4c1" }
+            }
+        }"#;
+        let running = br#"[{ "Id": "19a4d5d687db" }]"#;
+
+        assert_eq!(stale_endpoints(network, running), vec!["crashed"]);
+        assert!(stale_endpoints(br#"{ "Containers": {} }"#, running).is_empty());
+        assert!(stale_endpoints(network, b"").is_empty());
+    }
+}
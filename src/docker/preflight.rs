@@ -0,0 +1,415 @@
+//! Checks that the Docker environment is fit for a run (the daemons are
+//! reachable, the verifier image is available, the networks exist, and the
+//! host allows the limits containers request) so that a misconfiguration is
+//! caught in seconds rather than deep into a run.
+
+use crate::docker::container::{APPLICATION_OPEN_FILE_LIMIT, SOCKET_BACKLOG};
+use crate::docker::docker_config::DockerEnvironment;
+use crate::docker::image::{image_exists, image_is_pullable, VERIFIER_IMAGE};
+use crate::docker::network::get_network_id;
+use crate::docker::request::{docker_request, Method};
+use crate::error::ToolsetError::DockerRequestFailed;
+use crate::error::ToolsetResult;
+use dockurl::network::NetworkMode;
+use std::path::Path;
+use std::time::Duration;
+
+/// How long the Docker daemon is given to answer a ping.
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The outcome of a single check of the Docker environment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreflightCheck {
+    pub name: String,
+    pub passed: bool,
+    /// What was found and, on failure, what to do about it.
+    pub message: String,
+}
+
+/// The outcome of every check of the Docker environment.
+#[derive(Clone, Debug, Default)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+impl PreflightReport {
+    /// Whether every check passed.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    /// Gets the checks which failed.
+    pub fn failures(&self) -> Vec<&PreflightCheck> {
+        self.checks.iter().filter(|check| !check.passed).collect()
+    }
+}
+
+/// Checks the Docker environment described by `environment` without changing
+/// it; no network is created nor container started.
+pub fn check_environment(environment: &DockerEnvironment) -> ToolsetResult<PreflightReport> {
+    let mut report = PreflightReport::default();
+    for docker_host in docker_hosts(environment) {
+        report.checks.push(check_daemon(environment, &docker_host));
+    }
+    report.checks.push(check_verifier_image(environment));
+    for (role, docker_host) in networks(environment) {
+        report.checks.push(check_network(
+            environment.use_unix_socket,
+            &environment.network_mode,
+            role,
+            &docker_host,
+        ));
+    }
+    if is_local(environment, &environment.server_docker_host) {
+        report.checks.push(check_kernel_limit(
+            "fs.nr_open",
+            read_kernel_limit("/proc/sys/fs/nr_open"),
+            u64::from(APPLICATION_OPEN_FILE_LIMIT),
+            "the open file limit of application containers",
+        ));
+        // Containers on a bridge network have their own network namespace,
+        // in which Docker sets the backlog; in host mode, the host's applies.
+        if let NetworkMode::Host = environment.network_mode {
+            report.checks.push(check_kernel_limit(
+                "net.core.somaxconn",
+                read_kernel_limit("/proc/sys/net/core/somaxconn"),
+                u64::from(SOCKET_BACKLOG),
+                "the socket backlog of containers in host network mode",
+            ));
+        }
+    }
+
+    Ok(report)
+}
+
+//
+// PRIVATES
+//
+impl PreflightCheck {
+    fn passed(name: &str, message: String) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: true,
+            message,
+        }
+    }
+
+    fn failed(name: &str, message: String) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: false,
+            message,
+        }
+    }
+}
+
+/// Gets every Docker host of the run, once each.
+fn docker_hosts(environment: &DockerEnvironment) -> Vec<String> {
+    let mut docker_hosts = vec![
+        environment.server_docker_host.clone(),
+        environment.database_docker_host.clone(),
+        environment.client_docker_host.clone(),
+    ];
+    docker_hosts.extend(environment.replica_docker_hosts.iter().cloned());
+    let mut unique = Vec::with_capacity(docker_hosts.len());
+    for docker_host in docker_hosts {
+        if !unique.contains(&docker_host) {
+            unique.push(docker_host);
+        }
+    }
+
+    unique
+}
+
+/// Gets the Docker host on which each role's containers join the network,
+/// once each. On a bridge network, the application, database and verifier
+/// containers all join the TFBNetwork of the database Docker host.
+fn networks(environment: &DockerEnvironment) -> Vec<(&'static str, String)> {
+    let mut networks = match environment.network_mode {
+        NetworkMode::Bridge => vec![(
+            "application, database and verifier",
+            environment.database_docker_host.clone(),
+        )],
+        NetworkMode::Host => vec![
+            ("application", environment.server_docker_host.clone()),
+            ("database", environment.database_docker_host.clone()),
+            ("verifier", environment.client_docker_host.clone()),
+        ],
+    };
+    for docker_host in &environment.replica_docker_hosts {
+        networks.push(("benchmarker", docker_host.clone()));
+    }
+    let mut unique: Vec<(&'static str, String)> = Vec::with_capacity(networks.len());
+    for network in networks {
+        if !unique.iter().any(|(_, host)| *host == network.1) {
+            unique.push(network);
+        }
+    }
+
+    unique
+}
+
+/// Checks that the Docker daemon on `docker_host` answers a ping.
+fn check_daemon(environment: &DockerEnvironment, docker_host: &str) -> PreflightCheck {
+    let use_unix_socket = environment.use_unix_socket;
+    let tls = environment.docker_tls.as_ref();
+    let via = match (use_unix_socket, tls) {
+        (true, _) => "via /var/run/docker.sock",
+        (false, Some(_)) => "over TLS",
+        (false, None) => "over TCP",
+    };
+    match docker_request(
+        use_unix_socket,
+        tls,
+        docker_host,
+        Method::Get,
+        "/_ping",
+        Some(PING_TIMEOUT),
+    ) {
+        Ok(_) => PreflightCheck::passed(
+            "daemon",
+            format!("Docker daemon reachable at {} {}", docker_host, via),
+        ),
+        Err(DockerRequestFailed { code, .. }) => PreflightCheck::failed(
+            "daemon",
+            format!(
+                "Docker daemon at {} {} answered a ping with {}",
+                docker_host, via, code
+            ),
+        ),
+        Err(error) => PreflightCheck::failed(
+            "daemon",
+            format!(
+                "Docker daemon unreachable at {} {}; is it running{}? ({})",
+                docker_host,
+                via,
+                match (use_unix_socket, tls) {
+                    (true, _) => "",
+                    (false, Some(_)) => " and listening for TLS on TCP port 2376",
+                    (false, None) => " and listening on TCP port 2375",
+                },
+                error
+            ),
+        ),
+    }
+}
+
+/// Checks that the verifier image is present on the client Docker host, or
+/// can be built or pulled.
+fn check_verifier_image(environment: &DockerEnvironment) -> PreflightCheck {
+    if let Some(context) = environment.verifier_build_context {
+        return if Path::new(context).join("Dockerfile").exists() {
+            PreflightCheck::passed(
+                "verifier image",
+                format!("Verifier will be built from {}", context),
+            )
+        } else {
+            PreflightCheck::failed(
+                "verifier image",
+                format!("No Dockerfile in the verifier build context {}", context),
+            )
+        };
+    }
+
+    let docker_host = &environment.client_docker_host;
+    let available = || -> ToolsetResult<Option<&str>> {
+        let tls = environment.docker_tls.as_ref();
+        if image_exists(
+            VERIFIER_IMAGE,
+            docker_host,
+            environment.use_unix_socket,
+            tls,
+        )? {
+            Ok(Some("is present on"))
+        } else if image_is_pullable(
+            VERIFIER_IMAGE,
+            docker_host,
+            environment.use_unix_socket,
+            tls,
+        )? {
+            Ok(Some("will be pulled to"))
+        } else {
+            Ok(None)
+        }
+    };
+
+    match available() {
+        Ok(Some(how)) => PreflightCheck::passed(
+            "verifier image",
+            format!("{} {} {}", VERIFIER_IMAGE, how, docker_host),
+        ),
+        Ok(None) => PreflightCheck::failed(
+            "verifier image",
+            format!(
+                "{} is neither present on nor pullable by {}; check its registry access, \
+                or build it with --verifier-build-context",
+                VERIFIER_IMAGE, docker_host
+            ),
+        ),
+        Err(error) => PreflightCheck::failed(
+            "verifier image",
+            format!(
+                "Could not query {} for {} ({:?})",
+                docker_host, VERIFIER_IMAGE, error
+            ),
+        ),
+    }
+}
+
+/// Checks the network which the containers of `role` join on `docker_host`:
+/// the host network must exist, while a missing TFBNetwork is created by the
+/// run.
+fn check_network(
+    use_unix_socket: bool,
+    network_mode: &NetworkMode,
+    role: &str,
+    docker_host: &str,
+) -> PreflightCheck {
+    let network_name = match network_mode {
+        NetworkMode::Bridge => "TFBNetwork",
+        NetworkMode::Host => "host",
+    };
+    match (
+        get_network_id(use_unix_socket, docker_host, network_name),
+        network_mode,
+    ) {
+        (Ok(_), _) => PreflightCheck::passed(
+            "network",
+            format!(
+                "Network {} exists on {} for {} containers",
+                network_name, docker_host, role
+            ),
+        ),
+        (Err(_), NetworkMode::Bridge) => PreflightCheck::passed(
+            "network",
+            format!(
+                "Network {} will be created on {} for {} containers",
+                network_name, docker_host, role
+            ),
+        ),
+        (Err(_), NetworkMode::Host) => PreflightCheck::failed(
+            "network",
+            format!(
+                "Network {} does not exist on {}, so {} containers cannot join it",
+                network_name, docker_host, role
+            ),
+        ),
+    }
+}
+
+/// Checks that the kernel limit `name`, whose current value is `value` (if it
+/// could be read), allows the `required` value needed for `purpose`.
+fn check_kernel_limit(
+    name: &str,
+    value: Option<u64>,
+    required: u64,
+    purpose: &str,
+) -> PreflightCheck {
+    match value {
+        Some(value) if value >= required => PreflightCheck::passed(
+            name,
+            format!("{} is {}; at least {} is required", name, value, required),
+        ),
+        Some(value) => PreflightCheck::failed(
+            name,
+            format!(
+                "{} is {}, below the {} required for {}; raise it with `sysctl -w {}={}`",
+                name, value, required, purpose, name, required
+            ),
+        ),
+        None => PreflightCheck::passed(
+            name,
+            format!("{} could not be read; it was not checked", name),
+        ),
+    }
+}
+
+/// Reads the kernel limit at `path`; e.g. `/proc/sys/fs/nr_open`.
+fn read_kernel_limit(path: &str) -> Option<u64> {
+    std::fs::read_to_string(path)
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+}
+
+/// Whether `docker_host` is this host, whose kernel limits can be read.
+fn is_local(environment: &DockerEnvironment, docker_host: &str) -> bool {
+    environment.use_unix_socket
+        || docker_host.starts_with("localhost:")
+        || docker_host.starts_with("127.0.0.1:")
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::docker_config::DockerEnvironment;
+    use crate::docker::preflight::{check_kernel_limit, networks, PreflightCheck, PreflightReport};
+    use dockurl::network::NetworkMode;
+
+    #[test]
+    fn it_checks_kernel_limits_against_what_is_required() {
+        assert!(check_kernel_limit("fs.nr_open", Some(1_048_576), 200_000, "files").passed);
+        assert!(check_kernel_limit("fs.nr_open", None, 200_000, "files").passed);
+
+        let check = check_kernel_limit("fs.nr_open", Some(65_536), 200_000, "files");
+        assert!(!check.passed);
+        assert!(check.message.contains("sysctl -w fs.nr_open=200000"));
+    }
+
+    #[test]
+    fn it_fails_when_any_check_fails() {
+        let mut report = PreflightReport::default();
+        report
+            .checks
+            .push(PreflightCheck::passed("daemon", String::default()));
+        assert!(report.passed());
+
+        report
+            .checks
+            .push(PreflightCheck::failed("network", String::default()));
+        assert!(!report.passed());
+        assert_eq!(report.failures()[0].name, "network");
+    }
+
+    #[test]
+    fn it_checks_the_network_of_each_docker_host_once() {
+        let mut environment = DockerEnvironment {
+            use_unix_socket: false,
+            docker_tls: None,
+            server_docker_host: "tfb-server:2375".to_string(),
+            database_docker_host: "tfb-database:2375".to_string(),
+            client_docker_host: "tfb-client:2375".to_string(),
+            replica_docker_hosts: vec![
+                "tfb-client:2375".to_string(),
+                "tfb-client-2:2375".to_string(),
+            ],
+            network_mode: NetworkMode::Bridge,
+            verifier_build_context: None,
+        };
+        assert_eq!(
+            networks(&environment),
+            vec![
+                (
+                    "application, database and verifier",
+                    "tfb-database:2375".to_string()
+                ),
+                ("benchmarker", "tfb-client:2375".to_string()),
+                ("benchmarker", "tfb-client-2:2375".to_string()),
+            ]
+        );
+
+        environment.network_mode = NetworkMode::Host;
+        assert_eq!(
+            networks(&environment),
+            vec![
+                ("application", "tfb-server:2375".to_string()),
+                ("database", "tfb-database:2375".to_string()),
+                ("verifier", "tfb-client:2375".to_string()),
+                ("benchmarker", "tfb-client-2:2375".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Probes used to determine when an application container is ready to accept
+//! requests.
+
+use crate::docker::listener::simple::Simple;
+use crate::error::ToolsetError::BenchmarkTargetUnreachable;
+use crate::error::ToolsetResult;
+use curl::easy::Easy2;
+use rand::Rng;
+use std::fmt;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+/// How long a single probe may take before it is considered failed.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The check performed against the application container to determine that
+/// it is accepting requests.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadinessProbe {
+    /// Any HTTP response from the first URL configured for the `Test`.
+    TestUrl,
+    /// Any HTTP response from the given path.
+    Path(String),
+    /// A successful TCP connection to the exposed port.
+    Tcp,
+}
+impl FromStr for ReadinessProbe {
+    type Err = String;
+
+    /// Parses `url`, `tcp`, or an absolute path such as `/health`.
+    fn from_str(probe: &str) -> Result<Self, Self::Err> {
+        match probe {
+            "url" => Ok(ReadinessProbe::TestUrl),
+            "tcp" => Ok(ReadinessProbe::Tcp),
+            path if path.starts_with('/') => Ok(ReadinessProbe::Path(path.to_string())),
+            _ => Err(format!(
+                "Readiness probe must be `url`, `tcp`, or a path starting with `/`: {}",
+                probe
+            )),
+        }
+    }
+}
+impl fmt::Display for ReadinessProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadinessProbe::TestUrl => write!(f, "url"),
+            ReadinessProbe::Path(path) => write!(f, "{}", path),
+            ReadinessProbe::Tcp => write!(f, "tcp"),
+        }
+    }
+}
+
+/// Exponential backoff with jitter between readiness checks, bounded by a
+/// total wait. Jitter keeps the checks from falling into step with a service
+/// which restarts during its initialization (e.g. MySQL on first run).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Backoff {
+    /// The delay after the first failed check.
+    pub initial: Duration,
+    /// The longest delay between any two checks.
+    pub max: Duration,
+    /// The longest total wait, after which checking gives up.
+    pub timeout: Duration,
+}
+impl Backoff {
+    /// Gets the delay after the given zero-based failed `attempt`; the capped
+    /// exponential delay for that attempt, of which a random half is dropped.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .initial
+            .checked_mul(2u32.saturating_pow(attempt))
+            .map_or(self.max, |delay| delay.min(self.max));
+        let half = delay / 2;
+
+        half + half.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+/// Performs a single `probe` against the application listening on `host` and
+/// `port`, over TLS when `tls` is set, returning whether it is ready.
+/// `test_url` is the endpoint probed by `ReadinessProbe::TestUrl`.
+pub fn is_ready(probe: &ReadinessProbe, tls: bool, host: &str, port: &str, test_url: &str) -> bool {
+    match probe {
+        ReadinessProbe::TestUrl => responds_to_http(tls, host, port, test_url),
+        ReadinessProbe::Path(path) => responds_to_http(tls, host, port, path),
+        ReadinessProbe::Tcp => accepts_tcp_connection(host, port),
+    }
+}
+
+/// Confirms that the benchmark target listening on `host` and `port` accepts
+/// TCP connections, as found by `connects`, before a benchmark is run against
+/// it, retrying up to `retries` times `retry_interval` apart.
+pub fn preflight(
+    host: &str,
+    port: &str,
+    retries: u32,
+    retry_interval: Duration,
+    mut connects: impl FnMut() -> ToolsetResult<bool>,
+) -> ToolsetResult<()> {
+    for attempt in 0..=retries {
+        if connects()? {
+            return Ok(());
+        }
+        if attempt < retries {
+            thread::sleep(retry_interval);
+        }
+    }
+
+    Err(BenchmarkTargetUnreachable {
+        host: host.to_string(),
+        port: port.to_string(),
+        attempts: retries + 1,
+    })
+}
+
+//
+// PRIVATES
+//
+
+/// Whether the application responds to an HTTP request for `path` with any
+/// response at all. Over TLS, the application's certificate is not verified;
+/// test applications commonly serve a self-signed one.
+fn responds_to_http(tls: bool, host: &str, port: &str, path: &str) -> bool {
+    let scheme = if tls { "https" } else { "http" };
+    let mut easy = Easy2::new(Simple::new());
+    if easy
+        .url(&format!("{}://{}:{}{}", scheme, host, port, path))
+        .is_err()
+        || easy.timeout(PROBE_TIMEOUT).is_err()
+        || easy.ssl_verify_peer(false).is_err()
+        || easy.ssl_verify_host(false).is_err()
+    {
+        return false;
+    }
+    let _ = easy.perform();
+
+    matches!(easy.response_code(), Ok(code) if code > 0)
+}
+
+/// Whether the application accepts a TCP connection.
+fn accepts_tcp_connection(host: &str, port: &str) -> bool {
+    match format!("{}:{}", host, port).to_socket_addrs() {
+        Ok(addresses) => addresses
+            .into_iter()
+            .any(|address| TcpStream::connect_timeout(&address, PROBE_TIMEOUT).is_ok()),
+        Err(_) => false,
+    }
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::readiness::{
+        accepts_tcp_connection, is_ready, preflight, Backoff, ReadinessProbe,
+    };
+    use crate::error::ToolsetError::BenchmarkTargetUnreachable;
+    use std::net::TcpListener;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    #[test]
+    fn it_can_parse_readiness_probes() {
+        assert_eq!(ReadinessProbe::from_str("url"), Ok(ReadinessProbe::TestUrl));
+        assert_eq!(ReadinessProbe::from_str("tcp"), Ok(ReadinessProbe::Tcp));
+        assert_eq!(
+            ReadinessProbe::from_str("/health"),
+            Ok(ReadinessProbe::Path("/health".to_string()))
+        );
+        assert!(ReadinessProbe::from_str("health").is_err());
+    }
+
+    #[test]
+    fn it_can_probe_a_tcp_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port().to_string();
+        assert!(is_ready(
+            &ReadinessProbe::Tcp,
+            false,
+            "127.0.0.1",
+            &port,
+            "/"
+        ));
+
+        drop(listener);
+        assert!(!is_ready(
+            &ReadinessProbe::Tcp,
+            false,
+            "127.0.0.1",
+            &port,
+            "/"
+        ));
+    }
+
+    #[test]
+    fn it_fails_preflight_once_retries_are_exhausted() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port().to_string();
+        let connects = || Ok(accepts_tcp_connection("127.0.0.1", &port));
+        assert!(preflight("127.0.0.1", &port, 0, Duration::from_millis(0), connects).is_ok());
+
+        drop(listener);
+        match preflight("127.0.0.1", &port, 2, Duration::from_millis(0), connects) {
+            Err(BenchmarkTargetUnreachable { attempts, .. }) => assert_eq!(attempts, 3),
+            result => panic!("expected BenchmarkTargetUnreachable; got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn it_backs_off_exponentially_up_to_its_cap() {
+        let backoff = Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_millis(1000),
+            timeout: Duration::from_secs(10),
+        };
+        for (attempt, delay) in [(0, 100), (1, 200), (3, 800), (4, 1000), (40, 1000)] {
+            let jittered = backoff.delay(attempt);
+            assert!(
+                jittered >= Duration::from_millis(delay / 2),
+                "{:?}",
+                jittered
+            );
+            assert!(jittered <= Duration::from_millis(delay), "{:?}", jittered);
+        }
+    }
+}
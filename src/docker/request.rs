@@ -0,0 +1,185 @@
+//! Requests made directly to the Docker Engine API, for the endpoints and
+//! options which `dockurl` does not support.
+
+use crate::docker::docker_config::DockerTls;
+use crate::docker::listener::body::Body;
+use crate::error::ToolsetError::DockerRequestFailed;
+use crate::error::ToolsetResult;
+use curl::easy::{Easy2, Handler, List};
+use serde_json::Value;
+use std::time::Duration;
+
+/// The method of a request to the Docker daemon.
+pub enum Method {
+    Get,
+    /// A POST with an optional JSON body.
+    Post(Option<Value>),
+    /// A POST of the given tar archive; e.g. the context of a build.
+    PostArchive(Vec<u8>),
+}
+
+/// Makes a request to `path` (e.g. `/images/<id>/json`) of the Docker daemon
+/// on `docker_host`, over TLS with the certificates of `tls` if given and
+/// bounded by `timeout` if given, and returns the body of the response. A response whose status is not a success is returned as a
+/// `DockerRequestFailed` carrying its status code and error message.
+pub fn docker_request(
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+    docker_host: &str,
+    method: Method,
+    path: &str,
+    timeout: Option<Duration>,
+) -> ToolsetResult<Vec<u8>> {
+    let request = match method {
+        Method::Get => format!("GET {}", path),
+        _ => format!("POST {}", path),
+    };
+    let mut easy = docker_stream(
+        use_unix_socket,
+        tls,
+        docker_host,
+        method,
+        path,
+        timeout,
+        Body::default(),
+    )?;
+
+    let code = easy.response_code()?;
+    check_response(&request, code, std::mem::take(&mut easy.get_mut().body))
+}
+
+/// Makes a request like `docker_request`, but hands the response to `handler`
+/// as it arrives; e.g. the progress of a build. The status of the response is
+/// not checked, as only `handler` knows the error a streamed response
+/// carries; both are read from the returned handle.
+pub fn docker_stream<H: Handler>(
+    use_unix_socket: bool,
+    tls: Option<&DockerTls>,
+    docker_host: &str,
+    method: Method,
+    path: &str,
+    timeout: Option<Duration>,
+    handler: H,
+) -> ToolsetResult<Easy2<H>> {
+    let mut easy = Easy2::new(handler);
+    if use_unix_socket {
+        easy.unix_socket("/var/run/docker.sock")?;
+    }
+    match method {
+        Method::Get => easy.get(true)?,
+        Method::Post(json) => {
+            let json = json.map(|json| json.to_string()).unwrap_or_default();
+            post(&mut easy, "application/json", json.as_bytes())?;
+        }
+        Method::PostArchive(archive) => post(&mut easy, "application/x-tar", &archive)?,
+    }
+    let scheme = match tls {
+        Some(tls) => {
+            easy.cainfo(&tls.ca_cert)?;
+            easy.ssl_cert(&tls.cert)?;
+            easy.ssl_key(&tls.key)?;
+            "https"
+        }
+        None => "http",
+    };
+    easy.url(&format!("{}://{}{}", scheme, docker_host, path))?;
+    if let Some(timeout) = timeout {
+        easy.timeout(timeout)?;
+    }
+    easy.perform()?;
+
+    Ok(easy)
+}
+
+/// Percent-encodes the given `value` for use in a query string; e.g. the JSON
+/// `filters` of a list request.
+pub fn encode_query<T: ToString>(value: T) -> String {
+    curl::easy::Easy::new().url_encode(value.to_string().as_bytes())
+}
+
+//
+// PRIVATES
+//
+
+/// Makes `easy` a POST of the given `body` of `content_type`.
+fn post<H: Handler>(easy: &mut Easy2<H>, content_type: &str, body: &[u8]) -> ToolsetResult<()> {
+    let mut headers = List::new();
+    headers.append(&format!("Content-Type: {}", content_type))?;
+    easy.post(true)?;
+    easy.http_headers(headers)?;
+    easy.post_field_size(body.len() as u64)?;
+    easy.post_fields_copy(body)?;
+
+    Ok(())
+}
+
+/// Gets the `body` of a response to `request`, or the error Docker responded
+/// with should its status `code` not be a success.
+fn check_response(request: &str, code: u32, body: Vec<u8>) -> ToolsetResult<Vec<u8>> {
+    if (200..300).contains(&code) {
+        return Ok(body);
+    }
+    let message = match serde_json::from_slice::<Value>(&body) {
+        Ok(error) => error["message"].as_str().map(str::to_string),
+        Err(_) => None,
+    };
+
+    Err(DockerRequestFailed {
+        request: request.to_string(),
+        code,
+        message: message.unwrap_or_else(|| String::from_utf8_lossy(&body).to_string()),
+    })
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::request::{check_response, encode_query};
+    use crate::error::ToolsetError::DockerRequestFailed;
+    use serde_json::json;
+
+    #[test]
+    fn it_returns_the_body_of_a_successful_response() {
+        let body = br#"[{"Id":"aaaaaaaaaaaa1111"}]"#.to_vec();
+        assert_eq!(
+            check_response("GET /containers/json", 200, body.clone()).unwrap(),
+            body
+        );
+        assert!(check_response("POST /containers/abc/stop", 204, Vec::new()).is_ok());
+    }
+
+    #[test]
+    fn it_fails_with_the_error_docker_responded_with() {
+        let error = br#"{"message":"No such image: tfb.test.gemini"}"#.to_vec();
+        match check_response("GET /images/tfb.test.gemini/json", 404, error) {
+            Err(DockerRequestFailed {
+                request,
+                code,
+                message,
+            }) => {
+                assert_eq!(request, "GET /images/tfb.test.gemini/json");
+                assert_eq!(code, 404);
+                assert_eq!(message, "No such image: tfb.test.gemini");
+            }
+            _ => panic!("expected DockerRequestFailed"),
+        }
+
+        match check_response("GET /_ping", 500, b"Internal Server Error".to_vec()) {
+            Err(DockerRequestFailed { message, .. }) => {
+                assert_eq!(message, "Internal Server Error")
+            }
+            _ => panic!("expected DockerRequestFailed"),
+        }
+    }
+
+    #[test]
+    fn it_encodes_query_values() {
+        assert_eq!(
+            encode_query(json!({ "ancestor": ["abc"] })),
+            "%7B%22ancestor%22%3A%5B%22abc%22%5D%7D"
+        );
+    }
+}
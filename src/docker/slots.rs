@@ -0,0 +1,82 @@
+//! Limits how many container groups (an application container and the
+//! containers run against it) may run at once, so that runs cannot exhaust
+//! the memory or file descriptors of a host, nor flood a busy daemon with
+//! container creation.
+
+use std::sync::{Arc, Condvar, Mutex};
+
+/// A counting semaphore of container slots. `acquire` blocks until a slot is
+/// free; the slot is released once its `ContainerSlot` is dropped.
+#[derive(Clone, Debug)]
+pub struct ContainerSlots {
+    max: usize,
+    running: Arc<(Mutex<usize>, Condvar)>,
+}
+impl ContainerSlots {
+    /// Creates slots for at most `max` container groups; at least one.
+    pub fn new(max: usize) -> Self {
+        Self {
+            max: max.max(1),
+            running: Arc::new((Mutex::new(0), Condvar::new())),
+        }
+    }
+
+    /// Blocks the current thread until a slot is free and takes it.
+    pub fn acquire(&self) -> ContainerSlot {
+        let (running, released) = &*self.running;
+        let mut running = running.lock().unwrap();
+        while *running >= self.max {
+            running = released.wait(running).unwrap();
+        }
+        *running += 1;
+
+        ContainerSlot {
+            running: Arc::clone(&self.running),
+        }
+    }
+}
+
+/// A slot taken from `ContainerSlots`, released when dropped.
+#[derive(Debug)]
+pub struct ContainerSlot {
+    running: Arc<(Mutex<usize>, Condvar)>,
+}
+impl Drop for ContainerSlot {
+    fn drop(&mut self) {
+        let (running, released) = &*self.running;
+        if let Ok(mut running) = running.lock() {
+            *running -= 1;
+            released.notify_one();
+        }
+    }
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::slots::ContainerSlots;
+    use std::sync::mpsc::channel;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn it_blocks_until_a_slot_is_released() {
+        let slots = ContainerSlots::new(1);
+        let slot = slots.acquire();
+
+        let (sender, receiver) = channel();
+        let waiting = slots.clone();
+        let handle = thread::spawn(move || {
+            let _slot = waiting.acquire();
+            sender.send(()).unwrap();
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+
+        drop(slot);
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+        handle.join().unwrap();
+    }
+}
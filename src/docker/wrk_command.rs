@@ -0,0 +1,323 @@
+//! Rewrites the `wrk` commands retrieved from the verifier so that the
+//! duration, thread count, and connection count of a benchmark can be changed
+//! (e.g. for quick iteration) without rebuilding the verifier image.
+
+use crate::docker::BenchmarkCommands;
+
+/// The `wrk` flags which take a value as the following argument; their values
+/// must be skipped so that they are not mistaken for flags themselves.
+const FLAGS_WITH_VALUES: &[&str] = &[
+    "-c",
+    "--connections",
+    "-d",
+    "--duration",
+    "-t",
+    "--threads",
+    "-s",
+    "--script",
+    "-H",
+    "--header",
+    "--timeout",
+    "-R",
+    "--rate",
+];
+
+/// The test types whose benchmarks sweep the number of queries made per
+/// request, which ends the URL of each command, rather than the number of
+/// connections.
+const QUERY_TEST_TYPES: &[&str] = &["query", "update", "cached_query", "cached-query"];
+
+/// Gets the level which the `wrk` `command` benchmarks for `test_type`; the
+/// number of queries per request for the query test types, or else the number
+/// of connections.
+pub fn benchmark_level(test_type: &str, command: &[String]) -> Option<u32> {
+    if QUERY_TEST_TYPES.contains(&test_type) {
+        let url = command
+            .iter()
+            .find(|arg| arg.starts_with("http://") || arg.starts_with("https://"))?;
+        let digits = url.len() - url.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+
+        return url[url.len() - digits..].parse::<u32>().ok();
+    }
+
+    let mut args = command.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "-c" | "--connections" => args.next().map(String::as_str),
+            _ => arg
+                .strip_prefix("--connections=")
+                .or_else(|| arg.strip_prefix("-c").filter(|value| !value.is_empty())),
+        };
+        if let Some(value) = value {
+            return value.parse::<u32>().ok();
+        }
+    }
+
+    None
+}
+
+/// Overrides for the flags of the `wrk` commands run by the benchmarker.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WrkOverrides {
+    /// Replaces `-d`/`--duration`, in seconds.
+    pub duration: Option<u32>,
+    /// Replaces `-t`/`--threads`.
+    pub threads: Option<u32>,
+    /// Replaces `-c`/`--connections`. Note that this flattens a concurrency
+    /// sweep to the same number of connections for every level.
+    pub connections: Option<u32>,
+}
+impl WrkOverrides {
+    /// Applies these overrides to every command of the given `commands`.
+    pub fn apply(&self, commands: BenchmarkCommands) -> BenchmarkCommands {
+        if *self == WrkOverrides::default() {
+            return commands;
+        }
+
+        BenchmarkCommands {
+            primer_command: self.rewrite(&commands.primer_command),
+            warmup_command: self.rewrite(&commands.warmup_command),
+            benchmark_commands: commands
+                .benchmark_commands
+                .iter()
+                .map(|command| self.rewrite(command))
+                .collect(),
+        }
+    }
+}
+
+//
+// PRIVATES
+//
+impl WrkOverrides {
+    /// Rewrites the overridden flags of `command`, keeping the form in which
+    /// each was given (`-d 15`, `-d15`, `--duration 15`, or `--duration=15`).
+    /// Every other argument is passed through untouched.
+    fn rewrite(&self, command: &[String]) -> Vec<String> {
+        let mut rewritten = Vec::with_capacity(command.len());
+        let mut args = command.iter();
+        while let Some(arg) = args.next() {
+            if FLAGS_WITH_VALUES.contains(&arg.as_str()) {
+                rewritten.push(arg.clone());
+                if let Some(value) = args.next() {
+                    rewritten.push(match self.value_for(arg) {
+                        Some(value) => value.to_string(),
+                        None => value.clone(),
+                    });
+                }
+            } else if let Some(flag) = long_flag_with_value(arg) {
+                rewritten.push(match self.value_for(flag) {
+                    Some(value) => format!("{}={}", flag, value),
+                    None => arg.clone(),
+                });
+            } else if let Some(flag) = short_flag_with_value(arg) {
+                rewritten.push(match self.value_for(flag) {
+                    Some(value) => format!("{}{}", flag, value),
+                    None => arg.clone(),
+                });
+            } else {
+                rewritten.push(arg.clone());
+            }
+        }
+
+        rewritten
+    }
+
+    /// Gets the overridden value of the given `flag`, if any.
+    fn value_for(&self, flag: &str) -> Option<u32> {
+        match flag {
+            "-d" | "--duration" => self.duration,
+            "-t" | "--threads" => self.threads,
+            "-c" | "--connections" => self.connections,
+            _ => None,
+        }
+    }
+}
+
+/// Gets the flag of a long flag given with its value; e.g. `--duration=15`.
+fn long_flag_with_value(arg: &str) -> Option<&str> {
+    if !arg.starts_with("--") {
+        return None;
+    }
+    let index = arg.find('=')?;
+
+    Some(&arg[..index])
+}
+
+/// Gets the flag of a short flag given with its value attached; e.g. `-d15`.
+fn short_flag_with_value(arg: &str) -> Option<&str> {
+    if arg.starts_with("--") || !arg.starts_with('-') {
+        return None;
+    }
+    let flag = arg.get(..2)?;
+    if arg.len() > 2 && FLAGS_WITH_VALUES.contains(&flag) {
+        Some(flag)
+    } else {
+        None
+    }
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::wrk_command::{benchmark_level, WrkOverrides};
+    use crate::docker::BenchmarkCommands;
+
+    fn command(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn it_rewrites_only_the_overridden_flags() {
+        let overrides = WrkOverrides {
+            duration: Some(5),
+            threads: None,
+            connections: Some(64),
+        };
+        let rewritten = overrides.rewrite(&command(&[
+            "wrk",
+            "-H",
+            "Host: tfb-server",
+            "--latency",
+            "-d",
+            "15",
+            "-c",
+            "512",
+            "--timeout",
+            "8",
+            "-t",
+            "4",
+            "http://tfb-server:8080/json",
+        ]));
+
+        assert_eq!(
+            rewritten,
+            command(&[
+                "wrk",
+                "-H",
+                "Host: tfb-server",
+                "--latency",
+                "-d",
+                "5",
+                "-c",
+                "64",
+                "--timeout",
+                "8",
+                "-t",
+                "4",
+                "http://tfb-server:8080/json",
+            ])
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_form_of_each_flag() {
+        let overrides = WrkOverrides {
+            duration: Some(5),
+            threads: Some(2),
+            connections: Some(64),
+        };
+        let rewritten = overrides.rewrite(&command(&[
+            "wrk",
+            "-d15",
+            "--threads=8",
+            "--connections",
+            "512",
+        ]));
+
+        assert_eq!(
+            rewritten,
+            command(&["wrk", "-d5", "--threads=2", "--connections", "64"])
+        );
+    }
+
+    #[test]
+    fn it_passes_unknown_flags_and_flag_values_through() {
+        let overrides = WrkOverrides {
+            duration: Some(5),
+            threads: Some(2),
+            connections: Some(64),
+        };
+        let args = command(&[
+            "wrk",
+            "--unknown",
+            "-x",
+            "--other=value",
+            "-H",
+            "-d 15",
+            "-s",
+            "pipeline.lua",
+            "http://tfb-server:8080/plaintext",
+        ]);
+
+        assert_eq!(overrides.rewrite(&args), args);
+    }
+
+    #[test]
+    fn it_applies_overrides_to_every_command() {
+        let overrides = WrkOverrides {
+            duration: Some(5),
+            threads: None,
+            connections: None,
+        };
+        let commands = overrides.apply(BenchmarkCommands {
+            primer_command: command(&["wrk", "-d", "5"]),
+            warmup_command: command(&["wrk", "-d", "15"]),
+            benchmark_commands: vec![command(&["wrk", "-d", "15", "-c", "16"])],
+        });
+
+        assert_eq!(commands.warmup_command, command(&["wrk", "-d", "5"]));
+        assert_eq!(
+            commands.benchmark_commands,
+            vec![command(&["wrk", "-d", "5", "-c", "16"])]
+        );
+    }
+
+    #[test]
+    fn it_gets_the_level_each_command_benchmarks() {
+        let json = |connections: &str| {
+            command(&[
+                "wrk",
+                "-H",
+                "Host: tfb-server",
+                "-d",
+                "15",
+                "-c",
+                connections,
+                "http://tfb-server:8080/json",
+            ])
+        };
+        assert_eq!(benchmark_level("json", &json("16")), Some(16));
+        assert_eq!(benchmark_level("json", &json("512")), Some(512));
+        assert_eq!(
+            benchmark_level("plaintext", &command(&["wrk", "--connections=4096"])),
+            Some(4096)
+        );
+        assert_eq!(
+            benchmark_level("json", &command(&["wrk", "-c256"])),
+            Some(256)
+        );
+        assert_eq!(
+            benchmark_level("json", &command(&["wrk", "-d", "15"])),
+            None
+        );
+
+        let query = command(&[
+            "wrk",
+            "-c",
+            "512",
+            "https://tfb-server:8081/queries?queries=20",
+        ]);
+        assert_eq!(benchmark_level("query", &query), Some(20));
+        assert_eq!(
+            benchmark_level(
+                "update",
+                &command(&["wrk", "http://tfb-server:8080/updates"])
+            ),
+            None
+        );
+    }
+}
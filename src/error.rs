@@ -1,4 +1,6 @@
+use crate::docker::ContainerSnapshot;
 use std::io;
+use std::time::Duration;
 
 use thiserror::Error;
 
@@ -36,8 +38,58 @@ pub enum ToolsetError {
     #[error("Invalid FrameworkBenchmarks directory: {0}")]
     InvalidFrameworkBenchmarksDirError(String),
 
-    #[error("Docker Container did not respond")]
-    NoResponseFromDockerContainerError,
+    #[error("Application never became ready to accept requests")]
+    ApplicationNeverReady,
+
+    #[error("Database {database} was not ready after {attempts} attempts")]
+    DatabaseNeverReady { database: String, attempts: u32 },
+
+    #[error("Test {test} has test types given different database hosts ({hosts}); its application container can only reach one")]
+    ConflictingDatabaseHosts { test: String, hosts: String },
+
+    #[error("Bandwidth limits require the bridge network mode")]
+    BandwidthLimitRequiresBridgeNetworkError,
+
+    #[error("Failed to detach stale endpoint {endpoint} from network {network_id}")]
+    FailedToDetachStaleEndpoint {
+        network_id: String,
+        endpoint: String,
+    },
+
+    #[error("Invalid network mode: {value}; expected `bridge` or `host`")]
+    InvalidNetworkMode { value: String },
+
+    #[error("Invalid concurrency level: {value}")]
+    InvalidConcurrencyLevels { value: String },
+
+    #[error("Run exceeded its timeout of {}s", .0.as_secs())]
+    RunTimedOut(Duration),
+
+    #[error("Invalid verifier env file {path} at line {line_number}: {line}")]
+    InvalidVerifierEnvFile {
+        path: String,
+        line_number: usize,
+        line: String,
+    },
+
+    #[error("Memory reservation of {reservation} bytes exceeds the memory limit of {limit} bytes")]
+    MemoryReservationExceedsLimitError { reservation: u64, limit: u64 },
+
+    #[error("Docker responded to {request} with {code}: {message}")]
+    DockerRequestFailed {
+        request: String,
+        code: u32,
+        message: String,
+    },
+
+    #[error("Volume host path does not exist: {0}")]
+    VolumeHostPathNotFoundError(String),
+
+    #[error("Invalid seccomp profile {path}: {reason}")]
+    InvalidSeccompProfileError { path: String, reason: String },
+
+    #[error("Docker rejected the GPU request; is the NVIDIA Container Toolkit installed? {0}")]
+    GpuRequestRejectedError(String),
 
     #[error("Unknown benchmarker mode: {0}")]
     UnknownBenchmarkerModeError(String),
@@ -48,18 +100,63 @@ pub enum ToolsetError {
     #[error("Verification failed")]
     VerificationFailedException,
 
-    #[error("Application server container shut down after start")]
-    AppServerContainerShutDownError,
+    #[error("{failed} preflight checks of the Docker environment failed")]
+    PreflightFailed { failed: usize },
+
+    #[error("Application server container shut down after start; {0}")]
+    AppServerContainerShutDownError(Box<ContainerSnapshot>),
 
-    #[error("Failed to inspect container for port mappings")]
-    ContainerPortMappingInspectionError,
+    #[error("Failed to inspect container for port mappings; {0}")]
+    ContainerPortMappingInspectionError(Box<ContainerSnapshot>),
 
-    #[error("Dockerfile must expose port")]
-    ExposePortError,
+    #[error("Dockerfile must expose port; {0}")]
+    ExposePortError(Box<ContainerSnapshot>),
 
-    #[error("Failed to retrieve benchmark commands")]
-    FailedBenchmarkCommandRetrievalError,
+    #[error("Container {container_id} exposes no ports; add an EXPOSE to its Dockerfile")]
+    NoExposedPorts { container_id: String },
+
+    #[error("TLS test type {test_type} requires the application to expose its TLS port {port}")]
+    TlsPortNotExposedError { test_type: String, port: u16 },
+
+    #[error("Failed to retrieve benchmark commands; {0}")]
+    FailedBenchmarkCommandRetrievalError(Box<ContainerSnapshot>),
 
     #[error("Failed to parse benchmark results")]
     BenchmarkDataParseError,
+
+    #[error("Benchmark client failed to run: {reason}")]
+    BenchmarkClientFailed { reason: String },
+
+    #[error("{error}")]
+    BenchmarkFailedWithLogs {
+        error: Box<ToolsetError>,
+        logs: String,
+    },
+
+    #[error("{errors} socket errors in {total_requests} requests exceeds the maximum ratio of {max_ratio}")]
+    SocketErrorThresholdExceeded {
+        errors: u64,
+        total_requests: u32,
+        max_ratio: f64,
+    },
+
+    #[error("Benchmark target {host}:{port} was unreachable after {attempts} attempts")]
+    BenchmarkTargetUnreachable {
+        host: String,
+        port: String,
+        attempts: u32,
+    },
+}
+impl ToolsetError {
+    /// Attaches the captured output of the benchmark client, if any, to this
+    /// error so that the failure can be diagnosed from it.
+    pub fn with_logs(self, logs: Option<String>) -> Self {
+        match logs {
+            Some(logs) => ToolsetError::BenchmarkFailedWithLogs {
+                error: Box::new(self),
+                logs,
+            },
+            None => self,
+        }
+    }
 }
@@ -0,0 +1,46 @@
+//! Lifecycle hooks allow custom logic to be run at specific points of a run;
+//! e.g. snapshotting disk usage before a container starts, or flushing
+//! metrics before it is torn down.
+
+use crate::docker::listener::benchmarker::BenchmarkResults;
+use crate::docker::Verification;
+use crate::report::TestKey;
+use crate::status::ContainerRole;
+use std::fmt;
+
+/// Callbacks invoked as a run progresses. Every method does nothing by
+/// default, so implementors need only override the points they care about.
+/// Hooks must be `Debug`, as is the `Benchmarker` which holds them.
+pub trait RunHooks: fmt::Debug {
+    /// Called once the container given by `container_id` is created for the
+    /// test given by `test_name`, before it is started.
+    fn on_container_created(
+        &mut self,
+        _role: ContainerRole,
+        _test_name: &str,
+        _container_id: &str,
+    ) {
+    }
+
+    /// Called once the container given by `container_id` is started.
+    fn on_container_started(
+        &mut self,
+        _role: ContainerRole,
+        _test_name: &str,
+        _container_id: &str,
+    ) {
+    }
+
+    /// Called once a test type has been verified, whether or not it passed.
+    fn on_verified(&mut self, _verification: &Verification) {}
+
+    /// Called once every benchmark of the test type given by `test` has
+    /// completed.
+    fn on_benchmark_complete(&mut self, _test: &TestKey, _results: &[BenchmarkResults]) {}
+
+    /// Called before the container given by `container_id` is torn down.
+    fn on_teardown(&mut self, _container_id: &str) {}
+
+    /// Called once the container given by `container_id` has been torn down.
+    fn on_torn_down(&mut self, _container_id: &str) {}
+}
@@ -1,7 +1,9 @@
 use crate::config::{Named, Test};
+use crate::docker::listener::events::DockerEvent;
 use crate::docker::Verification;
 use crate::error::ToolsetError::InvalidFrameworkBenchmarksDirError;
 use crate::error::{ToolsetError, ToolsetResult};
+use crate::manifest::Manifest;
 use crate::metadata;
 use crate::results::Results;
 use chrono::Utc;
@@ -24,6 +26,8 @@ pub struct Logger {
     log_dir: Option<PathBuf>,
     log_file: Option<PathBuf>,
     pub quiet: bool,
+    /// Whether `debug` calls also write to standard out.
+    pub verbose: bool,
 }
 
 impl Logger {
@@ -38,6 +42,7 @@ impl Logger {
             log_dir: None,
             log_file: None,
             quiet: false,
+            verbose: false,
         }
     }
 
@@ -51,6 +56,7 @@ impl Logger {
             log_dir: None,
             log_file: None,
             quiet: false,
+            verbose: false,
         }
     }
 
@@ -66,6 +72,7 @@ impl Logger {
             log_dir: Some(log_dir),
             log_file: None,
             quiet: false,
+            verbose: false,
         }
     }
 
@@ -138,6 +145,17 @@ impl Logger {
         Ok(())
     }
 
+    /// Logs debug output to the given file in the configured `log_dir`, and to
+    /// standard out only when this `Logger` is `verbose`.
+    pub fn debug<T>(&self, text: T) -> ToolsetResult<()>
+    where
+        T: std::fmt::Display,
+    {
+        let mut logger = self.clone();
+        logger.quiet |= !self.verbose;
+        logger.log(text)
+    }
+
     /// Serializes and writes the given `results` to `results.json` in the root
     /// of the current `results` directory.
     pub fn write_results(&self, results: &Results) -> ToolsetResult<()> {
@@ -161,6 +179,32 @@ impl Logger {
         Ok(())
     }
 
+    /// Serializes and writes the given `manifest` to `manifest.json` in the
+    /// root of the current `results` directory.
+    pub fn write_manifest(&self, manifest: &Manifest) -> ToolsetResult<()> {
+        if let Some(results_dir) = &self.results_dir {
+            let mut manifest_file = results_dir.clone();
+            manifest_file.push("manifest.json");
+
+            std::fs::write(manifest_file, serde_json::to_string_pretty(manifest)?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Serializes and writes the given Docker `events` to `docker_events.json`
+    /// in the root of the current `results` directory.
+    pub fn write_docker_events(&self, events: &[DockerEvent]) -> ToolsetResult<()> {
+        if let Some(results_dir) = &self.results_dir {
+            let mut events_file = results_dir.clone();
+            events_file.push("docker_events.json");
+
+            std::fs::write(events_file, serde_json::to_string_pretty(events)?)?;
+        }
+
+        Ok(())
+    }
+
     /// Logs output to standard out and optionally to the given file in the
     /// configured `log_dir`.
     pub fn error<T>(&self, text: T) -> ToolsetResult<()>
@@ -243,12 +287,13 @@ pub fn report_verifications(
     logger.set_log_file("benchmark.txt");
     let mut test_results = HashMap::new();
     for verification in &verifications {
-        if !test_results.contains_key(&verification.test_name) {
+        let test_name = verification.tagged_test_name();
+        if !test_results.contains_key(&test_name) {
             let array: Vec<Verification> = Vec::new();
-            test_results.insert(verification.test_name.clone(), array);
+            test_results.insert(test_name.clone(), array);
         }
         test_results
-            .get_mut(&verification.test_name)
+            .get_mut(&test_name)
             .unwrap()
             .push(verification.clone());
     }
@@ -323,6 +368,7 @@ mod tests {
     use crate::io::print_all_frameworks;
     use crate::io::print_all_tests;
     use crate::io::print_all_tests_with_tag;
+    use crate::io::Logger;
     use crate::metadata::TAG_BROKEN;
 
     #[test]
@@ -356,4 +402,28 @@ mod tests {
             Err(e) => panic!("io::print_all_tests_with_tag failed. error: {:?}", e),
         };
     }
+
+    #[test]
+    fn it_keeps_debug_output_in_the_log_file() {
+        let log_dir = std::env::temp_dir().join(format!("tfb-debug-{}", std::process::id()));
+        std::fs::create_dir_all(&log_dir).unwrap();
+        let mut logger = Logger::in_dir(log_dir.to_str().unwrap());
+        logger.set_log_file("database.txt");
+
+        logger
+            .debug("Database readiness attempt 1 exited with 1")
+            .unwrap();
+        logger.verbose = true;
+        logger
+            .debug("Database readiness attempt 2 exited with 0")
+            .unwrap();
+
+        let logged = std::fs::read_to_string(log_dir.join("database.txt")).unwrap();
+        std::fs::remove_dir_all(&log_dir).unwrap();
+        assert_eq!(
+            logged,
+            "Database readiness attempt 1 exited with 1\nDatabase readiness attempt 2 exited with 0\n"
+        );
+        assert!(!logger.quiet);
+    }
 }
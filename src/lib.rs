@@ -0,0 +1,43 @@
+//! The toolset as a library, for embedders which drive runs from their own
+//! binary and consume the returned `RunReport` rather than the log and
+//! results files.
+//!
+//! A run is configured from the same arguments as the CLI:
+//!
+//! ```no_run
+//! use tfb_toolset::{list_projects_to_run, modes, options, Benchmarker, DockerConfig};
+//!
+//! let matches = options::parse().get_matches_from(vec!["tfb", "--mode", "benchmark"]);
+//! let docker_config = DockerConfig::new(&matches).unwrap();
+//! let projects = list_projects_to_run(&matches);
+//! let mut benchmarker = Benchmarker::new(docker_config, projects, modes::BENCHMARK);
+//! let report = benchmarker.benchmark().unwrap();
+//! println!("{} passed, {} failed", report.passed, report.failed);
+//! ```
+
+mod benchmarker;
+mod cli;
+mod config;
+mod docker;
+mod error;
+mod hooks;
+mod io;
+mod manifest;
+mod metadata;
+pub mod options;
+mod progress;
+mod report;
+mod results;
+mod status;
+
+#[macro_use]
+extern crate lazy_static;
+extern crate regex;
+
+pub use crate::benchmarker::{modes, Benchmarker};
+pub use crate::cli::run;
+pub use crate::docker::docker_config::DockerConfig;
+pub use crate::error::{ToolsetError, ToolsetResult};
+pub use crate::hooks::RunHooks;
+pub use crate::metadata::list_projects_to_run;
+pub use crate::report::{RunReport, TestKey, TestReport};
@@ -0,0 +1,220 @@
+//! The run manifest records which framework / test / test type combinations
+//! passed or failed verification, so that a later run can re-run only the
+//! failures.
+
+use crate::config::{Named, Project};
+use crate::docker::Verification;
+use crate::error::ToolsetResult;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub framework_name: String,
+    pub test_name: String,
+    /// The test type verified; empty when the test failed before any test
+    /// type could be verified (e.g. the container failed to start).
+    pub test_type: String,
+    /// The database the test was run against when sweeping databases.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    pub passed: bool,
+}
+impl ManifestEntry {
+    /// Whether this entry and `other` describe the same test type.
+    fn is_same_test_type(&self, other: &ManifestEntry) -> bool {
+        self.is_same_test(other) && self.test_type == other.test_type
+    }
+
+    /// Whether this entry and `other` describe the same test.
+    fn is_same_test(&self, other: &ManifestEntry) -> bool {
+        self.framework_name == other.framework_name
+            && self.test_name == other.test_name
+            && self.database == other.database
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+impl Manifest {
+    /// Builds the `Manifest` for the given `verifications`.
+    pub fn from_verifications(verifications: &[Verification]) -> Self {
+        Self {
+            entries: verifications
+                .iter()
+                .map(|verification| ManifestEntry {
+                    framework_name: verification.framework_name.clone(),
+                    test_name: verification.test_name.clone(),
+                    test_type: verification.type_name.clone(),
+                    database: verification.database.clone(),
+                    passed: verification.errors.is_empty(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Loads the `Manifest` at the given `path`.
+    pub fn load(path: &Path) -> ToolsetResult<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Gets the entries which failed verification.
+    pub fn failures(&self) -> Vec<&ManifestEntry> {
+        self.entries.iter().filter(|entry| !entry.passed).collect()
+    }
+
+    /// Merges the entries of a subsequent run into this `Manifest`, replacing
+    /// the entries for every test which was re-run.
+    pub fn merge(&mut self, rerun: Manifest) {
+        self.entries.retain(|entry| {
+            !rerun.entries.iter().any(|rerun_entry| {
+                // A test which failed outright (or was previously failed
+                // outright) is replaced wholesale by its re-run entries.
+                if rerun_entry.test_type.is_empty() || entry.test_type.is_empty() {
+                    rerun_entry.is_same_test(entry)
+                } else {
+                    rerun_entry.is_same_test_type(entry)
+                }
+            })
+        });
+        self.entries.extend(rerun.entries);
+    }
+
+    /// Filters the given `projects` down to the tests and test types which
+    /// failed in this `Manifest`.
+    pub fn filter_projects(&self, projects: Vec<Project>) -> Vec<Project> {
+        let failures = self.failures();
+        projects
+            .into_iter()
+            .filter_map(|mut project| {
+                let framework_name = project.framework.get_name();
+                project.tests.retain(|test| {
+                    failures.iter().any(|failure| {
+                        failure.framework_name == framework_name
+                            && failure.test_name == test.get_name()
+                    })
+                });
+                for test in &mut project.tests {
+                    let test_types: Vec<&String> = failures
+                        .iter()
+                        .filter(|failure| {
+                            failure.framework_name == framework_name
+                                && failure.test_name == test.get_name()
+                        })
+                        .map(|failure| &failure.test_type)
+                        .collect();
+                    // An empty test type means the whole test failed.
+                    if !test_types.iter().any(|test_type| test_type.is_empty()) {
+                        test.urls
+                            .retain(|test_type, _| test_types.contains(&test_type));
+                    }
+                }
+
+                if project.tests.is_empty() {
+                    None
+                } else {
+                    Some(project)
+                }
+            })
+            .collect()
+    }
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::listener::verifier::Error;
+    use crate::docker::Verification;
+    use crate::manifest::{Manifest, ManifestEntry};
+
+    fn entry(test_name: &str, test_type: &str, passed: bool) -> ManifestEntry {
+        ManifestEntry {
+            framework_name: "Gemini".to_string(),
+            test_name: test_name.to_string(),
+            test_type: test_type.to_string(),
+            database: None,
+            passed,
+        }
+    }
+
+    #[test]
+    fn it_can_build_a_manifest_from_verifications() {
+        let verifications = vec![
+            Verification {
+                framework_name: "Gemini".to_string(),
+                test_name: "gemini".to_string(),
+                type_name: "json".to_string(),
+                database: None,
+                warnings: Vec::default(),
+                errors: Vec::default(),
+                logs: None,
+            },
+            Verification {
+                framework_name: "Gemini".to_string(),
+                test_name: "gemini".to_string(),
+                type_name: "db".to_string(),
+                database: None,
+                warnings: Vec::default(),
+                errors: vec![Error {
+                    message: "No response".to_string(),
+                    short_message: "Failed to Verify".to_string(),
+                }],
+                logs: None,
+            },
+        ];
+        let manifest = Manifest::from_verifications(&verifications);
+
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.failures(), vec![&entry("gemini", "db", false)]);
+    }
+
+    #[test]
+    fn it_can_merge_a_rerun_manifest() {
+        let mut manifest = Manifest {
+            entries: vec![
+                entry("gemini", "json", true),
+                entry("gemini", "db", false),
+                entry("gemini-mysql", "", false),
+            ],
+        };
+        manifest.merge(Manifest {
+            entries: vec![
+                entry("gemini", "db", true),
+                entry("gemini-mysql", "json", true),
+                entry("gemini-mysql", "db", false),
+            ],
+        });
+
+        assert_eq!(manifest.entries.len(), 4);
+        assert_eq!(
+            manifest.failures(),
+            vec![&entry("gemini-mysql", "db", false)]
+        );
+    }
+
+    #[test]
+    fn it_merges_swept_databases_separately() {
+        let mut postgres = entry("gemini", "db", false);
+        postgres.database = Some("postgres".to_string());
+        let mut mysql = entry("gemini", "db", false);
+        mysql.database = Some("mysql".to_string());
+        let mut manifest = Manifest {
+            entries: vec![postgres.clone(), mysql.clone()],
+        };
+        postgres.passed = true;
+        manifest.merge(Manifest {
+            entries: vec![postgres],
+        });
+
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.failures(), vec![&mysql]);
+    }
+}
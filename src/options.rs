@@ -1,14 +1,23 @@
 use crate::benchmarker::modes;
+use crate::docker::host_config::{GpuDevices, PortRange, RestartPolicy};
+use crate::docker::listener::demultiplexer::AttachStreams;
+use crate::docker::network::parse_network_mode;
+use crate::docker::readiness::ReadinessProbe;
 use clap::{App, Arg};
+use regex::Regex;
+use std::fmt::Display;
+use std::str::FromStr;
 
 /// All the arguments that the CLI accepts.
 pub mod args {
     pub const AUDIT: &str = "Audit";
     pub const CLEAN: &str = "Clean";
     pub const QUIET: &str = "Quiet";
+    pub const VERBOSE: &str = "Verbose";
     pub const RESULTS_NAME: &str = "Results Name";
     pub const RESULTS_ENVIRONMENT: &str = "Results Environment";
     pub const RESULTS_UPLOAD_URI: &str = "Results Upload URI";
+    pub const EXPORT_TFB_RESULTS: &str = "Export TFB Results";
     pub const PARSE_RESULTS: &str = "Parse Results";
     pub const TEST_NAMES: &str = "Test Name(s)";
     pub const TEST_DIRS: &str = "Test Dir(s)";
@@ -17,6 +26,9 @@ pub mod args {
     pub const EXCLUDE: &str = "Exclude";
     pub const TYPES: &str = "Type(s)";
     pub const MODE: &str = "Mode";
+    pub const VERIFY_CONTAINER: &str = "Verify Container";
+    pub const STATUS_FILE: &str = "Status File";
+    pub const SELF_CHECK: &str = "Self Check";
     pub const LIST_FRAMEWORKS: &str = "List Frameworks";
     pub const LIST_TESTS: &str = "List Tests";
     pub const LIST_TESTS_WITH_TAG: &str = "List Tests with Tag";
@@ -26,9 +38,32 @@ pub mod args {
     pub const DOCKER_HOST_DEFAULT: &str = "localhost";
     pub const SERVER_HOST: &str = "Server Host";
     pub const SERVER_HOST_DEFAULT: &str = "tfb-server";
+    pub const SERVER_HOST_OVERRIDES: &str = "Server Host Overrides";
+    pub const SERVER_DOMAIN: &str = "Server Domain";
     pub const DATABASE_DOCKER_HOST: &str = "Database Docker Host";
+    pub const DATABASES: &str = "Databases";
+    pub const DATABASE_VOLUMES: &str = "Database Volumes";
+    pub const VERIFIER_ENV_FILE: &str = "Verifier Env File";
+    pub const VERIFIER_BUILD_CONTEXT: &str = "Verifier Build Context";
+    pub const OMIT_VERIFIER_PLACEHOLDERS: &str = "Omit Verifier Placeholders";
+    pub const GPUS: &str = "GPUs";
+    pub const GPU_CAPABILITIES: &str = "GPU Capabilities";
+    pub const CAP_ADD: &str = "Add Capabilities";
+    pub const CAP_DROP: &str = "Drop Capabilities";
+    pub const SECURITY_OPT: &str = "Security Options";
+    pub const MEMORY_LIMIT: &str = "Memory Limit";
+    pub const MEMORY_RESERVATION: &str = "Memory Reservation";
+    pub const CPU_SHARES: &str = "CPU Shares";
+    pub const BENCHMARKER_CPUSET: &str = "Benchmarker CPU Set";
+    pub const WRK_DURATION: &str = "Wrk Duration";
+    pub const WRK_THREADS: &str = "Wrk Threads";
+    pub const WRK_CONNECTIONS: &str = "Wrk Connections";
+    pub const BENCHMARK_PREFLIGHT: &str = "Benchmark Preflight";
+    pub const PREFLIGHT_RETRIES: &str = "Preflight Retries";
+    pub const PREFLIGHT_RETRY_INTERVAL: &str = "Preflight Retry Interval";
     pub const DATABASE_HOST: &str = "Database Host";
     pub const DATABASE_HOST_DEFAULT: &str = "tfb-database";
+    pub const DATABASE_HOST_OVERRIDES: &str = "Database Host Overrides";
     pub const CLIENT_DOCKER_HOST: &str = "Client Docker Host";
     pub const CLIENT_HOST: &str = "Client Host";
     pub const CLIENT_HOST_DEFAULT: &str = "tfb-client";
@@ -37,7 +72,40 @@ pub mod args {
     pub const QUERY_LEVELS: &str = "Query Levels";
     pub const CACHED_QUERY_LEVELS: &str = "Cached Query Levels";
     pub const NETWORK_MODE: &str = "Network Mode";
+    pub const HOST_PORT_RANGE: &str = "Host Port Range";
+    pub const REUSE_NETWORK: &str = "Reuse Network";
     pub const DOCKER_CLEANUP: &str = "Auto-Clean Docker Containers and Images";
+    pub const KEEP_FAILED: &str = "Keep Failed Containers";
+    pub const CAPTURE_LOGS: &str = "Capture Logs";
+    pub const ATTACH_STREAMS: &str = "Attach Streams";
+    pub const NO_TTY: &str = "No TTY";
+    pub const SKIP_IMAGE_PRUNE: &str = "Skip Pruning Dangling Images";
+    pub const RESTART_POLICY: &str = "Restart Policy";
+    pub const USE_COMMAND_CACHE: &str = "Use Benchmark Command Cache";
+    pub const REFRESH_COMMAND_CACHE: &str = "Refresh Benchmark Command Cache";
+    pub const RERUN_FAILED: &str = "Re-run Failed";
+    pub const STOP_TIMEOUT: &str = "Stop Timeout";
+    pub const STOP_GRACE_PERIOD: &str = "Stop Grace Period";
+    pub const LOG_BUFFER_BYTES: &str = "Log Buffer Bytes";
+    pub const LOG_BUFFER_LINES: &str = "Log Buffer Lines";
+    pub const RUN_TIMEOUT: &str = "Run Timeout";
+    pub const MAX_CONCURRENT: &str = "Max Concurrent";
+    pub const MAX_SOCKET_ERROR_RATIO: &str = "Max Socket Error Ratio";
+    pub const RECORD_DOCKER_EVENTS: &str = "Record Docker Events";
+    pub const READINESS_PROBE: &str = "Readiness Probe";
+    pub const READINESS_INTERVAL: &str = "Readiness Interval";
+    pub const READINESS_TIMEOUT: &str = "Readiness Timeout";
+    pub const PORT_BINDING_RETRIES: &str = "Port Binding Retries";
+    pub const PORT_BINDING_RETRY_INTERVAL: &str = "Port Binding Retry Interval";
+    pub const DATABASE_READINESS_BACKOFF: &str = "Database Readiness Backoff";
+    pub const DATABASE_READINESS_MAX_BACKOFF: &str = "Database Readiness Max Backoff";
+    pub const DATABASE_READINESS_TIMEOUT: &str = "Database Readiness Timeout";
+    pub const BANDWIDTH_LIMIT: &str = "Bandwidth Limit";
+    pub const MTU: &str = "MTU";
+    pub const BENCHMARKER_REPLICAS: &str = "Benchmarker Replicas";
+    pub const TLS_CA_CERT: &str = "TLS CA Certificate";
+    pub const TLS_CERT: &str = "TLS Certificate";
+    pub const TLS_KEY: &str = "TLS Key";
 }
 
 pub mod network_modes {
@@ -77,6 +145,12 @@ pub fn parse<'app>() -> App<'app> {
                 .short('q')
                 .long("quiet")
         )
+        .arg(
+            Arg::new(args::VERBOSE)
+                .about("Also print debug messages to stdout, such as each database readiness attempt")
+                .takes_value(false)
+                .long("verbose")
+        )
         .arg(
             Arg::new(args::RESULTS_NAME)
                 .about(
@@ -98,6 +172,13 @@ pub fn parse<'app>() -> App<'app> {
                 .about("A URI where the in-progress results.json file will be POSTed periodically")
                 .long("results-upload-uri")
         )
+        .arg(
+            Arg::new(args::EXPORT_TFB_RESULTS)
+                .about("Writes the results of a benchmark run to this file in the schema of the official \
+                    results.json, with an entry per concurrency level")
+                .long("export-tfb-results")
+                .takes_value(true)
+        )
         .arg(
             Arg::new(args::PARSE_RESULTS)
                 .about("Parses the results of the given timestamp and merges that with the latest results")
@@ -108,6 +189,113 @@ pub fn parse<'app>() -> App<'app> {
                 .about("Automatically remove containers and images after they have exited")
                 .long("rm")
         )
+        .arg(
+            Arg::new(args::KEEP_FAILED)
+                .about("Do not remove the verifier and application containers of a failed verification")
+                .long("keep-failed")
+        )
+        .arg(
+            Arg::new(args::CAPTURE_LOGS)
+                .about("Include the output of the verifier and benchmark client in verifications and results, \
+                    bounded by the log buffer limits")
+                .long("capture-logs")
+        )
+        .arg(
+            Arg::new(args::ATTACH_STREAMS)
+                .about("The output streams of the application and database containers to capture; `stdout`, \
+                    `stderr`, or `both`. Streams cannot be told apart when a TTY is allocated.")
+                .long("attach-streams")
+                .takes_value(true)
+                .default_value("both")
+                .validator(|streams| streams.parse::<AttachStreams>())
+        )
+        .arg(
+            Arg::new(args::NO_TTY)
+                .about("Do not allocate a TTY for containers, so their output is free of terminal control sequences")
+                .long("no-tty")
+        )
+        .arg(
+            Arg::new(args::STOP_TIMEOUT)
+                .about("The maximum time in seconds to wait for a container to be ready to stop before forcing teardown")
+                .long("stop-timeout")
+                .default_value("60")
+                .validator(validate_number::<u64>)
+        )
+        .arg(
+            Arg::new(args::STOP_GRACE_PERIOD)
+                .about("The time in seconds a container is given to exit cleanly once signalled to stop before \
+                    it is killed, as with `docker stop`; 0 kills containers outright")
+                .long("stop-grace-period")
+                .default_value("10")
+                .validator(validate_number::<u64>)
+        )
+        .arg(
+            Arg::new(args::LOG_BUFFER_BYTES)
+                .about("The maximum bytes of a container's output retained in memory; older output is dropped \
+                    but is still written to the logs")
+                .long("log-buffer-bytes")
+                .default_value("16777216")
+                .validator(validate_number::<usize>)
+        )
+        .arg(
+            Arg::new(args::LOG_BUFFER_LINES)
+                .about("The maximum lines of a container's output retained in memory; older output is dropped \
+                    but is still written to the logs")
+                .long("log-buffer-lines")
+                .default_value("100000")
+                .validator(validate_number::<usize>)
+        )
+        .arg(
+            Arg::new(args::RUN_TIMEOUT)
+                .about("The maximum time in seconds the whole run may take before all containers are torn down")
+                .long("run-timeout")
+                .takes_value(true)
+                .validator(validate_number::<u64>)
+        )
+        .arg(
+            Arg::new(args::MAX_CONCURRENT)
+                .about("The most application containers, with the containers run against them, which may \
+                    run at once; creating more blocks until one is torn down")
+                .long("max-concurrent")
+                .default_value("1")
+                .validator(validate_max_concurrent)
+        )
+        .arg(
+            Arg::new(args::MAX_SOCKET_ERROR_RATIO)
+                .about("Fail a benchmark whose socket errors exceed this fraction of its total requests; e.g. 0.01")
+                .long("max-socket-error-ratio")
+                .takes_value(true)
+                .validator(validate_ratio)
+        )
+        .arg(
+            Arg::new(args::SKIP_IMAGE_PRUNE)
+                .about("Do not prune dangling toolset images when auto-cleaning images")
+                .long("skip-image-prune")
+        )
+        .arg(
+            Arg::new(args::RECORD_DOCKER_EVENTS)
+                .about("Record container die/oom/kill/health events to docker_events.json in the results directory")
+                .long("record-docker-events")
+        )
+        .arg(
+            Arg::new(args::RESTART_POLICY)
+                .about("Restart policy for the application container; e.g. `on-failure:3`. \
+                    Restarting mid-benchmark skews results, so this is for debugging only.")
+                .long("restart-policy")
+                .takes_value(true)
+                .default_value("no")
+                .validator(|policy| policy.parse::<RestartPolicy>())
+        )
+        .arg(
+            Arg::new(args::USE_COMMAND_CACHE)
+                .about("Replay cached benchmark commands instead of retrieving them from the verifier")
+                .long("use-command-cache")
+        )
+        .arg(
+            Arg::new(args::REFRESH_COMMAND_CACHE)
+                .about("Retrieve benchmark commands from the verifier and overwrite any cached commands")
+                .long("refresh-command-cache")
+        )
         // Test options
         .arg(
             Arg::new(args::TEST_NAMES)
@@ -147,6 +335,12 @@ pub fn parse<'app>() -> App<'app> {
                 .takes_value(true)
                 .multiple(true)
         )
+        .arg(
+            Arg::new(args::RERUN_FAILED)
+                .about("Re-run only the tests which failed verification in the given manifest.json")
+                .long("rerun-failed")
+                .takes_value(true)
+        )
         .arg(
             Arg::new(args::TYPES)
                 .about("Which type(s) of tests to run")
@@ -163,6 +357,26 @@ pub fn parse<'app>() -> App<'app> {
                 .takes_value(true)
                 .possible_values(&[modes::BENCHMARK, modes::VERIFY, modes::CICD, modes::DEBUG])
         )
+        .arg(
+            Arg::new(args::VERIFY_CONTAINER)
+                .about("In verify mode, verifies the first selected test against this already running \
+                    application container instead of starting one; the container is left running")
+                .long("verify-container")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new(args::SELF_CHECK)
+                .about("Checks that the Docker environment is fit for a run in the given mode (daemons reachable, \
+                    verifier image available, networks present, kernel limits sufficient) and exits")
+                .long("self-check")
+        )
+        .arg(
+            Arg::new(args::STATUS_FILE)
+                .about("Writes run events (containers started, tests verified, benchmarks completed, etc.) \
+                    to this file as lines of JSON as they happen")
+                .long("status-file")
+                .takes_value(true)
+        )
         .arg(
             Arg::new(args::LIST_FRAMEWORKS)
                 .about("Lists all the known frameworks found in the current dir that can be run")
@@ -191,6 +405,7 @@ pub fn parse<'app>() -> App<'app> {
                 .about("The duration in seconds for which each benchmark should be measured")
                 .long("duration")
                 .default_value("15")
+                .validator(validate_number::<u32>)
         )
         .arg(
             Arg::new(args::SERVER_DOCKER_HOST)
@@ -210,18 +425,220 @@ pub fn parse<'app>() -> App<'app> {
                 .long("client-docker-host")
                 .default_value(args::DOCKER_HOST_DEFAULT)
         )
+        .arg(
+            Arg::new(args::BENCHMARKER_REPLICAS)
+                .about("Hostname/IP for the Docker daemon of each benchmarker client to run concurrently; \
+                    repeat a host to run several clients on it. Defaults to one client on the Client Docker Host")
+                .long("benchmarker-replicas")
+                .takes_value(true)
+                .multiple(true)
+        )
+        .arg(
+            Arg::new(args::TLS_CA_CERT)
+                .about("Path to the CA certificate used to verify remote Docker daemons over TLS; \
+                    defaults to ca.pem in DOCKER_CERT_PATH when DOCKER_TLS_VERIFY is set")
+                .long("tls-ca-cert")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new(args::TLS_CERT)
+                .about("Path to the client certificate used to authenticate with remote Docker daemons over TLS; \
+                    defaults to cert.pem in DOCKER_CERT_PATH when DOCKER_TLS_VERIFY is set")
+                .long("tls-cert")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new(args::TLS_KEY)
+                .about("Path to the client key used to authenticate with remote Docker daemons over TLS; \
+                    defaults to key.pem in DOCKER_CERT_PATH when DOCKER_TLS_VERIFY is set")
+                .long("tls-key")
+                .takes_value(true)
+        )
         .arg(
             Arg::new(args::SERVER_HOST)
                 .about("Hostname/IP for the application server")
                 .long("server-host")
                 .default_value(args::SERVER_HOST_DEFAULT)
         )
+        .arg(
+            Arg::new(args::SERVER_DOMAIN)
+                .about("Domain name for the application container; defaults to the server host")
+                .long("server-domain")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new(args::SERVER_HOST_OVERRIDES)
+                .about("Hostname/IP for the application server for a given test type, given as `test_type=host`")
+                .long("server-host-for")
+                .takes_value(true)
+                .multiple(true)
+                .validator(validate_host_override)
+        )
         .arg(
             Arg::new(args::DATABASE_HOST)
                 .about("Hostname/IP for the database server")
                 .long("database-host")
                 .default_value(args::DATABASE_HOST_DEFAULT)
         )
+        .arg(
+            Arg::new(args::DATABASE_HOST_OVERRIDES)
+                .about("Hostname/IP for the database server for a given test type, given as `test_type=host`; \
+                    e.g. `query=replica.db` and `update=primary.db`. In the host network mode, the test types \
+                    run by one application must share a host")
+                .long("database-host-for")
+                .takes_value(true)
+                .multiple(true)
+                .validator(validate_host_override)
+        )
+        .arg(
+            Arg::new(args::DATABASES)
+                .about("Databases to run each test which uses a database against, in turn; \
+                    defaults to the database configured for each test")
+                .long("databases")
+                .takes_value(true)
+                .multiple(true)
+        )
+        .arg(
+            Arg::new(args::DATABASE_VOLUMES)
+                .about("Bind mounts a host path into the database container, given as `host_path:container_path`; \
+                    e.g. `/mnt/nvme/postgres:/var/lib/postgresql/data`")
+                .long("database-volume")
+                .takes_value(true)
+                .multiple(true)
+                .validator(validate_volume)
+        )
+        .arg(
+            Arg::new(args::VERIFIER_ENV_FILE)
+                .about("A file of `KEY=VALUE` lines to add to the verifier's environment; \
+                    variables set by the toolset take precedence")
+                .long("verifier-env-file")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new(args::VERIFIER_BUILD_CONTEXT)
+                .about("A directory containing a Dockerfile from which to build the verifier image before \
+                    the run, rather than pulling it")
+                .long("verifier-build-context")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::new(args::OMIT_VERIFIER_PLACEHOLDERS)
+                .about("Omit the placeholder PORT, ENDPOINT, and TEST_TYPE env vars when checking databases; \
+                    for verifier images which no longer require them")
+                .long("omit-verifier-placeholders")
+        )
+        .arg(
+            Arg::new(args::GPUS)
+                .about("GPUs to request for the application container: `all`, a count, or `device=<id>,...`; \
+                    requires the NVIDIA Container Toolkit on the server Docker host")
+                .long("gpus")
+                .takes_value(true)
+                .validator(|gpus| gpus.parse::<GpuDevices>())
+        )
+        .arg(
+            Arg::new(args::GPU_CAPABILITIES)
+                .about("Driver capabilities required of the requested GPUs; e.g. `compute` or `utility`")
+                .long("gpu-capabilities")
+                .takes_value(true)
+                .multiple(true)
+                .requires(args::GPUS)
+        )
+        .arg(
+            Arg::new(args::CAP_DROP)
+                .about("Kernel capabilities to drop from the application container; e.g. `ALL`. \
+                    The container is then no longer run privileged")
+                .long("cap-drop")
+                .takes_value(true)
+                .multiple(true)
+        )
+        .arg(
+            Arg::new(args::CAP_ADD)
+                .about("Kernel capabilities to add to the application container after any are dropped; \
+                    e.g. `NET_BIND_SERVICE`. The container is then no longer run privileged")
+                .long("cap-add")
+                .takes_value(true)
+                .multiple(true)
+        )
+        .arg(
+            Arg::new(args::SECURITY_OPT)
+                .about("Security options for the application container; e.g. `seccomp=/path/to/profile.json`. \
+                    The container is then no longer run privileged")
+                .long("security-opt")
+                .takes_value(true)
+                .multiple(true)
+        )
+        .arg(
+            Arg::new(args::MEMORY_LIMIT)
+                .about("Hard memory limit in bytes for the application container; it is killed if it exceeds this")
+                .long("memory-limit")
+                .takes_value(true)
+                .validator(validate_number::<u64>)
+        )
+        .arg(
+            Arg::new(args::MEMORY_RESERVATION)
+                .about("Soft memory limit in bytes for the application container, enforced only when the host \
+                    is short of memory; must not exceed the memory limit")
+                .long("memory-reservation")
+                .takes_value(true)
+                .validator(validate_number::<u64>)
+        )
+        .arg(
+            Arg::new(args::CPU_SHARES)
+                .about("Relative CPU weight of the application container under contention; Docker's default is 1024")
+                .long("cpu-shares")
+                .takes_value(true)
+                .validator(validate_number::<u64>)
+        )
+        .arg(
+            Arg::new(args::BENCHMARKER_CPUSET)
+                .about("The CPUs to pin the benchmarker container (and so its wrk threads) to; e.g. `0-3,8`. \
+                    Keeps the client off the application's cores on a combined host")
+                .long("benchmarker-cpuset")
+                .takes_value(true)
+                .validator(validate_cpuset)
+        )
+        .arg(
+            Arg::new(args::WRK_DURATION)
+                .about("Overrides the duration in seconds of every wrk command retrieved from the verifier")
+                .long("wrk-duration")
+                .takes_value(true)
+                .validator(validate_number::<u32>)
+        )
+        .arg(
+            Arg::new(args::WRK_THREADS)
+                .about("Overrides the thread count of every wrk command retrieved from the verifier")
+                .long("wrk-threads")
+                .takes_value(true)
+                .validator(validate_number::<u32>)
+        )
+        .arg(
+            Arg::new(args::WRK_CONNECTIONS)
+                .about("Overrides the connection count of every wrk command retrieved from the verifier; this \
+                    replaces every concurrency level of a sweep")
+                .long("wrk-connections")
+                .takes_value(true)
+                .validator(validate_number::<u32>)
+        )
+        .arg(
+            Arg::new(args::BENCHMARK_PREFLIGHT)
+                .about("Confirm from each benchmarker host that the application accepts TCP connections before running each wrk command; \
+                    fails the benchmark rather than recording a run against an unreachable target")
+                .long("benchmark-preflight")
+        )
+        .arg(
+            Arg::new(args::PREFLIGHT_RETRIES)
+                .about("The number of times to retry a failed benchmark preflight")
+                .long("preflight-retries")
+                .default_value("5")
+                .validator(validate_number::<u32>)
+        )
+        .arg(
+            Arg::new(args::PREFLIGHT_RETRY_INTERVAL)
+                .about("The interval in milliseconds between benchmark preflight retries")
+                .long("preflight-retry-interval")
+                .default_value("500")
+                .validator(validate_number::<u64>)
+        )
         .arg(
             Arg::new(args::CLIENT_HOST)
                 .about("Hostname/IP for the client server")
@@ -260,27 +677,266 @@ pub fn parse<'app>() -> App<'app> {
                 .multiple(true)
                 .default_values(&["1", "10", "20", "50", "100"])
         )
+        .arg(
+            Arg::new(args::READINESS_PROBE)
+                .about("How to determine the application is ready: `url` requests the test's first URL, \
+                    `tcp` connects to its port, and a path (e.g. `/health`) requests that path")
+                .long("readiness-probe")
+                .takes_value(true)
+                .default_value("url")
+                .validator(|probe| probe.parse::<ReadinessProbe>())
+        )
+        .arg(
+            Arg::new(args::READINESS_INTERVAL)
+                .about("The interval in milliseconds between readiness probes")
+                .long("readiness-interval")
+                .default_value("1000")
+                .validator(validate_number::<u64>)
+        )
+        .arg(
+            Arg::new(args::READINESS_TIMEOUT)
+                .about("The time in seconds to wait for the application to become ready")
+                .long("readiness-timeout")
+                .default_value("60")
+                .validator(validate_number::<u64>)
+        )
+        .arg(
+            Arg::new(args::PORT_BINDING_RETRIES)
+                .about("The number of times to re-inspect the application container for its host port \
+                    binding while Docker is still assigning it")
+                .long("port-binding-retries")
+                .default_value("5")
+                .validator(validate_number::<u32>)
+        )
+        .arg(
+            Arg::new(args::PORT_BINDING_RETRY_INTERVAL)
+                .about("The interval in milliseconds before re-inspecting for a host port binding; \
+                    doubled after each retry")
+                .long("port-binding-retry-interval")
+                .default_value("100")
+                .validator(validate_number::<u64>)
+        )
+        .arg(
+            Arg::new(args::DATABASE_READINESS_BACKOFF)
+                .about("The delay in milliseconds before re-checking a database which is not yet ready; \
+                    doubled, with jitter, after each check")
+                .long("database-readiness-backoff")
+                .default_value("500")
+                .validator(validate_number::<u64>)
+        )
+        .arg(
+            Arg::new(args::DATABASE_READINESS_MAX_BACKOFF)
+                .about("The longest delay in milliseconds between database readiness checks")
+                .long("database-readiness-max-backoff")
+                .default_value("10000")
+                .validator(validate_number::<u64>)
+        )
+        .arg(
+            Arg::new(args::DATABASE_READINESS_TIMEOUT)
+                .about("The time in seconds to wait for the database to become ready")
+                .long("database-readiness-timeout")
+                .default_value("300")
+                .validator(validate_number::<u64>)
+        )
         // Network options
+        .arg(
+            Arg::new(args::BANDWIDTH_LIMIT)
+                .about("Limits the benchmarker's egress bandwidth to the given `tc` rate; e.g. `100mbit`")
+                .long("bandwidth-limit")
+                .takes_value(true)
+                .validator(validate_bandwidth_rate)
+        )
+        .arg(
+            Arg::new(args::MTU)
+                .about("The MTU of the TFBNetwork; only applied when the network is created")
+                .long("mtu")
+                .takes_value(true)
+                .validator(validate_number::<u32>)
+        )
         .arg(
             Arg::new(args::NETWORK_MODE)
-                .about("The network mode with which Docker should be run")
+                .about("The network mode with which Docker should be run; `bridge` or `host`")
                 .long("network-mode")
                 .takes_value(true)
                 .default_value(network_modes::BRIDGE)
-                .possible_values(&[network_modes::BRIDGE, network_modes::HOST])
+                .validator(|mode| parse_network_mode(mode).map(|_| ()).map_err(|e| e.to_string()))
+        )
+        .arg(
+            Arg::new(args::HOST_PORT_RANGE)
+                .about("Publish the ports of the application and database containers within this range \
+                    of host ports, e.g. `30000-30100`, rather than on any ephemeral port")
+                .long("host-port-range")
+                .takes_value(true)
+                .validator(|range| range.parse::<PortRange>())
+        )
+        .arg(
+            Arg::new(args::REUSE_NETWORK)
+                .about("Keep the TFBNetwork of a previous run, detaching any endpoints it left behind")
+                .long("reuse-network")
         )
 }
 
+//
+// PRIVATES
+//
+
+/// Validates that `rate` is a rate understood by `tc`; e.g. `100mbit`.
+fn validate_bandwidth_rate(rate: &str) -> Result<(), String> {
+    lazy_static! {
+        static ref RATE: Regex =
+            Regex::new(r"^[0-9]+(bit|kbit|mbit|gbit|tbit|bps|kbps|mbps|gbps|tbps)$").unwrap();
+    }
+    if RATE.is_match(rate) {
+        Ok(())
+    } else {
+        Err(format!("Invalid tc rate: {}", rate))
+    }
+}
+
+/// Validates that `volume` is of the form `host_path:container_path` where
+/// both paths are absolute.
+fn validate_volume(volume: &str) -> Result<(), String> {
+    match volume.split_once(':') {
+        Some((host_path, container_path))
+            if host_path.starts_with('/') && container_path.starts_with('/') =>
+        {
+            Ok(())
+        }
+        _ => Err(format!(
+            "Volume must be `host_path:container_path` with absolute paths: {}",
+            volume
+        )),
+    }
+}
+
+/// Validates that `value` is a number of type `T`; e.g. a count of retries
+/// or a number of seconds.
+fn validate_number<T: FromStr>(value: &str) -> Result<(), String>
+where
+    T::Err: Display,
+{
+    match str::parse::<T>(value) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}: {}", e, value)),
+    }
+}
+
+/// Validates that `ratio` is a fraction between 0 and 1 inclusive.
+fn validate_ratio(ratio: &str) -> Result<(), String> {
+    match str::parse::<f64>(ratio) {
+        Ok(value) if (0f64..=1f64).contains(&value) => Ok(()),
+        _ => Err(format!("Ratio must be between 0 and 1: {}", ratio)),
+    }
+}
+
+/// Validates that `cpus` is a list of CPUs and CPU ranges; e.g. `0-3,8`.
+fn validate_cpuset(cpus: &str) -> Result<(), String> {
+    let valid = cpus.split(',').all(|cpus| match cpus.split_once('-') {
+        Some((first, last)) => match (str::parse::<u32>(first), str::parse::<u32>(last)) {
+            (Ok(first), Ok(last)) => first <= last,
+            _ => false,
+        },
+        None => str::parse::<u32>(cpus).is_ok(),
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "CPU set must be a list of CPUs or ranges such as `0-3,8`: {}",
+            cpus
+        ))
+    }
+}
+
+/// Validates that `max_concurrent` is a count of at least one.
+fn validate_max_concurrent(max_concurrent: &str) -> Result<(), String> {
+    match str::parse::<usize>(max_concurrent) {
+        Ok(count) if count > 0 => Ok(()),
+        _ => Err(format!(
+            "Max concurrent must be at least 1: {}",
+            max_concurrent
+        )),
+    }
+}
+
+/// Validates that `host_override` is of the form `test_type=host`.
+fn validate_host_override(host_override: &str) -> Result<(), String> {
+    match host_override.split_once('=') {
+        Some((test_type, host)) if !test_type.is_empty() && !host.is_empty() => Ok(()),
+        _ => Err(format!(
+            "Host override must be `test_type=host`: {}",
+            host_override
+        )),
+    }
+}
+
 //
 // TESTS
 //
 
 #[cfg(test)]
 mod tests {
-    use crate::options::parse;
+    use crate::options::{
+        parse, validate_bandwidth_rate, validate_cpuset, validate_host_override,
+        validate_max_concurrent, validate_number, validate_ratio, validate_volume,
+    };
 
     #[test]
     fn it_can_parse_with_no_program_arguments() {
         parse();
     }
+
+    #[test]
+    fn it_can_validate_bandwidth_rates() {
+        assert!(validate_bandwidth_rate("100mbit").is_ok());
+        assert!(validate_bandwidth_rate("1gbit").is_ok());
+        assert!(validate_bandwidth_rate("100").is_err());
+        assert!(validate_bandwidth_rate("fast").is_err());
+    }
+
+    #[test]
+    fn it_can_validate_volumes() {
+        assert!(validate_volume("/mnt/nvme/postgres:/var/lib/postgresql/data").is_ok());
+        assert!(validate_volume("postgres:/var/lib/postgresql/data").is_err());
+        assert!(validate_volume("/mnt/nvme/postgres").is_err());
+    }
+
+    #[test]
+    fn it_can_validate_host_overrides() {
+        assert!(validate_host_override("query=10.0.0.2").is_ok());
+        assert!(validate_host_override("query=").is_err());
+        assert!(validate_host_override("10.0.0.2").is_err());
+    }
+
+    #[test]
+    fn it_can_validate_ratios() {
+        assert!(validate_ratio("0.01").is_ok());
+        assert!(validate_ratio("1").is_ok());
+        assert!(validate_ratio("1.5").is_err());
+        assert!(validate_ratio("some").is_err());
+    }
+
+    #[test]
+    fn it_can_validate_max_concurrent() {
+        assert!(validate_max_concurrent("4").is_ok());
+        assert!(validate_max_concurrent("0").is_err());
+        assert!(validate_max_concurrent("some").is_err());
+    }
+
+    #[test]
+    fn it_can_validate_cpusets() {
+        assert!(validate_cpuset("0-3,8").is_ok());
+        assert!(validate_cpuset("12").is_ok());
+        assert!(validate_cpuset("3-0").is_err());
+        assert!(validate_cpuset("0-3,").is_err());
+        assert!(validate_cpuset("all").is_err());
+    }
+
+    #[test]
+    fn it_can_validate_numbers() {
+        assert!(validate_number::<u64>("30").is_ok());
+        assert!(validate_number::<u32>("-1").is_err());
+        assert!(validate_number::<u64>("10s").is_err());
+        assert!(validate_number::<usize>("").is_err());
+    }
 }
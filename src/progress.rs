@@ -0,0 +1,123 @@
+//! Tracks how far along a run is across all of its frameworks, tests, test
+//! types, and phases, and estimates how long the remainder will take.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Progress through a run made up of `total` units of work; a unit being a
+/// single phase (e.g. verification or benchmarking) of a single test type.
+#[derive(Debug)]
+pub struct Progress {
+    total: usize,
+    completed: usize,
+    started: Instant,
+}
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            completed: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Marks `units` more units of work as completed.
+    pub fn complete(&mut self, units: usize) {
+        self.completed = (self.completed + units).min(self.total);
+    }
+
+    /// The percentage of units completed.
+    pub fn percentage(&self) -> usize {
+        (self.completed * 100)
+            .checked_div(self.total)
+            .unwrap_or(100)
+    }
+
+    /// Estimates the time remaining from the average time taken per unit so
+    /// far; `None` until at least one unit has completed.
+    pub fn eta(&self) -> Option<Duration> {
+        self.eta_after(self.started.elapsed())
+    }
+}
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Progress: {}/{} ({}%)",
+            self.completed,
+            self.total,
+            self.percentage()
+        )?;
+        if let Some(eta) = self.eta() {
+            write!(f, "; ETA {}", format_duration(eta))?;
+        }
+
+        Ok(())
+    }
+}
+
+//
+// PRIVATES
+//
+impl Progress {
+    /// Estimates the time remaining given that `elapsed` has passed so far.
+    fn eta_after(&self, elapsed: Duration) -> Option<Duration> {
+        if self.completed == 0 {
+            return None;
+        }
+        let remaining = (self.total - self.completed) as u32;
+
+        Some(elapsed / self.completed as u32 * remaining)
+    }
+}
+
+/// Formats `duration` as hours, minutes, and seconds; e.g. `1h 2m 3s`.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::progress::{format_duration, Progress};
+    use std::time::Duration;
+
+    #[test]
+    fn it_can_track_progress() {
+        let mut progress = Progress::new(4);
+        assert_eq!(progress.percentage(), 0);
+        assert_eq!(progress.eta_after(Duration::from_secs(10)), None);
+
+        progress.complete(1);
+        assert_eq!(progress.percentage(), 25);
+        assert_eq!(
+            progress.eta_after(Duration::from_secs(10)),
+            Some(Duration::from_secs(30))
+        );
+
+        progress.complete(5);
+        assert_eq!(progress.percentage(), 100);
+        assert_eq!(
+            progress.eta_after(Duration::from_secs(40)),
+            Some(Duration::from_secs(0))
+        );
+    }
+
+    #[test]
+    fn it_can_format_durations() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1h 2m 3s");
+    }
+}
@@ -0,0 +1,238 @@
+//! The run report aggregates everything produced by a run (verifications and
+//! benchmark results for every framework / test / test type) so that the
+//! results can be consumed programmatically rather than from the log and
+//! results files.
+
+use crate::docker::listener::benchmarker::BenchmarkResults;
+use crate::docker::Verification;
+use crate::error::ToolsetError;
+use crate::error::ToolsetError::BenchmarkFailedWithLogs;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Identifies a single test type of a test run during a run.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub struct TestKey {
+    pub framework_name: String,
+    pub test_name: String,
+    /// The test type run; empty when the test failed before any test type
+    /// could be run (e.g. the container failed to start).
+    pub test_type: String,
+    /// The database the test was run against when sweeping databases.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+}
+
+/// The outcome of a single test type.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestReport {
+    #[serde(flatten)]
+    pub key: TestKey,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub benchmarks: Vec<BenchmarkResults>,
+    /// The error which prevented the test type from being run, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The output of the benchmark client which failed, when captured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logs: Option<String>,
+}
+impl TestReport {
+    /// Whether the test type ran and, if verified, passed verification.
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+            && self
+                .verification
+                .iter()
+                .all(|verification| verification.errors.is_empty())
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RunReport {
+    #[serde(serialize_with = "serialize_tests")]
+    pub tests: BTreeMap<TestKey, TestReport>,
+    pub passed: usize,
+    pub failed: usize,
+    pub duration: Duration,
+}
+impl RunReport {
+    /// Whether every test type run passed; a run which ran nothing did not
+    /// succeed.
+    pub fn succeeded(&self) -> bool {
+        !self.tests.is_empty() && self.failed == 0
+    }
+
+    /// Adds the given `verification` of a test type.
+    pub fn add_verification(&mut self, verification: Verification) {
+        self.insert(TestReport {
+            key: TestKey {
+                framework_name: verification.framework_name.clone(),
+                test_name: verification.test_name.clone(),
+                test_type: verification.type_name.clone(),
+                database: verification.database.clone(),
+            },
+            verification: Some(verification),
+            benchmarks: Vec::default(),
+            error: None,
+            logs: None,
+        });
+    }
+
+    /// Adds the benchmark `results` of a test type.
+    pub fn add_benchmarks(&mut self, key: TestKey, results: Vec<BenchmarkResults>) {
+        self.insert(TestReport {
+            key,
+            verification: None,
+            benchmarks: results,
+            error: None,
+            logs: None,
+        });
+    }
+
+    /// Adds the `error` which prevented a test type from being run.
+    pub fn add_error(&mut self, key: TestKey, error: &ToolsetError) {
+        let (error, logs) = match error {
+            BenchmarkFailedWithLogs { error, logs } => (error.as_ref(), Some(logs.clone())),
+            error => (error, None),
+        };
+        self.insert(TestReport {
+            key,
+            verification: None,
+            benchmarks: Vec::default(),
+            error: Some(format!("{:?}", error)),
+            logs,
+        });
+    }
+}
+
+//
+// PRIVATES
+//
+impl RunReport {
+    /// Inserts `report`, replacing any previous report of the same test type.
+    fn insert(&mut self, report: TestReport) {
+        self.count(&report, 1);
+        if let Some(replaced) = self.tests.insert(report.key.clone(), report) {
+            self.count(&replaced, -1);
+        }
+    }
+
+    /// Adjusts the passed or failed count for `report` by `delta`.
+    fn count(&mut self, report: &TestReport, delta: isize) {
+        let count = if report.passed() {
+            &mut self.passed
+        } else {
+            &mut self.failed
+        };
+        *count = (*count as isize + delta) as usize;
+    }
+}
+
+/// Serializes the reports of each test type as a list; JSON objects cannot be
+/// keyed by a `TestKey`, which is instead flattened into each report.
+fn serialize_tests<S: Serializer>(
+    tests: &BTreeMap<TestKey, TestReport>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(tests.values())
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::listener::verifier::Error;
+    use crate::docker::Verification;
+    use crate::error::ToolsetError::{BenchmarkClientFailed, BenchmarkDataParseError};
+    use crate::report::{RunReport, TestKey};
+
+    fn verification(type_name: &str, passed: bool) -> Verification {
+        Verification {
+            framework_name: "Gemini".to_string(),
+            test_name: "gemini".to_string(),
+            type_name: type_name.to_string(),
+            database: None,
+            warnings: Vec::default(),
+            errors: if passed {
+                Vec::default()
+            } else {
+                vec![Error {
+                    message: "No response".to_string(),
+                    short_message: "Failed to Verify".to_string(),
+                }]
+            },
+            logs: None,
+        }
+    }
+
+    fn key(test_type: &str) -> TestKey {
+        TestKey {
+            framework_name: "Gemini".to_string(),
+            test_name: "gemini".to_string(),
+            test_type: test_type.to_string(),
+            database: None,
+        }
+    }
+
+    #[test]
+    fn it_can_count_passes_and_failures() {
+        let mut report = RunReport::default();
+        assert!(!report.succeeded());
+
+        report.add_verification(verification("json", true));
+        report.add_verification(verification("db", false));
+        report.add_error(key("plaintext"), &BenchmarkDataParseError);
+        assert_eq!((report.passed, report.failed), (1, 2));
+        assert!(!report.succeeded());
+        assert!(!report.tests[&key("db")].passed());
+
+        report.add_verification(verification("db", true));
+        report.add_benchmarks(key("plaintext"), Vec::default());
+        assert_eq!((report.passed, report.failed), (3, 0));
+        assert!(report.succeeded());
+    }
+
+    #[test]
+    fn it_serializes_tests_as_a_list() {
+        let mut report = RunReport::default();
+        report.add_verification(verification("json", true));
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["tests"][0]["frameworkName"], "Gemini");
+        assert_eq!(json["tests"][0]["testType"], "json");
+        assert_eq!(json["passed"], 1);
+    }
+
+    #[test]
+    fn it_keeps_the_logs_of_a_failed_benchmark() {
+        let mut report = RunReport::default();
+        let error = BenchmarkClientFailed {
+            reason: "exited with code 1 without any output".to_string(),
+        }
+        .with_logs(Some("unable to connect to tfb-server:8080".to_string()));
+        report.add_error(key("plaintext"), &error);
+
+        let test = &report.tests[&key("plaintext")];
+        assert!(test
+            .error
+            .as_ref()
+            .unwrap()
+            .starts_with("BenchmarkClientFailed"));
+        assert_eq!(
+            test.logs.as_deref(),
+            Some("unable to connect to tfb-server:8080")
+        );
+
+        report.add_error(key("plaintext"), &BenchmarkDataParseError.with_logs(None));
+        assert!(report.tests[&key("plaintext")].logs.is_none());
+    }
+}
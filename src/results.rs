@@ -1,8 +1,11 @@
 use crate::config::Named;
 use crate::docker::docker_config::DockerConfig;
+use crate::docker::listener::benchmarker::{BenchmarkResults, SocketErrors};
+use crate::docker::tag_with_database;
 use crate::error::ToolsetResult;
 use crate::io::get_tfb_dir;
 use crate::metadata::list_all_projects;
+use chrono::{TimeZone, Utc};
 use rand::Rng;
 use serde::Serialize;
 use std::collections::HashMap;
@@ -105,16 +108,8 @@ impl Results {
             .unwrap()
             .as_millis();
         results.duration = docker_config.duration;
-        results.concurrency_levels = docker_config
-            .concurrency_levels
-            .split(',')
-            .map(|l| str::parse::<u32>(l).unwrap())
-            .collect();
-        results.pipeline_concurrency_levels = docker_config
-            .pipeline_concurrency_levels
-            .split(',')
-            .map(|l| str::parse::<u32>(l).unwrap())
-            .collect();
+        results.concurrency_levels = docker_config.concurrency_levels.clone();
+        results.pipeline_concurrency_levels = docker_config.pipeline_concurrency_levels.clone();
         results.cached_query_intervals = docker_config
             .cached_query_levels
             .split(',')
@@ -130,8 +125,63 @@ impl Results {
 
         Ok(results)
     }
+
+    /// Records the successful benchmark of `test_type` of the test named
+    /// `name`, with an entry in `raw_data` for each of its `results`; one per
+    /// concurrency (or query) level.
+    pub fn add_success(
+        &mut self,
+        name: &str,
+        test_type: &str,
+        results: &[BenchmarkResults],
+        database: &Option<String>,
+    ) {
+        let name = self.add_framework(name, database);
+        let raw_data = self
+            .raw_data
+            .entry(test_type.to_string())
+            .or_default()
+            .entry(name.clone())
+            .or_default();
+        raw_data.extend(
+            results
+                .iter()
+                .map(|results| benchmark_data(results, database)),
+        );
+        if let Some(end_time) = results.iter().map(|results| results.end_time).max() {
+            self.completed.insert(
+                name.clone(),
+                Utc.timestamp_millis(end_time as i64)
+                    .format("%Y%m%d%H%M%S")
+                    .to_string(),
+            );
+        }
+        self.succeeded
+            .entry(test_type.to_string())
+            .or_default()
+            .push(name);
+    }
+
+    /// Records the failed benchmark of `test_type` of the test named `name`.
+    pub fn add_failure(&mut self, name: &str, test_type: &str, database: &Option<String>) {
+        let name = self.add_framework(name, database);
+        self.failed
+            .entry(test_type.to_string())
+            .or_default()
+            .push(name);
+    }
+
+    /// Writes these results to `path` in the schema of the official
+    /// `results.json`.
+    pub fn export(&self, path: &str) -> ToolsetResult<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
 }
 
+/// The results of a single concurrency (or query) level of a benchmark. Socket
+/// errors are only present when there were some, as with the output of `wrk`.
 #[derive(Serialize, Clone, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct BenchmarkData {
@@ -139,8 +189,24 @@ pub struct BenchmarkData {
     pub latency_max: String,
     pub latency_stdev: String,
     pub total_requests: u32,
+    /// In seconds since the epoch.
     pub start_time: u128,
+    /// In seconds since the epoch.
     pub end_time: u128,
+    /// The responses which were not 2xx or 3xx.
+    #[serde(rename = "5xx", skip_serializing_if = "Option::is_none")]
+    pub non_2xx_3xx: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u32>,
+    /// The database benchmarked against when sweeping databases.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -232,3 +298,219 @@ pub struct MetaData {
     pub database_os: String,
     pub approach: String,
 }
+
+//
+// PRIVATES
+//
+impl Results {
+    /// Adds the test named `name` to the frameworks run and gets the name,
+    /// tagged with the `database` being swept, under which its outcomes are
+    /// recorded.
+    fn add_framework(&mut self, name: &str, database: &Option<String>) -> String {
+        if !self.frameworks.iter().any(|framework| framework == name) {
+            self.frameworks.push(name.to_string());
+        }
+
+        tag_with_database(name, database)
+    }
+}
+
+impl BenchmarkData {
+    /// Sets each kind of socket error of which there were some.
+    fn set_socket_errors(&mut self, socket_errors: &SocketErrors) {
+        let some = |count: u32| if count > 0 { Some(count) } else { None };
+        self.connect = some(socket_errors.connect);
+        self.read = some(socket_errors.read);
+        self.write = some(socket_errors.write);
+        self.timeout = some(socket_errors.timeout);
+    }
+}
+
+/// Gets the entry of the run of a single concurrency (or query) level given
+/// by `results`.
+fn benchmark_data(results: &BenchmarkResults, database: &Option<String>) -> BenchmarkData {
+    let mut data = BenchmarkData {
+        latency_avg: results.thread_stats.latency.average.clone(),
+        latency_max: results.thread_stats.latency.max.clone(),
+        latency_stdev: results.thread_stats.latency.standard_deviation.clone(),
+        total_requests: results.total_requests,
+        start_time: results.start_time / 1_000,
+        end_time: results.end_time / 1_000,
+        non_2xx_3xx: results.non_2xx_3xx,
+        database: database.clone(),
+        ..BenchmarkData::default()
+    };
+    data.set_socket_errors(&results.socket_errors);
+
+    data
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::listener::benchmarker::tests::results;
+    use crate::docker::listener::benchmarker::SocketErrors;
+    use crate::results::{Git, MetaData, Results};
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    /// An abridged `results.json` as produced by the official toolset.
+    const SAMPLE: &str = r#"{
+        "uuid": "2bc6a4ad-d5c1-4e2b-8c0e-59b8a1d2b3f1",
+        "name": "Continuous Benchmarking Run 2020-07-29 19:33:41",
+        "environmentDescription": "Citrine",
+        "git": {
+            "commitId": "9f6a1c4d1c4e0b3c2a5f4d1e7b6a9c8d7e6f5a4b",
+            "repositoryUrl": "https://github.com/TechEmpower/FrameworkBenchmarks.git",
+            "branchName": "master"
+        },
+        "startTime": 1596051221000,
+        "completionTime": 1596145843000,
+        "duration": 15,
+        "concurrencyLevels": [16, 32, 64, 128, 256, 512],
+        "pipelineConcurrencyLevels": [256, 1024, 4096, 16384],
+        "queryIntervals": [1, 5, 10, 15, 20],
+        "cachedQueryIntervals": [1, 10, 20, 50, 100],
+        "frameworks": ["gemini"],
+        "testMetadata": [{
+            "versus": "servlet",
+            "project_name": "gemini",
+            "display_name": "gemini",
+            "name": "gemini",
+            "classification": "Fullstack",
+            "database": "MySQL",
+            "language": "Java",
+            "os": "Linux",
+            "notes": "",
+            "tags": [],
+            "framework": "Gemini",
+            "webserver": "Resin",
+            "orm": "Micro",
+            "platform": "Servlet",
+            "database_os": "Linux",
+            "approach": "Realistic"
+        }],
+        "rawData": {
+            "json": {
+                "gemini": [
+                    {
+                        "latencyAvg": "1.35ms",
+                        "latencyMax": "14.09ms",
+                        "latencyStdev": "0.00us",
+                        "totalRequests": 2118331,
+                        "startTime": 1596051280,
+                        "endTime": 1596051295
+                    },
+                    {
+                        "latencyAvg": "2.81ms",
+                        "latencyMax": "38.41ms",
+                        "latencyStdev": "0.00us",
+                        "totalRequests": 2213704,
+                        "startTime": 1596051280,
+                        "endTime": 1596051295,
+                        "read": 1,
+                        "timeout": 2
+                    }
+                ]
+            }
+        },
+        "verify": {},
+        "succeeded": { "json": ["gemini"] },
+        "failed": {},
+        "completed": { "gemini": "20200729193455" }
+    }"#;
+
+    /// Gets the results of a run with the settings of `SAMPLE` and nothing
+    /// recorded yet. `Results::default` would read the git metadata of the
+    /// benchmarks dir.
+    fn run() -> Results {
+        Results {
+            uuid: "2bc6a4ad-d5c1-4e2b-8c0e-59b8a1d2b3f1".to_string(),
+            name: "Continuous Benchmarking Run 2020-07-29 19:33:41".to_string(),
+            start_time: 1_596_051_221_000,
+            completion_time: 1_596_145_843_000,
+            duration: 15,
+            test_metadata: vec![MetaData {
+                versus: "servlet".to_string(),
+                project_name: "gemini".to_string(),
+                display_name: "gemini".to_string(),
+                name: "gemini".to_string(),
+                classification: "Fullstack".to_string(),
+                database: "MySQL".to_string(),
+                language: "Java".to_string(),
+                os: "Linux".to_string(),
+                notes: String::default(),
+                tags: Vec::default(),
+                framework: "Gemini".to_string(),
+                webserver: "Resin".to_string(),
+                orm: "Micro".to_string(),
+                platform: "Servlet".to_string(),
+                database_os: "Linux".to_string(),
+                approach: "Realistic".to_string(),
+            }],
+            environment_description: "Citrine".to_string(),
+            git: Git {
+                commit_id: "9f6a1c4d1c4e0b3c2a5f4d1e7b6a9c8d7e6f5a4b".to_string(),
+                repository_url: "https://github.com/TechEmpower/FrameworkBenchmarks.git"
+                    .to_string(),
+                branch_name: "master".to_string(),
+            },
+            query_intervals: vec![1, 5, 10, 15, 20],
+            cached_query_intervals: vec![1, 10, 20, 50, 100],
+            concurrency_levels: vec![16, 32, 64, 128, 256, 512],
+            pipeline_concurrency_levels: vec![256, 1024, 4096, 16384],
+            frameworks: Vec::default(),
+            raw_data: HashMap::new(),
+            verify: HashMap::new(),
+            succeeded: HashMap::new(),
+            failed: HashMap::new(),
+            completed: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn it_exports_the_official_schema() {
+        let level = |total_requests, latency_average, max| {
+            let mut level = results(total_requests, latency_average, max);
+            level.start_time = 1_596_051_280_000;
+            level.end_time = 1_596_051_295_000;
+            level
+        };
+        let mut first = level(2_118_331, "1.35ms", "14.09ms");
+        first.socket_errors = SocketErrors::default();
+        let second = level(2_213_704, "2.81ms", "38.41ms");
+
+        let mut exported = run();
+        exported.add_success("gemini", "json", &[first, second], &None);
+
+        // Parsed from its text, as `serde_json::Value` cannot hold a `u128`.
+        let json: Value = serde_json::from_str(&serde_json::to_string(&exported).unwrap()).unwrap();
+        let sample: Value = serde_json::from_str(SAMPLE).unwrap();
+        assert_eq!(json, sample);
+    }
+
+    #[test]
+    fn it_records_every_outcome_under_the_test_name() {
+        let mut benchmark = results(300_000, "1.00ms", "10.00ms");
+        benchmark.non_2xx_3xx = Some(3);
+        let mysql = Some("mysql".to_string());
+
+        let mut exported = run();
+        exported.add_success("Gemini", "db", &[benchmark], &mysql);
+        exported.add_failure("Gemini", "fortune", &mysql);
+        let json: Value = serde_json::from_str(&serde_json::to_string(&exported).unwrap()).unwrap();
+
+        let raw_data = &json["rawData"]["db"]["Gemini (mysql)"];
+        assert_eq!(raw_data.as_array().unwrap().len(), 1);
+        assert_eq!(raw_data[0]["totalRequests"], 300_000);
+        assert_eq!(raw_data[0]["5xx"], 3);
+        assert_eq!(raw_data[0]["database"], "mysql");
+        assert_eq!(json["succeeded"]["db"][0], "Gemini (mysql)");
+        assert_eq!(json["failed"]["fortune"][0], "Gemini (mysql)");
+        assert_eq!(json["completed"]["Gemini (mysql)"], "19700101000016");
+        assert_eq!(json["frameworks"], serde_json::json!(["Gemini"]));
+    }
+}
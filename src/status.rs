@@ -0,0 +1,196 @@
+//! Structured events describing the progress of a run, pushed to an optional
+//! status channel as they happen so that a run can be followed live (e.g. by
+//! a dashboard) without parsing its logs.
+
+use crate::docker::listener::benchmarker::BenchmarkResults;
+use crate::docker::Verification;
+use crate::error::ToolsetResult;
+use crate::hooks::RunHooks;
+use crate::report::TestKey;
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+use std::thread::JoinHandle;
+
+/// The part a container plays in running a test.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ContainerRole {
+    Application,
+    Database,
+    /// A benchmarker client, or a probe confirming it can reach the
+    /// application.
+    Benchmarker,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum RunEvent {
+    #[serde(rename_all = "camelCase")]
+    ContainerCreated {
+        role: ContainerRole,
+        test_name: String,
+        container_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ContainerStarted {
+        role: ContainerRole,
+        test_name: String,
+        container_id: String,
+    },
+    VerificationPassed {
+        verification: Verification,
+    },
+    VerificationFailed {
+        verification: Verification,
+    },
+    BenchmarkCompleted {
+        test: TestKey,
+        results: Vec<BenchmarkResults>,
+    },
+    #[serde(rename_all = "camelCase")]
+    ContainerTornDown {
+        container_id: String,
+    },
+}
+impl RunEvent {
+    /// Gets the event for the given `verification` having passed or failed.
+    pub fn verified(verification: &Verification) -> Self {
+        let verification = verification.clone();
+        if verification.errors.is_empty() {
+            RunEvent::VerificationPassed { verification }
+        } else {
+            RunEvent::VerificationFailed { verification }
+        }
+    }
+}
+
+/// Sends each point of a run's lifecycle to the status channel as a
+/// `RunEvent`. A receiver which has gone away must not interrupt the run, so
+/// failures to send are ignored.
+impl RunHooks for Sender<RunEvent> {
+    fn on_container_created(&mut self, role: ContainerRole, test_name: &str, container_id: &str) {
+        self.send(RunEvent::ContainerCreated {
+            role,
+            test_name: test_name.to_string(),
+            container_id: container_id.to_string(),
+        })
+        .unwrap_or(());
+    }
+
+    fn on_container_started(&mut self, role: ContainerRole, test_name: &str, container_id: &str) {
+        self.send(RunEvent::ContainerStarted {
+            role,
+            test_name: test_name.to_string(),
+            container_id: container_id.to_string(),
+        })
+        .unwrap_or(());
+    }
+
+    fn on_verified(&mut self, verification: &Verification) {
+        self.send(RunEvent::verified(verification)).unwrap_or(());
+    }
+
+    fn on_benchmark_complete(&mut self, test: &TestKey, results: &[BenchmarkResults]) {
+        self.send(RunEvent::BenchmarkCompleted {
+            test: test.clone(),
+            results: results.to_vec(),
+        })
+        .unwrap_or(());
+    }
+
+    fn on_torn_down(&mut self, container_id: &str) {
+        self.send(RunEvent::ContainerTornDown {
+            container_id: container_id.to_string(),
+        })
+        .unwrap_or(());
+    }
+}
+
+/// Creates a status channel whose events are appended to the file at `path`
+/// as lines of JSON. The events are written in the background until the
+/// sender is dropped; join the returned handle to wait for the last of them.
+pub fn write_run_events(path: &str) -> ToolsetResult<(Sender<RunEvent>, JoinHandle<()>)> {
+    let mut file = File::create(path)?;
+    let (sender, receiver) = channel::<RunEvent>();
+    let handle = thread::spawn(move || {
+        for event in receiver {
+            if let Ok(json) = serde_json::to_string(&event) {
+                if writeln!(file, "{}", json)
+                    .and_then(|_| file.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok((sender, handle))
+}
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use crate::docker::Verification;
+    use crate::hooks::RunHooks;
+    use crate::status::{ContainerRole, RunEvent};
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn it_serializes_events_with_their_name() {
+        let event = RunEvent::ContainerCreated {
+            role: ContainerRole::Application,
+            test_name: "gemini".to_string(),
+            container_id: "abc".to_string(),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+
+        assert_eq!(json["event"], "containerCreated");
+        assert_eq!(json["role"], "application");
+        assert_eq!(json["containerId"], "abc");
+        assert_eq!(
+            serde_json::to_value(ContainerRole::Benchmarker).unwrap(),
+            "benchmarker"
+        );
+    }
+
+    #[test]
+    fn it_distinguishes_passed_and_failed_verifications() {
+        let verification = Verification {
+            framework_name: "Gemini".to_string(),
+            test_name: "gemini".to_string(),
+            type_name: "json".to_string(),
+            database: None,
+            warnings: Vec::default(),
+            errors: Vec::default(),
+            logs: None,
+        };
+
+        match RunEvent::verified(&verification) {
+            RunEvent::VerificationPassed { .. } => {}
+            event => panic!("expected VerificationPassed; got {:?}", event),
+        }
+    }
+
+    #[test]
+    fn it_sends_lifecycle_hooks_as_events() {
+        let (mut sender, receiver) = channel::<RunEvent>();
+        sender.on_container_created(ContainerRole::Database, "gemini", "abc");
+        sender.on_teardown("abc");
+        sender.on_torn_down("abc");
+        drop(sender);
+
+        let events: Vec<RunEvent> = receiver.iter().collect();
+        assert_eq!(events.len(), 2);
+        match &events[1] {
+            RunEvent::ContainerTornDown { container_id } => assert_eq!(container_id, "abc"),
+            event => panic!("expected ContainerTornDown; got {:?}", event),
+        }
+    }
+}